@@ -0,0 +1,2 @@
+-- Per-link redirect counter
+ALTER TABLE urls ADD COLUMN click_count INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,4 @@
+ALTER TABLE urls DROP COLUMN utm_campaign;
+ALTER TABLE urls DROP COLUMN utm_medium;
+ALTER TABLE urls DROP COLUMN utm_source;
+ALTER TABLE urls DROP COLUMN utm_enabled;
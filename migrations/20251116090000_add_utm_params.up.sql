@@ -0,0 +1,5 @@
+-- Opt-in UTM parameters merged into the destination on redirect
+ALTER TABLE urls ADD COLUMN utm_enabled INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE urls ADD COLUMN utm_source TEXT;
+ALTER TABLE urls ADD COLUMN utm_medium TEXT;
+ALTER TABLE urls ADD COLUMN utm_campaign TEXT;
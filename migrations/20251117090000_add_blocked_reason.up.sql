@@ -0,0 +1,2 @@
+-- Legal takedowns: blocked links stay for audit but answer 451
+ALTER TABLE urls ADD COLUMN blocked_reason TEXT;
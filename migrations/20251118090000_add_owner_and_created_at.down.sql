@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS urls_owner_created_idx;
+ALTER TABLE urls DROP COLUMN owner_key_id;
+ALTER TABLE urls DROP COLUMN created_at;
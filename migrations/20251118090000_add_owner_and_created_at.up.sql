@@ -0,0 +1,5 @@
+-- Creation time and the API key that created a link, for incident queries
+ALTER TABLE urls ADD COLUMN created_at DATETIME;
+ALTER TABLE urls ADD COLUMN owner_key_id TEXT;
+UPDATE urls SET created_at = CURRENT_TIMESTAMP WHERE created_at IS NULL;
+CREATE INDEX IF NOT EXISTS urls_owner_created_idx ON urls(owner_key_id, created_at);
@@ -0,0 +1,2 @@
+-- Per-link opt-out of the redirect interstitial page
+ALTER TABLE urls ADD COLUMN skip_interstitial INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS bloom_snapshot_chunks;
+ALTER TABLE bloom_snapshots DROP COLUMN chunk_count;
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS url_metadata_key_value_idx;
+DROP TABLE IF EXISTS url_metadata;
@@ -0,0 +1,6 @@
+-- Wildcard aliases such as `go/*`, tried only after an exact lookup misses
+CREATE TABLE IF NOT EXISTS alias_patterns (
+  id      INTEGER PRIMARY KEY,
+  pattern TEXT NOT NULL UNIQUE,
+  target  TEXT NOT NULL
+);
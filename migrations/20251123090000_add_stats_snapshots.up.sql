@@ -0,0 +1,9 @@
+-- Periodically computed aggregate_stats, read by the stats endpoint
+CREATE TABLE IF NOT EXISTS stats_snapshots (
+  id            INTEGER PRIMARY KEY,
+  taken_at      DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  total_links   INTEGER NOT NULL,
+  total_aliases INTEGER NOT NULL,
+  total_clicks  INTEGER NOT NULL,
+  expired_links INTEGER NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Time of the most recent redirect, set together with click_count
+ALTER TABLE urls ADD COLUMN last_accessed_at DATETIME;
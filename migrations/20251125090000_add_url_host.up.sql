@@ -0,0 +1,17 @@
+-- Destination host, lowercased, for abuse sweeps by domain. New rows get it
+-- from the `url` crate at insert; existing rows are backfilled below.
+ALTER TABLE urls ADD COLUMN host TEXT;
+
+-- Strip scheme, then path/query/fragment, then userinfo and port
+UPDATE urls SET host = substr(url, instr(url, '://') + 3) WHERE instr(url, '://') > 0;
+UPDATE urls SET host = substr(host, 1, instr(host, '/') - 1) WHERE instr(host, '/') > 0;
+UPDATE urls SET host = substr(host, 1, instr(host, '?') - 1) WHERE instr(host, '?') > 0;
+UPDATE urls SET host = substr(host, 1, instr(host, '#') - 1) WHERE instr(host, '#') > 0;
+UPDATE urls SET host = substr(host, instr(host, '@') + 1) WHERE instr(host, '@') > 0;
+UPDATE urls SET host = substr(host, 1, instr(host, ':') - 1)
+  WHERE host NOT LIKE '[%' AND instr(host, ':') > 0;
+UPDATE urls SET host = substr(host, 1, instr(host, ']'))
+  WHERE host LIKE '[%' AND instr(host, ']') > 0;
+UPDATE urls SET host = CASE WHEN host = '' THEN NULL ELSE lower(host) END;
+
+CREATE INDEX IF NOT EXISTS urls_host_idx ON urls (host);
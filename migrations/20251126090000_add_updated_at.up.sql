@@ -0,0 +1,4 @@
+-- Last change that affects how a link redirects (UTM, interstitial opt-out,
+-- blocking, alias retargets); click counting does not touch it
+ALTER TABLE urls ADD COLUMN updated_at DATETIME;
+UPDATE urls SET updated_at = created_at;
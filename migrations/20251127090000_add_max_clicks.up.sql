@@ -0,0 +1,2 @@
+-- Clicks after which a limited-use link stops redirecting; NULL for no limit
+ALTER TABLE urls ADD COLUMN max_clicks INTEGER;
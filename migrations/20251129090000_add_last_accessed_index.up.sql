@@ -0,0 +1,3 @@
+-- Recently used links; never-accessed rows are left out
+CREATE INDEX IF NOT EXISTS urls_last_accessed_idx ON urls (last_accessed_at)
+    WHERE last_accessed_at IS NOT NULL;
@@ -0,0 +1,11 @@
+-- One row per redirect served through resolve_and_track
+CREATE TABLE IF NOT EXISTS click_events (
+  id         INTEGER PRIMARY KEY,
+  url_id     INTEGER NOT NULL REFERENCES urls(id) ON DELETE CASCADE,
+  code       TEXT NOT NULL,
+  referrer   TEXT,
+  user_agent TEXT,
+  clicked_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS click_events_url_clicked_idx ON click_events (url_id, clicked_at);
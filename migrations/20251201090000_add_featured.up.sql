@@ -0,0 +1,4 @@
+-- Links curated for the homepage
+ALTER TABLE urls ADD COLUMN featured INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS urls_featured_idx ON urls (id) WHERE featured = 1;
@@ -0,0 +1,2 @@
+-- Incremental sync reads links in (updated_at, id) order
+CREATE INDEX IF NOT EXISTS urls_updated_idx ON urls (updated_at, id);
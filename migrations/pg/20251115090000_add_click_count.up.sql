@@ -0,0 +1,2 @@
+-- Per-link redirect counter
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS click_count BIGINT NOT NULL DEFAULT 0;
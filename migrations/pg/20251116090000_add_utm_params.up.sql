@@ -0,0 +1,6 @@
+-- Opt-in UTM parameters merged into the destination on redirect
+ALTER TABLE urls
+    ADD COLUMN IF NOT EXISTS utm_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS utm_source TEXT,
+    ADD COLUMN IF NOT EXISTS utm_medium TEXT,
+    ADD COLUMN IF NOT EXISTS utm_campaign TEXT;
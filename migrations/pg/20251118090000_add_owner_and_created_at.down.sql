@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS urls_owner_created_idx;
+ALTER TABLE urls
+    DROP COLUMN IF EXISTS owner_key_id,
+    DROP COLUMN IF EXISTS created_at;
@@ -0,0 +1,5 @@
+-- Creation time and the API key that created a link, for incident queries
+ALTER TABLE urls
+    ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    ADD COLUMN IF NOT EXISTS owner_key_id TEXT;
+CREATE INDEX IF NOT EXISTS urls_owner_created_idx ON urls (owner_key_id, created_at);
@@ -0,0 +1,2 @@
+-- Per-link opt-out of the redirect interstitial page
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS skip_interstitial BOOLEAN NOT NULL DEFAULT FALSE;
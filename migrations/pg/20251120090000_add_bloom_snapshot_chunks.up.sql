@@ -0,0 +1,9 @@
+-- Large snapshots are split across rows; chunk_count = 0 keeps data inline
+ALTER TABLE bloom_snapshots ADD COLUMN IF NOT EXISTS chunk_count INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS bloom_snapshot_chunks (
+  name        TEXT NOT NULL REFERENCES bloom_snapshots(name) ON DELETE CASCADE,
+  chunk_index INTEGER NOT NULL,
+  data        BYTEA NOT NULL,
+  PRIMARY KEY (name, chunk_index)
+);
@@ -0,0 +1,9 @@
+-- Free-form key/value tags attached to links
+CREATE TABLE IF NOT EXISTS url_metadata (
+  url_id BIGINT NOT NULL REFERENCES urls(id) ON DELETE CASCADE,
+  key    TEXT NOT NULL,
+  value  TEXT NOT NULL,
+  PRIMARY KEY (url_id, key)
+);
+
+CREATE INDEX IF NOT EXISTS url_metadata_key_value_idx ON url_metadata (key, value);
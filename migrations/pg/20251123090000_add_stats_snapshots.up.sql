@@ -0,0 +1,9 @@
+-- Periodically computed aggregate_stats, read by the stats endpoint
+CREATE TABLE IF NOT EXISTS stats_snapshots (
+  id            BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+  taken_at      TIMESTAMPTZ NOT NULL DEFAULT now(),
+  total_links   BIGINT NOT NULL,
+  total_aliases BIGINT NOT NULL,
+  total_clicks  BIGINT NOT NULL,
+  expired_links BIGINT NOT NULL
+);
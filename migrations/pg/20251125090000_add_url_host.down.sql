@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS urls_host_idx;
+DROP TRIGGER IF EXISTS urls_set_host ON urls;
+DROP FUNCTION IF EXISTS trg_urls_set_host();
+DROP FUNCTION IF EXISTS url_host(TEXT);
+ALTER TABLE urls DROP COLUMN IF EXISTS host;
@@ -0,0 +1,5 @@
+-- Last change that affects how a link redirects (UTM, interstitial opt-out,
+-- blocking, alias retargets); click counting does not touch it
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS updated_at TIMESTAMPTZ;
+UPDATE urls SET updated_at = created_at;
+ALTER TABLE urls ALTER COLUMN updated_at SET DEFAULT now();
@@ -0,0 +1,2 @@
+-- Never-clicked links by age, for pruning dead links
+CREATE INDEX IF NOT EXISTS urls_unclicked_idx ON urls (created_at, id) WHERE click_count = 0;
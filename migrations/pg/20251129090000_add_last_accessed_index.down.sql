@@ -0,0 +1 @@
+DROP INDEX IF EXISTS urls_last_accessed_idx;
@@ -0,0 +1,11 @@
+-- One row per redirect served through resolve_and_track
+CREATE TABLE IF NOT EXISTS click_events (
+  id         BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+  url_id     BIGINT NOT NULL REFERENCES urls(id) ON DELETE CASCADE,
+  code       TEXT NOT NULL,
+  referrer   TEXT,
+  user_agent TEXT,
+  clicked_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS click_events_url_clicked_idx ON click_events (url_id, clicked_at);
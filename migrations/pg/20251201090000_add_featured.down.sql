@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS urls_featured_idx;
+ALTER TABLE urls DROP COLUMN IF EXISTS featured;
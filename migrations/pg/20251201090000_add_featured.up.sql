@@ -0,0 +1,4 @@
+-- Links curated for the homepage
+ALTER TABLE urls ADD COLUMN IF NOT EXISTS featured BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX IF NOT EXISTS urls_featured_idx ON urls (id) WHERE featured;
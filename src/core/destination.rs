@@ -0,0 +1,110 @@
+//! Destination URL rules shared by the handlers and the database layer.
+//!
+//! Everything that decides what a submitted destination is stored as lives
+//! here, so lookups by URL hash it exactly the way creating a link does.
+
+use crate::configuration::{PrivacySettings, UrlSchemeSettings};
+
+/// Why a destination could not be normalized.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum UrlError {
+    #[error("Wrong number of slashes (separators) in scheme")]
+    SchemeSlashes,
+    #[error("{0}")]
+    Invalid(#[source] url::ParseError),
+    #[error("Unsupported scheme: {0}")]
+    UnsupportedScheme(String),
+    #[error("Unsupported or invalid URL")]
+    Unsupported,
+}
+
+/// Parses and normalizes a URL:
+/// - Enforces http/https schemes
+/// - Removes fragments
+/// - Lowercases host
+/// - Validates proper slashes after scheme using manual parsing
+pub fn normalize_url(raw: &str) -> Result<String, UrlError> {
+    let is_http = raw.starts_with("http://");
+    let is_https = raw.starts_with("https://");
+
+    if is_http || is_https {
+        let scheme_len = if is_http { 7 } else { 8 };
+        if raw[scheme_len..].starts_with('/') {
+            return Err(UrlError::SchemeSlashes);
+        }
+
+        let mut u = url::Url::parse(raw).map_err(UrlError::Invalid)?;
+        u.set_fragment(None);
+
+        if let Some(h) = u.host_str() {
+            let lower = h.to_ascii_lowercase();
+            if lower != h {
+                let _ = u.set_host(Some(&lower));
+            }
+        }
+        return Ok(u.to_string());
+    }
+
+    // If it looks like a URL with a scheme but not http(s), call it what it is: unsupported scheme.
+    if let Some(pos) = raw.find("://") {
+        let scheme = &raw[..pos];
+        return Err(UrlError::UnsupportedScheme(scheme.to_string()));
+    }
+
+    // Explicitly catch http/https missing slashes like "http:example.com".
+    if raw.starts_with("http:") || raw.starts_with("https:") {
+        return Err(UrlError::SchemeSlashes);
+    }
+
+    // Everything else is just not a URL we handle.
+    Err(UrlError::Unsupported)
+}
+
+/// What a submitted destination is stored as: [`normalize_url`], then
+/// [`strip_tracking_params`]. Anything that looks a destination up by URL
+/// goes through this too, so it finds what create stored.
+pub fn canonical_destination(raw: &str, privacy: &PrivacySettings) -> Result<String, UrlError> {
+    Ok(strip_tracking_params(&normalize_url(raw)?, privacy))
+}
+
+/// The scheme of `url` if `settings` refuses it, read the way browsers do:
+/// ignoring case, leading whitespace and control characters, and embedded
+/// tabs and newlines, so `" JaVa\tScript:..."` is still `javascript`.
+pub fn blocked_scheme(url: &str, settings: &UrlSchemeSettings) -> Option<String> {
+    let cleaned: String = url
+        .trim_start_matches(|c: char| c <= ' ')
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .collect();
+    let (scheme, _) = cleaned.split_once(':')?;
+    let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    (valid && settings.is_blocked(scheme)).then(|| scheme.to_ascii_lowercase())
+}
+
+/// Drops the query parameters `settings` marks as tracking, leaving `url`
+/// untouched when it has none or stripping is disabled.
+pub fn strip_tracking_params(url: &str, settings: &PrivacySettings) -> String {
+    if !settings.strip_tracking_params {
+        return url.to_string();
+    }
+    let Ok(mut parsed) = url::Url::parse(url) else {
+        return url.to_string();
+    };
+    let pairs: Vec<(String, String)> = parsed.query_pairs().into_owned().collect();
+    let kept: Vec<_> = pairs
+        .iter()
+        .filter(|(key, _)| !settings.is_tracking_param(key))
+        .collect();
+    if kept.len() == pairs.len() {
+        return url.to_string();
+    }
+    if kept.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    parsed.into()
+}
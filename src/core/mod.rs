@@ -1,2 +1,3 @@
+pub mod destination;
 pub mod extractors;
 pub mod security;
@@ -1,5 +1,6 @@
 pub mod jwt;
 pub mod password;
+pub mod preview;
 
 use hmac::Hmac;
 use sha2::Sha256;
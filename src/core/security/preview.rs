@@ -0,0 +1,115 @@
+//! Signed, expiring preview tokens.
+//!
+//! A token is `<base64url(code)>.<expiry unix secs>.<base64url(mac)>` where
+//! `mac` is HMAC-SHA256 over the first two parts. It reveals a single code and
+//! cannot be altered or extended without the secret.
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{DateTime, Utc};
+use hmac::Mac;
+
+use crate::core::security::HmacSha256;
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum TokenError {
+    #[error("malformed preview token")]
+    Malformed,
+    #[error("invalid preview token signature")]
+    BadSignature,
+    #[error("preview token expired")]
+    Expired,
+}
+
+fn mac(payload: &str, secret: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+/// Issues a token granting a preview of `code` until `expiry`.
+pub fn make_preview_token(code: &str, expiry: DateTime<Utc>, secret: &[u8]) -> String {
+    let payload = format!("{}.{}", URL_SAFE_NO_PAD.encode(code), expiry.timestamp());
+    let signature = URL_SAFE_NO_PAD.encode(mac(&payload, secret).finalize().into_bytes());
+    format!("{payload}.{signature}")
+}
+
+/// Returns the code inside `token` if the signature matches and it has not expired.
+pub fn verify_preview_token(token: &str, secret: &[u8]) -> Result<String, TokenError> {
+    let (payload, signature) = token.rsplit_once('.').ok_or(TokenError::Malformed)?;
+    let (code, expiry) = payload.split_once('.').ok_or(TokenError::Malformed)?;
+
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|_| TokenError::Malformed)?;
+    mac(payload, secret)
+        .verify_slice(&signature)
+        .map_err(|_| TokenError::BadSignature)?;
+
+    let expiry: i64 = expiry.parse().map_err(|_| TokenError::Malformed)?;
+    if Utc::now().timestamp() >= expiry {
+        return Err(TokenError::Expired);
+    }
+
+    let code = URL_SAFE_NO_PAD
+        .decode(code)
+        .map_err(|_| TokenError::Malformed)?;
+    String::from_utf8(code).map_err(|_| TokenError::Malformed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    const SECRET: &[u8] = b"preview-secret";
+
+    #[test]
+    fn round_trips_until_expiry() {
+        let token = make_preview_token("Abc_123", Utc::now() + Duration::hours(1), SECRET);
+        assert_eq!(verify_preview_token(&token, SECRET).unwrap(), "Abc_123");
+
+        let expired = make_preview_token("Abc_123", Utc::now() - Duration::seconds(1), SECRET);
+        assert_eq!(
+            verify_preview_token(&expired, SECRET),
+            Err(TokenError::Expired)
+        );
+    }
+
+    #[test]
+    fn rejects_tampering_and_foreign_secrets() {
+        let expiry = Utc::now() + Duration::hours(1);
+        let token = make_preview_token("Abc123", expiry, SECRET);
+
+        assert_eq!(
+            verify_preview_token(&token, b"other-secret"),
+            Err(TokenError::BadSignature)
+        );
+
+        // Swap in another code while keeping the original signature
+        let signature = token.rsplit('.').next().unwrap();
+        let forged = format!(
+            "{}.{}.{}",
+            URL_SAFE_NO_PAD.encode("Other1"),
+            expiry.timestamp(),
+            signature
+        );
+        assert_eq!(
+            verify_preview_token(&forged, SECRET),
+            Err(TokenError::BadSignature)
+        );
+
+        // Extending the expiry invalidates the signature as well
+        let (code, _) = token.split_once('.').unwrap();
+        let extended = format!("{}.{}.{}", code, expiry.timestamp() + 3600, signature);
+        assert_eq!(
+            verify_preview_token(&extended, SECRET),
+            Err(TokenError::BadSignature)
+        );
+
+        assert_eq!(
+            verify_preview_token("not-a-token", SECRET),
+            Err(TokenError::Malformed)
+        );
+    }
+}
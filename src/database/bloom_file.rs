@@ -0,0 +1,91 @@
+//! Bloom snapshots as standalone files, for backing the filter up apart from
+//! the database; see [`UrlDatabase::export_bloom_to_path`].
+//!
+//! A file is a fixed header followed by the payload exactly as
+//! [`UrlDatabase::save_bloom_snapshot`] stores it, uncompressed:
+//!
+//! | Bytes    | Content                          |
+//! |----------|----------------------------------|
+//! | `0..8`   | magic `USBLOOM\0`                |
+//! | `8..12`  | format version, big-endian `u32` |
+//! | `12..20` | payload length, big-endian `u64` |
+//! | `20..`   | payload                          |
+
+use std::path::Path;
+
+use super::{DatabaseError, UrlDatabase};
+use crate::shortcode::bloom_filter::check_snapshot_payload;
+
+const MAGIC: &[u8; 8] = b"USBLOOM\0";
+
+/// Format version written by [`export`]; [`import`] accepts only this one.
+pub const BLOOM_FILE_VERSION: u32 = 1;
+
+const HEADER_LEN: usize = 20;
+
+fn io(e: std::io::Error) -> DatabaseError {
+    DatabaseError::Io(e.to_string())
+}
+
+fn invalid(path: &Path, reason: impl std::fmt::Display) -> DatabaseError {
+    DatabaseError::InvalidSnapshot(format!("{}: {reason}", path.display()))
+}
+
+pub(super) async fn export<D: UrlDatabase + ?Sized>(
+    db: &D,
+    name: &str,
+    path: &Path,
+) -> Result<u64, DatabaseError> {
+    let payload = db
+        .load_bloom_snapshot(name)
+        .await?
+        .ok_or(DatabaseError::NotFound)?;
+
+    let mut file = Vec::with_capacity(HEADER_LEN + payload.len());
+    file.extend_from_slice(MAGIC);
+    file.extend_from_slice(&BLOOM_FILE_VERSION.to_be_bytes());
+    file.extend_from_slice(&(payload.len() as u64).to_be_bytes());
+    file.extend_from_slice(&payload);
+
+    // Written aside and renamed so a crash never leaves a truncated backup
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    tokio::fs::write(&partial, &file).await.map_err(io)?;
+    tokio::fs::rename(&partial, path).await.map_err(io)?;
+    Ok(payload.len() as u64)
+}
+
+pub(super) async fn import<D: UrlDatabase + ?Sized>(
+    db: &D,
+    name: &str,
+    path: &Path,
+) -> Result<u64, DatabaseError> {
+    let file = tokio::fs::read(path).await.map_err(io)?;
+    let (header, payload) = file
+        .split_at_checked(HEADER_LEN)
+        .ok_or_else(|| invalid(path, "too short for a bloom snapshot header"))?;
+    if &header[..8] != MAGIC {
+        return Err(invalid(path, "not a bloom snapshot file"));
+    }
+    let version = u32::from_be_bytes(header[8..12].try_into().expect("4 bytes"));
+    if version != BLOOM_FILE_VERSION {
+        return Err(invalid(
+            path,
+            format!("format version {version}, expected {BLOOM_FILE_VERSION}"),
+        ));
+    }
+    let len = u64::from_be_bytes(header[12..20].try_into().expect("8 bytes"));
+    if len != payload.len() as u64 {
+        return Err(invalid(
+            path,
+            format!(
+                "header says {len} payload bytes, file has {}",
+                payload.len()
+            ),
+        ));
+    }
+    check_snapshot_payload(payload).map_err(|e| invalid(path, e))?;
+
+    db.save_bloom_snapshot(name, payload).await?;
+    Ok(len)
+}
@@ -0,0 +1,105 @@
+//! Sample data for demos and local development; only built with the `demo`
+//! feature. See [`UrlDatabase::seed_demo_data`].
+
+use rand::Rng;
+
+use super::{DatabaseError, UrlDatabase};
+use crate::generator::{NanoIdEngine, ShortCodeGenerator};
+use crate::models::LinkSettings;
+
+const HOSTS: &[&str] = &[
+    "www.rust-lang.org",
+    "docs.rs",
+    "crates.io",
+    "github.com",
+    "news.ycombinator.com",
+    "en.wikipedia.org",
+];
+
+const PATHS: &[&str] = &["blog", "guides", "releases", "docs", "talks", "jobs"];
+
+const CAMPAIGNS: &[&str] = &["launch", "newsletter", "conference"];
+
+/// Every how many links one gets an alias or metadata.
+const ALIAS_EVERY: usize = 5;
+const METADATA_EVERY: usize = 3;
+
+/// Attempts per link before a code collision gives up.
+const CODE_ATTEMPTS: u32 = 8;
+
+pub(super) async fn seed<D: UrlDatabase + ?Sized>(
+    db: &D,
+    count: usize,
+    force: bool,
+) -> Result<u64, DatabaseError> {
+    if !force && !db.is_empty().await? {
+        return Ok(0);
+    }
+
+    let generator = NanoIdEngine::new(8, None);
+    let mut clicks = Vec::with_capacity(count);
+    let mut created = 0;
+    for i in 0..count {
+        let url = format!(
+            "https://{}/{}/{}",
+            HOSTS[i % HOSTS.len()],
+            PATHS[i / HOSTS.len() % PATHS.len()],
+            i + 1
+        );
+        let alias = (i % ALIAS_EVERY == 0).then(|| format!("demo-{}", i + 1));
+
+        let mut attempt = 0;
+        let outcome = loop {
+            let code = generator
+                .generate_retry(attempt)
+                .map_err(|e| DatabaseError::QueryError(format!("{e:?}")))?;
+            match db
+                .insert_url_with_alias(&code, &url, alias.as_deref(), &LinkSettings::default())
+                .await
+            {
+                Err(DatabaseError::Duplicate) if attempt + 1 < CODE_ATTEMPTS => attempt += 1,
+                result => break result?,
+            }
+        };
+        if !outcome.created {
+            continue;
+        }
+        created += 1;
+
+        if i % METADATA_EVERY == 0 {
+            let campaign = CAMPAIGNS[i / METADATA_EVERY % CAMPAIGNS.len()];
+            db.set_link_metadata(outcome.record.id, "campaign", campaign)
+                .await?;
+        }
+        let count = rand::rng().random_range(0..500u64);
+        if count > 0 {
+            clicks.push((outcome.record.code, count));
+        }
+    }
+
+    db.increment_clicks_batch(&clicks).await?;
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::UrlDatabase;
+    use crate::database::sqlite::test_db;
+
+    #[tokio::test]
+    async fn seed_demo_data_fills_an_empty_database_once() {
+        let db = test_db().await;
+
+        assert_eq!(db.seed_demo_data(12, false).await.unwrap(), 12);
+        assert_eq!(db.seed_demo_data(12, false).await.unwrap(), 0);
+
+        let stats = db.aggregate_stats().await.unwrap();
+        assert_eq!(stats.total_links, 12);
+        assert_eq!(stats.total_aliases, 3);
+        assert_eq!(db.distinct_metadata_keys().await.unwrap(), ["campaign"]);
+        assert_eq!(
+            db.get_url("demo-6").await.unwrap(),
+            "https://en.wikipedia.org/blog/6"
+        );
+    }
+}
@@ -0,0 +1,599 @@
+//! Link lifecycle events for external message queues.
+//!
+//! [`EventingUrlDatabase`] wraps any [`UrlDatabase`] and publishes a
+//! [`LinkEvent`] through an [`EventSink`] after each successful mutation.
+//! Only a no-op and a logging sink ship with the crate; Kafka, NATS or
+//! RabbitMQ publishers implement [`EventSink`] on top of their own clients.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{DatabaseError, EXPORT_PAGE_SIZE, SelfCheckReport, SelfTestReport, UrlDatabase};
+use crate::models::{
+    AggregateStats, CodeStats, ExportedLink, LinkSettings, PatternAlias, RedirectTarget,
+    StorageStats, UpsertResult, UrlRecord, Urls, UtmParams,
+};
+
+/// What happened to a link.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkEventKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// A link lifecycle event.
+#[derive(Clone, Debug, Serialize)]
+pub struct LinkEvent {
+    pub kind: LinkEventKind,
+    /// Row id of the link in `urls`
+    pub link_id: i64,
+    /// The affected code or alias; `None` when the mutation addressed the link by id only
+    pub code: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl LinkEvent {
+    pub fn new(kind: LinkEventKind, link_id: i64, code: Option<String>) -> Self {
+        Self {
+            kind,
+            link_id,
+            code,
+            timestamp: Utc::now(),
+        }
+    }
+}
+
+/// Destination for [`LinkEvent`]s.
+///
+/// `publish` runs on a spawned task, after the database call has already
+/// returned, so a slow or failing broker never delays or fails the mutation.
+/// Events are not guaranteed to arrive in mutation order; use `timestamp` to
+/// order them. Implementations report their own errors.
+#[async_trait]
+pub trait EventSink: Send + Sync + 'static {
+    async fn publish(&self, event: LinkEvent);
+}
+
+/// Discards every event.
+pub struct NoopEventSink;
+
+#[async_trait]
+impl EventSink for NoopEventSink {
+    async fn publish(&self, _event: LinkEvent) {}
+}
+
+/// Writes every event to the `tracing` log at info level.
+pub struct LoggingEventSink;
+
+#[async_trait]
+impl EventSink for LoggingEventSink {
+    async fn publish(&self, event: LinkEvent) {
+        tracing::info!(
+            kind = ?event.kind,
+            link_id = event.link_id,
+            code = event.code.as_deref(),
+            timestamp = %event.timestamp,
+            "link event"
+        );
+    }
+}
+
+/// Publishes a [`LinkEvent`] after every successful mutation of the wrapped
+/// database. Failed mutations publish nothing; reads are forwarded unchanged.
+pub struct EventingUrlDatabase<D, S> {
+    inner: D,
+    sink: Arc<S>,
+}
+
+impl<D: UrlDatabase, S: EventSink> EventingUrlDatabase<D, S> {
+    pub fn new(inner: D, sink: S) -> Self {
+        Self {
+            inner,
+            sink: Arc::new(sink),
+        }
+    }
+
+    fn emit(&self, event: LinkEvent) {
+        let sink = Arc::clone(&self.sink);
+        tokio::spawn(async move { sink.publish(event).await });
+    }
+}
+
+#[async_trait]
+impl<D: UrlDatabase, S: EventSink> UrlDatabase for EventingUrlDatabase<D, S> {
+    async fn insert_url(
+        &self,
+        code: &str,
+        url: &str,
+    ) -> Result<(UpsertResult, Urls), DatabaseError> {
+        let (upsert, urls) = self.inner.insert_url(code, url).await?;
+        if upsert.created {
+            self.emit(LinkEvent::new(
+                LinkEventKind::Created,
+                upsert.id,
+                Some(urls.code.clone()),
+            ));
+        }
+        Ok((upsert, urls))
+    }
+
+    async fn insert_url_with_settings(
+        &self,
+        code: &str,
+        url: &str,
+        settings: &LinkSettings,
+    ) -> Result<(UpsertResult, Urls), DatabaseError> {
+        let (upsert, urls) = self
+            .inner
+            .insert_url_with_settings(code, url, settings)
+            .await?;
+        if upsert.created {
+            self.emit(LinkEvent::new(
+                LinkEventKind::Created,
+                upsert.id,
+                Some(urls.code.clone()),
+            ));
+        }
+        Ok((upsert, urls))
+    }
+
+    async fn insert_alias(&self, alias_code: &str, code_id: i64) -> Result<(), DatabaseError> {
+        self.inner.insert_alias(alias_code, code_id).await?;
+        self.emit(LinkEvent::new(
+            LinkEventKind::Created,
+            code_id,
+            Some(alias_code.to_string()),
+        ));
+        Ok(())
+    }
+
+    async fn get_id_by_url(&self, url: &str) -> Result<Urls, DatabaseError> {
+        self.inner.get_id_by_url(url).await
+    }
+
+    async fn codes_for_urls(
+        &self,
+        urls: &[String],
+    ) -> Result<HashMap<String, Urls>, DatabaseError> {
+        self.inner.codes_for_urls(urls).await
+    }
+
+    async fn alias_counts(&self, codes: &[String]) -> Result<HashMap<String, u64>, DatabaseError> {
+        self.inner.alias_counts(codes).await
+    }
+
+    async fn stats_for_codes(
+        &self,
+        codes: &[String],
+    ) -> Result<HashMap<String, CodeStats>, DatabaseError> {
+        self.inner.stats_for_codes(codes).await
+    }
+
+    async fn next_hop(&self, code: &str) -> Result<Option<String>, DatabaseError> {
+        self.inner.next_hop(code).await
+    }
+
+    async fn get_id_by_code(&self, code: &str) -> Result<i64, DatabaseError> {
+        self.inner.get_id_by_code(code).await
+    }
+
+    async fn get_url(&self, id: &str) -> Result<String, DatabaseError> {
+        self.inner.get_url(id).await
+    }
+
+    async fn get_redirect_target(&self, code: &str) -> Result<RedirectTarget, DatabaseError> {
+        self.inner.get_redirect_target(code).await
+    }
+
+    async fn set_link_utm(&self, url_id: i64, utm: &UtmParams) -> Result<(), DatabaseError> {
+        self.inner.set_link_utm(url_id, utm).await?;
+        self.emit(LinkEvent::new(LinkEventKind::Updated, url_id, None));
+        Ok(())
+    }
+
+    async fn set_max_clicks(
+        &self,
+        url_id: i64,
+        max_clicks: Option<u64>,
+    ) -> Result<(), DatabaseError> {
+        self.inner.set_max_clicks(url_id, max_clicks).await?;
+        self.emit(LinkEvent::new(LinkEventKind::Updated, url_id, None));
+        Ok(())
+    }
+
+    async fn set_skip_interstitial(&self, url_id: i64, skip: bool) -> Result<(), DatabaseError> {
+        self.inner.set_skip_interstitial(url_id, skip).await?;
+        self.emit(LinkEvent::new(LinkEventKind::Updated, url_id, None));
+        Ok(())
+    }
+
+    async fn set_link_metadata(
+        &self,
+        url_id: i64,
+        key: &str,
+        value: &str,
+    ) -> Result<(), DatabaseError> {
+        self.inner.set_link_metadata(url_id, key, value).await?;
+        self.emit(LinkEvent::new(LinkEventKind::Updated, url_id, None));
+        Ok(())
+    }
+
+    async fn distinct_metadata_keys(&self) -> Result<Vec<String>, DatabaseError> {
+        self.inner.distinct_metadata_keys().await
+    }
+
+    async fn distinct_metadata_values(&self, key: &str) -> Result<Vec<String>, DatabaseError> {
+        self.inner.distinct_metadata_values(key).await
+    }
+
+    async fn set_link_owner(&self, url_id: i64, owner_key_id: &str) -> Result<(), DatabaseError> {
+        self.inner.set_link_owner(url_id, owner_key_id).await?;
+        self.emit(LinkEvent::new(LinkEventKind::Updated, url_id, None));
+        Ok(())
+    }
+
+    async fn reassign_owner(&self, from_owner: &str, to_owner: &str) -> Result<u64, DatabaseError> {
+        let mut moved = Vec::new();
+        let mut after_id = 0;
+        loop {
+            let page = self
+                .inner
+                .export_owner_page(from_owner, after_id, EXPORT_PAGE_SIZE)
+                .await?;
+            let Some(last) = page.last() else { break };
+            after_id = last.link.id;
+            moved.extend(page.into_iter().map(|exported| exported.link.id));
+        }
+        let count = self.inner.reassign_owner(from_owner, to_owner).await?;
+        for id in moved {
+            self.emit(LinkEvent::new(LinkEventKind::Updated, id, None));
+        }
+        Ok(count)
+    }
+
+    async fn get_url_admin(&self, code: &str) -> Result<UrlRecord, DatabaseError> {
+        self.inner.get_url_admin(code).await
+    }
+
+    async fn urls_by_owner_between(
+        &self,
+        owner_key_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        self.inner
+            .urls_by_owner_between(owner_key_id, from, to, offset, limit)
+            .await
+    }
+
+    async fn unclicked_urls(
+        &self,
+        older_than: DateTime<Utc>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        self.inner.unclicked_urls(older_than, offset, limit).await
+    }
+
+    async fn changed_since(
+        &self,
+        since: DateTime<Utc>,
+        after_id: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        self.inner.changed_since(since, after_id, limit).await
+    }
+
+    async fn recently_accessed(&self, limit: u64) -> Result<Vec<UrlRecord>, DatabaseError> {
+        self.inner.recently_accessed(limit).await
+    }
+
+    async fn export_owner_page(
+        &self,
+        owner_key_id: &str,
+        after_id: i64,
+        limit: u64,
+    ) -> Result<Vec<ExportedLink>, DatabaseError> {
+        self.inner
+            .export_owner_page(owner_key_id, after_id, limit)
+            .await
+    }
+
+    async fn find_by_host(
+        &self,
+        host: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        self.inner.find_by_host(host, offset, limit).await
+    }
+
+    async fn insert_pattern_alias(
+        &self,
+        pattern: &str,
+        target: &str,
+    ) -> Result<PatternAlias, DatabaseError> {
+        self.inner.insert_pattern_alias(pattern, target).await
+    }
+
+    async fn list_pattern_aliases(&self) -> Result<Vec<PatternAlias>, DatabaseError> {
+        self.inner.list_pattern_aliases().await
+    }
+
+    async fn retarget_alias(&self, alias: &str, new_code: &str) -> Result<(), DatabaseError> {
+        self.inner.retarget_alias(alias, new_code).await?;
+        let link_id = self.inner.get_id_by_code(new_code).await?;
+        self.emit(LinkEvent::new(
+            LinkEventKind::Updated,
+            link_id,
+            Some(alias.to_string()),
+        ));
+        Ok(())
+    }
+
+    async fn swap_codes(&self, code_a: &str, code_b: &str) -> Result<(), DatabaseError> {
+        self.inner.swap_codes(code_a, code_b).await?;
+        for code in [code_a, code_b] {
+            let link_id = self.inner.get_id_by_code(code).await?;
+            self.emit(LinkEvent::new(
+                LinkEventKind::Updated,
+                link_id,
+                Some(code.to_string()),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn block_link(&self, code: &str, reason: &str) -> Result<(), DatabaseError> {
+        self.inner.block_link(code, reason).await?;
+        let link_id = self.inner.get_id_by_code(code).await?;
+        self.emit(LinkEvent::new(
+            LinkEventKind::Updated,
+            link_id,
+            Some(code.to_string()),
+        ));
+        Ok(())
+    }
+
+    async fn unblock_link(&self, code: &str) -> Result<(), DatabaseError> {
+        self.inner.unblock_link(code).await?;
+        let link_id = self.inner.get_id_by_code(code).await?;
+        self.emit(LinkEvent::new(
+            LinkEventKind::Updated,
+            link_id,
+            Some(code.to_string()),
+        ));
+        Ok(())
+    }
+
+    async fn set_featured(&self, code: &str, featured: bool) -> Result<(), DatabaseError> {
+        self.inner.set_featured(code, featured).await?;
+        let link_id = self.inner.get_id_by_code(code).await?;
+        self.emit(LinkEvent::new(
+            LinkEventKind::Updated,
+            link_id,
+            Some(code.to_string()),
+        ));
+        Ok(())
+    }
+
+    async fn list_featured(&self, limit: u64) -> Result<Vec<UrlRecord>, DatabaseError> {
+        self.inner.list_featured(limit).await
+    }
+
+    async fn list_short_codes(
+        &self,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<String>, DatabaseError> {
+        self.inner.list_short_codes(offset, limit).await
+    }
+
+    async fn list_short_codes_after(
+        &self,
+        after: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<String>, DatabaseError> {
+        self.inner.list_short_codes_after(after, limit).await
+    }
+
+    async fn list_short_codes_with_prefix(
+        &self,
+        prefix: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<String>, DatabaseError> {
+        self.inner
+            .list_short_codes_with_prefix(prefix, offset, limit)
+            .await
+    }
+
+    async fn exists_many(&self, codes: &[String]) -> Result<HashMap<String, bool>, DatabaseError> {
+        self.inner.exists_many(codes).await
+    }
+
+    /// Codes are resolved to link ids first so each deletion can be reported;
+    /// codes that vanish in between are deleted but not reported.
+    async fn delete_urls_batch(&self, codes: &[String]) -> Result<u64, DatabaseError> {
+        let mut links = Vec::with_capacity(codes.len());
+        for code in codes {
+            if let Ok(id) = self.inner.get_id_by_code(code).await {
+                links.push((id, code.clone()));
+            }
+        }
+        let deleted = self.inner.delete_urls_batch(codes).await?;
+        for (id, code) in links {
+            self.emit(LinkEvent::new(LinkEventKind::Deleted, id, Some(code)));
+        }
+        Ok(deleted)
+    }
+
+    async fn set_expiry_batch(
+        &self,
+        codes: &[String],
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<u64, DatabaseError> {
+        let updated = self.inner.set_expiry_batch(codes, expires_at).await?;
+        if updated > 0 {
+            for code in codes {
+                if let Ok(id) = self.inner.get_id_by_code(code).await {
+                    self.emit(LinkEvent::new(
+                        LinkEventKind::Updated,
+                        id,
+                        Some(code.clone()),
+                    ));
+                }
+            }
+        }
+        Ok(updated)
+    }
+
+    async fn increment_click_count(&self, code: &str) -> Result<(), DatabaseError> {
+        self.inner.increment_click_count(code).await
+    }
+
+    async fn increment_click_count_by(&self, code: &str, by: u64) -> Result<(), DatabaseError> {
+        self.inner.increment_click_count_by(code, by).await
+    }
+
+    async fn increment_clicks_batch(
+        &self,
+        increments: &[(String, u64)],
+    ) -> Result<(), DatabaseError> {
+        self.inner.increment_clicks_batch(increments).await
+    }
+
+    async fn get_url_and_count(&self, code: &str) -> Result<String, DatabaseError> {
+        self.inner.get_url_and_count(code).await
+    }
+
+    async fn resolve_and_track(
+        &self,
+        code: &str,
+        referrer: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<String, DatabaseError> {
+        self.inner
+            .resolve_and_track(code, referrer, user_agent)
+            .await
+    }
+
+    async fn is_empty(&self) -> Result<bool, DatabaseError> {
+        self.inner.is_empty().await
+    }
+
+    async fn aggregate_stats(&self) -> Result<AggregateStats, DatabaseError> {
+        self.inner.aggregate_stats().await
+    }
+
+    async fn save_stats_snapshot(&self, stats: &AggregateStats) -> Result<(), DatabaseError> {
+        self.inner.save_stats_snapshot(stats).await
+    }
+
+    async fn latest_stats(&self) -> Result<AggregateStats, DatabaseError> {
+        self.inner.latest_stats().await
+    }
+
+    async fn find_hash_collisions(&self) -> Result<Vec<(Vec<u8>, Vec<String>)>, DatabaseError> {
+        self.inner.find_hash_collisions().await
+    }
+
+    async fn load_bloom_snapshot(&self, name: &str) -> Result<Option<Vec<u8>>, DatabaseError> {
+        self.inner.load_bloom_snapshot(name).await
+    }
+
+    async fn save_bloom_snapshot(&self, name: &str, data: &[u8]) -> Result<(), DatabaseError> {
+        self.inner.save_bloom_snapshot(name, data).await
+    }
+
+    async fn prune_bloom_snapshots(
+        &self,
+        keep_latest_per_name: usize,
+    ) -> Result<u64, DatabaseError> {
+        self.inner.prune_bloom_snapshots(keep_latest_per_name).await
+    }
+
+    async fn self_check(&self) -> Result<SelfCheckReport, DatabaseError> {
+        self.inner.self_check().await
+    }
+
+    async fn storage_stats(&self) -> Result<StorageStats, DatabaseError> {
+        self.inner.storage_stats().await
+    }
+
+    /// Runs on the inner database so the throwaway link emits no events.
+    async fn self_test(&self) -> Result<SelfTestReport, DatabaseError> {
+        self.inner.self_test().await
+    }
+
+    async fn flush(&self) -> Result<(), DatabaseError> {
+        self.inner.flush().await
+    }
+
+    async fn close(&self) {
+        self.inner.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::sqlite::test_db;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    struct ChannelSink(mpsc::UnboundedSender<LinkEvent>);
+
+    #[async_trait]
+    impl EventSink for ChannelSink {
+        async fn publish(&self, event: LinkEvent) {
+            let _ = self.0.send(event);
+        }
+    }
+
+    async fn next(rx: &mut mpsc::UnboundedReceiver<LinkEvent>) -> LinkEvent {
+        tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("no event published")
+            .expect("sink dropped")
+    }
+
+    #[tokio::test]
+    async fn successful_mutations_publish_events() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let db = EventingUrlDatabase::new(test_db().await, ChannelSink(tx));
+
+        let (upsert, _) = db
+            .insert_url("Abc1234", "https://example.com/")
+            .await
+            .unwrap();
+        let created = next(&mut rx).await;
+        assert_eq!(created.kind, LinkEventKind::Created);
+        assert_eq!(created.code.as_deref(), Some("Abc1234"));
+        assert_eq!(created.link_id, upsert.id);
+
+        // Re-shortening the same URL is not a new link
+        db.insert_url("Other12", "https://example.com/")
+            .await
+            .unwrap();
+        db.insert_alias("myAlias", upsert.id).await.unwrap();
+        let alias = next(&mut rx).await;
+        assert_eq!(alias.code.as_deref(), Some("myAlias"));
+
+        // A failed mutation publishes nothing
+        assert!(db.insert_alias("myAlias", upsert.id).await.is_err());
+        db.set_link_utm(upsert.id, &UtmParams::default())
+            .await
+            .unwrap();
+        let updated = next(&mut rx).await;
+        assert_eq!(updated.kind, LinkEventKind::Updated);
+        assert_eq!(updated.code, None);
+    }
+}
@@ -45,20 +45,339 @@
 //! # }
 //! ```
 
+use crate::configuration::{CodePrecedence, PrivacySettings};
+use crate::core::destination::canonical_destination;
 use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fmt;
+use std::path::Path;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 
 // module declarations
+pub mod bloom_file;
+pub mod buffered;
+#[cfg(feature = "demo")]
+mod demo;
+pub mod eventing;
 pub mod postgres_sql;
+pub mod read_only;
+pub mod schema;
+pub mod self_test;
 pub mod sqlite;
 
 // Re-exports for convenience
-use crate::models::{UpsertResult, Urls};
+use crate::models::{
+    AggregateStats, AliasOutcome, CodeStats, ExportedLink, InsertMode, InsertOutcome, LinkSettings,
+    PatternAlias, RedirectTarget, StorageStats, UpsertResult, UrlRecord, Urls, UtmParams,
+};
+pub use buffered::BufferedClickDatabase;
+use chrono::{DateTime, Utc};
+pub use eventing::{
+    EventSink, EventingUrlDatabase, LinkEvent, LinkEventKind, LoggingEventSink, NoopEventSink,
+};
 pub use postgres_sql::PostgresUrlDatabase;
+pub use read_only::{ReadOnlySwitch, ReadOnlyUrlDatabase};
+pub use schema::SelfCheckReport;
+pub use self_test::{SelfTestReport, SelfTestStep};
 pub use sqlite::*;
 
 pub const MAX_ALIAS_LENGTH: usize = 64;
 
+/// Longest resolution chain [`UrlDatabase::resolve_chain`] follows.
+pub const MAX_ALIAS_CHAIN_DEPTH: usize = 16;
+
+/// Number of `stats_snapshots` rows kept; older ones are pruned on save.
+pub const STATS_SNAPSHOT_HISTORY: i64 = 1_000;
+
+/// Maximum number of values bound into a single `IN (...)` list.
+///
+/// SQLite builds older than 3.32 cap a statement at 999 bind parameters and
+/// Postgres at 65535, so bulk lookups are split into chunks of this size.
+pub const BIND_CHUNK_SIZE: usize = 500;
+
+/// Default size above which bloom snapshots are stored as multiple chunks.
+pub const DEFAULT_SNAPSHOT_CHUNK_BYTES: usize = 16 * 1024 * 1024;
+
+/// Links fetched per page by [`UrlDatabase::export_owner`].
+pub const EXPORT_PAGE_SIZE: u64 = 500;
+
+/// Default for `pagination.max_offset`; see [`UrlDatabase::list_short_codes`].
+pub const DEFAULT_MAX_OFFSET: u64 = 100_000;
+
+/// Default for `pagination.max_page_size`; see [`UrlDatabase::list_short_codes`].
+pub const DEFAULT_MAX_PAGE_SIZE: u64 = 100;
+
+/// Splits `items` into slices that each fit into one bulk statement.
+pub(crate) fn bind_chunks<T>(items: &[T]) -> std::slice::Chunks<'_, T> {
+    items.chunks(BIND_CHUNK_SIZE)
+}
+
+/// Tables counted by [`UrlDatabase::storage_stats`].
+pub const STORAGE_TABLES: &[&str] = &[
+    "urls",
+    "aliases",
+    "url_metadata",
+    "alias_patterns",
+    "bloom_snapshots",
+    "bloom_snapshot_chunks",
+    "stats_snapshots",
+    "click_events",
+];
+
+/// One `(table, rows)` pair per [`STORAGE_TABLES`] entry, in a single
+/// statement so the counts come from one snapshot.
+pub(crate) fn table_counts_query() -> String {
+    STORAGE_TABLES
+        .iter()
+        .map(|table| format!("SELECT '{table}', COUNT(*) FROM {table}"))
+        .collect::<Vec<_>>()
+        .join(" UNION ALL ")
+}
+
+/// `ORDER BY` key over the `source` column of `all_short_codes` that puts the
+/// preferred side first, so `LIMIT 1` lookups are deterministic.
+pub(crate) fn source_order(precedence: CodePrecedence) -> &'static str {
+    match precedence {
+        CodePrecedence::Primary => "CASE source WHEN 'code' THEN 0 ELSE 1 END",
+        CodePrecedence::Alias => "CASE source WHEN 'alias' THEN 0 ELSE 1 END",
+    }
+}
+
+/// Rank of an `all_short_codes.source` value under `precedence`, lower
+/// first; the Rust-side counterpart of [`source_order`].
+pub(crate) fn source_rank(precedence: CodePrecedence, source: &str) -> u8 {
+    let preferred = match precedence {
+        CodePrecedence::Primary => "code",
+        CodePrecedence::Alias => "alias",
+    };
+    u8::from(source != preferred)
+}
+
+/// Attaches `(target_id, alias)` and `(url_id, key, value)` rows to the
+/// links they belong to. Rows for ids outside `links` are ignored.
+pub(crate) fn assemble_export(
+    links: Vec<UrlRecord>,
+    aliases: Vec<(i64, String)>,
+    metadata: Vec<(i64, String, String)>,
+) -> Vec<ExportedLink> {
+    let mut exported: Vec<ExportedLink> = links
+        .into_iter()
+        .map(|link| ExportedLink {
+            link,
+            aliases: Vec::new(),
+            metadata: Default::default(),
+        })
+        .collect();
+    let index: HashMap<i64, usize> = exported
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (e.link.id, i))
+        .collect();
+    for (id, alias) in aliases {
+        if let Some(&i) = index.get(&id) {
+            exported[i].aliases.push(alias);
+        }
+    }
+    for (id, key, value) in metadata {
+        if let Some(&i) = index.get(&id) {
+            exported[i].metadata.insert(key, value);
+        }
+    }
+    for e in &mut exported {
+        e.aliases.sort();
+    }
+    exported
+}
+
+/// Lowercased host of `url` as stored in `urls.host`; `None` for URLs
+/// without one.
+pub(crate) fn url_host(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url).ok()?;
+    parsed.host_str().map(str::to_ascii_lowercase)
+}
+
+/// Normalizes a [`UrlDatabase::find_by_host`] query into the exact host
+/// and, for `*.host`, the `LIKE` pattern matching its subdomains.
+pub(crate) fn host_filter(host: &str) -> (String, Option<String>) {
+    let host = host.trim().trim_end_matches('.').to_ascii_lowercase();
+    match host.strip_prefix("*.") {
+        Some(domain) => (
+            domain.to_string(),
+            Some(format!("%.{}", escape_like(domain))),
+        ),
+        None => (host, None),
+    }
+}
+
+/// Rejects an `OFFSET` above `max_offset` before it turns into a deep scan;
+/// `0` disables the check.
+pub(crate) fn check_offset(offset: u64, max_offset: u64) -> Result<(), DatabaseError> {
+    if max_offset > 0 && offset > max_offset {
+        return Err(DatabaseError::OffsetTooLarge {
+            offset,
+            max: max_offset,
+        });
+    }
+    Ok(())
+}
+
+/// Rejects a `LIMIT` above `max_page_size` rather than clamping it, so a
+/// caller that stops at the first short page never mistakes a cut page for
+/// the end; `0` disables the check.
+pub(crate) fn check_page_size(limit: u64, max_page_size: u64) -> Result<(), DatabaseError> {
+    if max_page_size > 0 && limit > max_page_size {
+        return Err(DatabaseError::PageTooLarge {
+            limit,
+            max: max_page_size,
+        });
+    }
+    Ok(())
+}
+
+/// Whether `owner` has the shape of an
+/// [`ApiKeyId`](crate::middleware::ApiKeyId): 16 lowercase hex digits.
+pub(crate) fn check_owner_key_id(owner: &str) -> Result<(), DatabaseError> {
+    let valid = owner.len() == 16
+        && owner
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+    if !valid {
+        return Err(DatabaseError::InvalidReference);
+    }
+    Ok(())
+}
+
+/// Maps a failed query to [`DatabaseError::Timeout`] when the pool ran out
+/// of time handing out a connection, [`DatabaseError::QueryError`] otherwise.
+pub(crate) fn query_error(e: sqlx::Error) -> DatabaseError {
+    match e {
+        sqlx::Error::PoolTimedOut => DatabaseError::Timeout,
+        e => DatabaseError::QueryError(e.to_string()),
+    }
+}
+
+/// Refuses a resolved link that may not be served, before any click on it is
+/// recorded.
+pub(crate) fn check_servable(
+    expires_at: Option<DateTime<Utc>>,
+    blocked_reason: Option<String>,
+) -> Result<(), DatabaseError> {
+    if let Some(reason) = blocked_reason {
+        return Err(DatabaseError::Blocked(reason));
+    }
+    if expires_at.is_some_and(|at| at <= Utc::now()) {
+        return Err(DatabaseError::Expired);
+    }
+    Ok(())
+}
+
+/// Escapes `\`, `%` and `_` so `value` matches literally inside a
+/// `LIKE ... ESCAPE '\'` pattern.
+pub(crate) fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// SHA-256 of `url`, as stored in `urls.url_hash`.
+pub(crate) fn sha256_bytes(url: &str) -> [u8; 32] {
+    Sha256::digest(url.as_bytes()).into()
+}
+
+/// Groups `urls` by the `url_hash` they would be stored under, taking each
+/// to its [`canonical_destination`] first. Inputs that fail to normalize are
+/// dropped.
+pub(crate) fn hash_normalized_urls(
+    urls: &[String],
+    privacy: &PrivacySettings,
+) -> HashMap<[u8; 32], Vec<String>> {
+    let mut by_hash: HashMap<[u8; 32], Vec<String>> = HashMap::new();
+    for url in urls {
+        if let Ok(normalized) = canonical_destination(url, privacy) {
+            by_hash
+                .entry(sha256_bytes(&normalized))
+                .or_default()
+                .push(url.clone());
+        }
+    }
+    by_hash
+}
+
+/// The hops of a resolution chain, rejecting revisits and chains longer than
+/// `max_depth`.
+pub(crate) struct AliasChain {
+    hops: Vec<String>,
+    max_depth: usize,
+}
+
+impl AliasChain {
+    pub(crate) fn new(max_depth: usize) -> Self {
+        Self {
+            hops: Vec::new(),
+            max_depth,
+        }
+    }
+
+    pub(crate) fn push(&mut self, code: String) -> Result<(), DatabaseError> {
+        let revisit = self.hops.contains(&code);
+        self.hops.push(code);
+        if revisit || self.hops.len() > self.max_depth {
+            return Err(DatabaseError::CyclicAlias(std::mem::take(&mut self.hops)));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn into_hops(self) -> Vec<String> {
+        self.hops
+    }
+}
+
+/// A migration `migrate` would apply, as reported by `migrate_dry_run`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingMigration {
+    pub version: i64,
+    pub description: String,
+}
+
+impl fmt::Display for PendingMigration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.version, self.description)
+    }
+}
+
+/// Up migrations of `migrator` whose version is not in `applied`.
+pub(crate) fn pending_migrations(
+    migrator: &sqlx::migrate::Migrator,
+    applied: &[i64],
+) -> Vec<PendingMigration> {
+    migrator
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration() && !applied.contains(&m.version))
+        .map(|m| PendingMigration {
+            version: m.version,
+            description: m.description.to_string(),
+        })
+        .collect()
+}
+
+/// Folds `(url_hash, url)` rows, sorted by hash, into one entry per hash.
+pub(crate) fn group_by_hash(rows: Vec<(Vec<u8>, String)>) -> Vec<(Vec<u8>, Vec<String>)> {
+    let mut groups: Vec<(Vec<u8>, Vec<String>)> = Vec::new();
+    for (hash, url) in rows {
+        match groups.last_mut() {
+            Some((last, urls)) if *last == hash => urls.push(url),
+            _ => groups.push((hash, vec![url])),
+        }
+    }
+    groups
+}
+
 /// Database operation errors.
 ///
 /// This enum represents all possible errors that can occur during database operations,
@@ -70,6 +389,45 @@ pub enum DatabaseError {
     MigrationError(String),
     NotFound,
     Duplicate,
+    /// A foreign key points at a row that does not exist, e.g. an alias or
+    /// metadata entry for a stale link id. This is a caller input error.
+    InvalidReference,
+    /// A write was rejected because the database is in read-only
+    /// maintenance mode; see [`ReadOnlyUrlDatabase`].
+    ReadOnly,
+    /// Following a code's resolution chain revisited a code or exceeded
+    /// [`MAX_ALIAS_CHAIN_DEPTH`]; holds the hops walked so far.
+    CyclicAlias(Vec<String>),
+    /// Writing output for the caller failed, e.g. during
+    /// [`UrlDatabase::export_owner`].
+    Io(String),
+    /// A bloom snapshot file has the wrong header, version or length; see
+    /// [`bloom_file`].
+    InvalidSnapshot(String),
+    /// The pool had no free connection within its acquire timeout. Handlers
+    /// answer this with the same 504 as the request timeout layer.
+    Timeout,
+    /// An `OFFSET` listing was asked to skip more rows than
+    /// `pagination.max_offset` allows; page with
+    /// [`UrlDatabase::list_short_codes_after`] instead.
+    OffsetTooLarge {
+        offset: u64,
+        max: u64,
+    },
+    /// A listing was asked for more rows per page than
+    /// `pagination.max_page_size` allows.
+    PageTooLarge {
+        limit: u64,
+        max: u64,
+    },
+    /// The link has used up its `max_clicks`; see
+    /// [`UrlDatabase::get_url_and_count`].
+    QuotaExceeded,
+    /// The link's `expires_at` has passed; see [`UrlDatabase::resolve_and_track`].
+    Expired,
+    /// The link is blocked, with the recorded reason; see
+    /// [`UrlDatabase::resolve_and_track`].
+    Blocked(String),
 }
 
 impl fmt::Display for DatabaseError {
@@ -79,6 +437,25 @@ impl fmt::Display for DatabaseError {
             DatabaseError::QueryError(msg) => write!(f, "Database query error: {}", msg),
             DatabaseError::NotFound => write!(f, "Record not found"),
             DatabaseError::Duplicate => write!(f, "Duplicate record"),
+            DatabaseError::InvalidReference => write!(f, "Referenced record does not exist"),
+            DatabaseError::ReadOnly => write!(f, "Database is in read-only mode"),
+            DatabaseError::Io(msg) => write!(f, "Writing output failed: {}", msg),
+            DatabaseError::InvalidSnapshot(msg) => write!(f, "Invalid bloom snapshot: {}", msg),
+            DatabaseError::Timeout => write!(f, "Database did not respond in time"),
+            DatabaseError::QuotaExceeded => write!(f, "Link has reached its click limit"),
+            DatabaseError::Expired => write!(f, "Link has expired"),
+            DatabaseError::Blocked(reason) => write!(f, "Link is blocked: {}", reason),
+            DatabaseError::OffsetTooLarge { offset, max } => write!(
+                f,
+                "Offset {} exceeds the maximum of {}; page with a keyset cursor instead",
+                offset, max
+            ),
+            DatabaseError::PageTooLarge { limit, max } => {
+                write!(f, "Page size {} exceeds the maximum of {}", limit, max)
+            }
+            DatabaseError::CyclicAlias(chain) => {
+                write!(f, "Alias chain does not terminate: {}", chain.join(" -> "))
+            }
             DatabaseError::MigrationError(msg) => write!(f, "Database migration error: {}", msg),
         }
     }
@@ -148,9 +525,153 @@ pub trait UrlDatabase: Send + Sync {
         code: &str,
         url: &str,
     ) -> Result<(UpsertResult, Urls), DatabaseError>;
+
+    /// Like [`UrlDatabase::insert_url`], but a newly created link is written
+    /// with `settings` in the same statement or transaction, so it never
+    /// exists without them. An already shortened destination keeps its own.
+    async fn insert_url_with_settings(
+        &self,
+        code: &str,
+        url: &str,
+        settings: &LinkSettings,
+    ) -> Result<(UpsertResult, Urls), DatabaseError>;
+
+    /// Adds `alias_code` as another code for the link `code_id`.
+    ///
+    /// Returns `DatabaseError::Duplicate` when the alias is taken, also as a
+    /// primary code, and `DatabaseError::InvalidReference` when no link has
+    /// id `code_id`.
     async fn insert_alias(&self, alias_code: &str, code_id: i64) -> Result<(), DatabaseError>;
+
+    /// Stores `url` under `code`, handling an already shortened destination
+    /// as `mode` says; [`InsertMode::Dedup`] is [`UrlDatabase::insert_url`].
+    ///
+    /// Destinations are unique per link, so [`InsertMode::AlwaysNew`] mints
+    /// `code` as an alias of the existing link: it resolves to the same
+    /// destination and shares its clicks and settings. `created` is then
+    /// `true` and the returned record carries the new code.
+    ///
+    /// With [`InsertMode::FailIfExists`], `DatabaseError::Duplicate` means
+    /// either that the destination exists or that `code` collided; a
+    /// [`UrlDatabase::get_id_by_url`] lookup tells the two apart.
+    async fn insert_url_with_mode(
+        &self,
+        code: &str,
+        url: &str,
+        mode: InsertMode,
+    ) -> Result<(UpsertResult, Urls), DatabaseError> {
+        let (upsert, record) = self.insert_url(code, url).await?;
+        if upsert.created {
+            return Ok((upsert, record));
+        }
+        match mode {
+            InsertMode::Dedup => Ok((upsert, record)),
+            InsertMode::FailIfExists => Err(DatabaseError::Duplicate),
+            InsertMode::AlwaysNew => {
+                self.insert_alias(code, upsert.id).await?;
+                Ok((
+                    UpsertResult {
+                        id: upsert.id,
+                        created: true,
+                    },
+                    Urls {
+                        id: upsert.id,
+                        code: code.to_string(),
+                    },
+                ))
+            }
+        }
+    }
+
+    /// Stores `url` under `code` like [`UrlDatabase::insert_url_with_settings`]
+    /// and, if given, points `alias` at the resulting link.
+    ///
+    /// An alias held by another link is reported as [`AliasOutcome::Taken`]
+    /// rather than an error, so `DatabaseError::Duplicate` always means `code`
+    /// collided and the caller may retry with a fresh one.
+    async fn insert_url_with_alias(
+        &self,
+        code: &str,
+        url: &str,
+        alias: Option<&str>,
+        settings: &LinkSettings,
+    ) -> Result<InsertOutcome, DatabaseError> {
+        let (upsert, record) = self.insert_url_with_settings(code, url, settings).await?;
+        let alias = match alias {
+            None => None,
+            Some(alias) => Some(match self.insert_alias(alias, upsert.id).await {
+                Ok(()) => AliasOutcome::Created(alias.to_string()),
+                Err(DatabaseError::Duplicate) => match self.get_id_by_code(alias).await {
+                    Ok(id) if id == upsert.id => AliasOutcome::Existing(alias.to_string()),
+                    Ok(_) | Err(DatabaseError::NotFound) => AliasOutcome::Taken(alias.to_string()),
+                    Err(e) => return Err(e),
+                },
+                Err(e) => return Err(e),
+            }),
+        };
+        Ok(InsertOutcome {
+            record,
+            created: upsert.created,
+            alias,
+        })
+    }
     async fn get_id_by_url(&self, url: &str) -> Result<Urls, DatabaseError>;
 
+    /// Looks up which of `urls` are already shortened, keyed by the input
+    /// URL.
+    ///
+    /// Inputs are taken to their
+    /// [`canonical_destination`](crate::core::destination::canonical_destination)
+    /// under the backend's `privacy` settings and hashed exactly as on create,
+    /// so two spellings of one destination both map to its record. Unknown and
+    /// unparseable URLs are omitted.
+    async fn codes_for_urls(&self, urls: &[String])
+    -> Result<HashMap<String, Urls>, DatabaseError>;
+
+    /// How many aliases point at each of the primary `codes`, from one
+    /// grouped query per chunk. Codes without aliases, and unknown codes,
+    /// map to `0`.
+    async fn alias_counts(&self, codes: &[String]) -> Result<HashMap<String, u64>, DatabaseError>;
+
+    /// Creation time, click count and last access for each of `codes`, looked
+    /// up in chunked `IN` queries instead of one call per code.
+    ///
+    /// Aliases resolve to their link's numbers. Unknown codes are omitted.
+    async fn stats_for_codes(
+        &self,
+        codes: &[String],
+    ) -> Result<HashMap<String, CodeStats>, DatabaseError>;
+
+    /// One resolution step: the code `code` forwards to, or `None` when
+    /// `code` is a primary code and resolution ends there.
+    ///
+    /// Returns `DatabaseError::NotFound` for unknown codes.
+    async fn next_hop(&self, code: &str) -> Result<Option<String>, DatabaseError>;
+
+    /// Every code visited while resolving `code`, starting with `code` and
+    /// ending with the primary code that holds the destination.
+    ///
+    /// Aliases point straight at primary codes today, so chains have at most
+    /// two entries; this walks [`next_hop`](UrlDatabase::next_hop) regardless
+    /// so loops introduced later surface as `DatabaseError::CyclicAlias`
+    /// instead of hanging a resolver.
+    async fn resolve_chain(&self, code: &str) -> Result<Vec<String>, DatabaseError> {
+        let mut chain = AliasChain::new(MAX_ALIAS_CHAIN_DEPTH);
+        let mut current = code.to_string();
+        loop {
+            chain.push(current.clone())?;
+            match self.next_hop(&current).await? {
+                Some(next) => current = next,
+                None => return Ok(chain.into_hops()),
+            }
+        }
+    }
+
+    /// Resolves a primary code, or an alias to its target, to the link's `id`.
+    ///
+    /// Returns `DatabaseError::NotFound` for unknown codes.
+    async fn get_id_by_code(&self, code: &str) -> Result<i64, DatabaseError>;
+
     /// Retrieves a URL by its short ID from the database.
     ///
     /// # Arguments
@@ -176,8 +697,750 @@ pub trait UrlDatabase: Send + Sync {
     /// # }
     /// ```
     async fn get_url(&self, id: &str) -> Result<String, DatabaseError>;
+
+    /// Like [`UrlDatabase::get_url`], but also returns the link's UTM opt-in so
+    /// the redirect handler can decorate the destination.
+    async fn get_redirect_target(&self, code: &str) -> Result<RedirectTarget, DatabaseError>;
+
+    /// Opts the link `url_id` in to UTM injection with the given per-link
+    /// parameters; unset parameters fall back to the global `utm` settings.
+    async fn set_link_utm(&self, url_id: i64, utm: &UtmParams) -> Result<(), DatabaseError>;
+
+    /// Limits the link `url_id` to `max_clicks` redirects, or lifts the
+    /// limit with `None`; see [`UrlDatabase::get_url_and_count`].
+    async fn set_max_clicks(
+        &self,
+        url_id: i64,
+        max_clicks: Option<u64>,
+    ) -> Result<(), DatabaseError>;
+
+    /// Sets whether the link `url_id` bypasses the redirect interstitial.
+    async fn set_skip_interstitial(&self, url_id: i64, skip: bool) -> Result<(), DatabaseError>;
+
+    /// Sets the metadata entry `key` of link `url_id` to `value`, replacing
+    /// any previous value.
+    async fn set_link_metadata(
+        &self,
+        url_id: i64,
+        key: &str,
+        value: &str,
+    ) -> Result<(), DatabaseError>;
+
+    /// Every metadata key attached to at least one link, sorted.
+    async fn distinct_metadata_keys(&self) -> Result<Vec<String>, DatabaseError>;
+
+    /// Every value stored under metadata `key`, sorted; empty for unknown keys.
+    async fn distinct_metadata_values(&self, key: &str) -> Result<Vec<String>, DatabaseError>;
+
+    /// Records `owner_key_id` as the API key that created the link `url_id`.
+    async fn set_link_owner(&self, url_id: i64, owner_key_id: &str) -> Result<(), DatabaseError>;
+
+    /// Moves every link owned by `from_owner` to `to_owner` in one
+    /// statement, returning how many moved; running it again moves nothing.
+    ///
+    /// Owners are [`ApiKeyId`](crate::middleware::ApiKeyId)s of configured
+    /// keys, which the database does not store, so only the shape of
+    /// `to_owner` is checked here: anything but 16 lowercase hex digits is
+    /// `DatabaseError::InvalidReference`. Callers confirm the key is
+    /// configured.
+    async fn reassign_owner(&self, from_owner: &str, to_owner: &str) -> Result<u64, DatabaseError>;
+
+    /// The link behind `code` (a primary code or an alias) with its status
+    /// columns, whether or not it currently resolves.
+    ///
+    /// Unlike [`UrlDatabase::get_url`] and the redirect lookups nothing is
+    /// filtered, so support can see why a link is expired or blocked. Only
+    /// expose this to administrators.
+    async fn get_url_admin(&self, code: &str) -> Result<UrlRecord, DatabaseError>;
+
+    /// Links created by `owner_key_id` with `from <= created_at < to`, oldest
+    /// first. Served by the `(owner_key_id, created_at)` index; `offset` and
+    /// `limit` follow [`UrlDatabase::list_short_codes`].
+    async fn urls_by_owner_between(
+        &self,
+        owner_key_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError>;
+
+    /// Links never clicked and created before `older_than`, oldest first,
+    /// for pruning dead links. Served by the partial `urls_unclicked_idx`;
+    /// `offset` is subject to `pagination.max_offset`.
+    ///
+    /// Clicks still held by [`BufferedClickDatabase`] are flushed first so
+    /// that recently used links are not reported.
+    async fn unclicked_urls(
+        &self,
+        older_than: DateTime<Utc>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError>;
+
+    /// Links changed at or after `since`, oldest change first, for syncing a
+    /// downstream cache or search index without a full scan. Ordered by
+    /// `(updated_at, id)` and served by `urls_updated_idx`; see
+    /// [`UrlRecord::updated_at`] for what counts as a change.
+    ///
+    /// Records carry `updated_at`. To fetch the next page, pass the last
+    /// record's `updated_at` and `Some(id)`: the keyset cursor skips exactly
+    /// what was returned. To start the next sync, pass the last `updated_at`
+    /// with `after_id: None`. `since` is inclusive, because SQLite stores
+    /// whole seconds and a link changed later in that second may sort before
+    /// the cursor; records of that second come again, so apply them
+    /// idempotently.
+    ///
+    /// Blocked and expired links are included with `blocked_reason` and
+    /// `expires_at` set. Deletes are hard, so a deleted link just drops out;
+    /// [`LinkEvent`]s report those.
+    async fn changed_since(
+        &self,
+        since: DateTime<Utc>,
+        after_id: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError>;
+
+    /// The `limit` most recently clicked links, newest first, from the
+    /// `urls_last_accessed_idx` index. Links never clicked are left out.
+    ///
+    /// Clicks still held by [`BufferedClickDatabase`] are flushed first.
+    async fn recently_accessed(&self, limit: u64) -> Result<Vec<UrlRecord>, DatabaseError>;
+
+    /// Up to `limit` links of `owner_key_id` with `id > after_id`, by id,
+    /// each with its aliases and metadata. Drives
+    /// [`UrlDatabase::export_owner`].
+    async fn export_owner_page(
+        &self,
+        owner_key_id: &str,
+        after_id: i64,
+        limit: u64,
+    ) -> Result<Vec<ExportedLink>, DatabaseError>;
+
+    /// Writes every link of `owner_key_id` to `writer` as NDJSON, one
+    /// [`ExportedLink`] per line, and returns how many were written.
+    ///
+    /// Only rows owned by `owner_key_id` are read. Pages are keyed by id, so
+    /// links created while the export runs do not shift later pages.
+    async fn export_owner(
+        &self,
+        owner_key_id: &str,
+        writer: &mut (dyn AsyncWrite + Send + Unpin),
+    ) -> Result<u64, DatabaseError> {
+        let io = |e: std::io::Error| DatabaseError::Io(e.to_string());
+        let mut after_id = 0;
+        let mut written = 0;
+        loop {
+            let page = self
+                .export_owner_page(owner_key_id, after_id, EXPORT_PAGE_SIZE)
+                .await?;
+            let mut buf = Vec::new();
+            for link in &page {
+                serde_json::to_writer(&mut buf, link)
+                    .map_err(|e| DatabaseError::Io(e.to_string()))?;
+                buf.push(b'\n');
+            }
+            writer.write_all(&buf).await.map_err(io)?;
+            written += page.len() as u64;
+            match page.last() {
+                Some(last) if page.len() as u64 == EXPORT_PAGE_SIZE => after_id = last.link.id,
+                _ => break,
+            }
+        }
+        writer.flush().await.map_err(io)?;
+        Ok(written)
+    }
+
+    /// Links whose destination host is `host`, by id. `*.example.com`
+    /// matches `example.com` and all of its subdomains. Case-insensitive;
+    /// served by the index on `urls.host`.
+    async fn find_by_host(
+        &self,
+        host: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError>;
+
+    /// Registers a wildcard alias. Callers validate it first with
+    /// [`pattern_alias::validate`](crate::shortcode::pattern_alias::validate).
+    ///
+    /// Returns `DatabaseError::Duplicate` if `pattern` is already registered.
+    async fn insert_pattern_alias(
+        &self,
+        pattern: &str,
+        target: &str,
+    ) -> Result<PatternAlias, DatabaseError>;
+
+    /// All wildcard aliases, in registration order.
+    async fn list_pattern_aliases(&self) -> Result<Vec<PatternAlias>, DatabaseError>;
+
+    /// Points `alias` at the link behind `new_code` (a primary code or another
+    /// alias) in a single transaction, so the alias never stops resolving.
+    ///
+    /// Returns `DatabaseError::NotFound` if either the alias or `new_code`
+    /// does not exist.
+    async fn retarget_alias(&self, alias: &str, new_code: &str) -> Result<(), DatabaseError>;
+
+    /// Exchanges the primary codes of two links in a single transaction, so
+    /// `code_a` then redirects to `code_b`'s destination and vice versa.
+    /// Aliases stay with their links.
+    ///
+    /// The set of codes is unchanged, so bloom filters need no update.
+    /// Returns `DatabaseError::NotFound` if either code is not a primary code.
+    async fn swap_codes(&self, code_a: &str, code_b: &str) -> Result<(), DatabaseError>;
+
+    /// Marks the link behind `code` (a primary code or alias) as legally
+    /// blocked. The row is kept for audit, but redirects answer 451 with
+    /// `reason` until [`UrlDatabase::unblock_link`] is called.
+    ///
+    /// Returns `DatabaseError::NotFound` for unknown codes.
+    async fn block_link(&self, code: &str, reason: &str) -> Result<(), DatabaseError>;
+
+    /// Lifts a block set by [`UrlDatabase::block_link`].
+    ///
+    /// Returns `DatabaseError::NotFound` for unknown codes.
+    async fn unblock_link(&self, code: &str) -> Result<(), DatabaseError>;
+
+    /// Adds the link behind `code` (a primary code or alias) to the featured
+    /// set shown on the homepage, or removes it. The flag lives on the link,
+    /// so it goes away when the link is deleted.
+    ///
+    /// Returns `DatabaseError::NotFound` for unknown codes.
+    async fn set_featured(&self, code: &str, featured: bool) -> Result<(), DatabaseError>;
+
+    /// Up to `limit` featured links, most recently created first, from the
+    /// `urls_featured_idx` index.
+    async fn list_featured(&self, limit: u64) -> Result<Vec<UrlRecord>, DatabaseError>;
+
+    /// Lists codes and aliases by `OFFSET`/`LIMIT`, in no particular order.
+    ///
+    /// The database still walks every skipped row, so offsets above
+    /// `pagination.max_offset` are refused with
+    /// `DatabaseError::OffsetTooLarge`, as in every offset listing:
+    /// [`UrlDatabase::list_short_codes_with_prefix`],
+    /// [`UrlDatabase::find_by_host`], [`UrlDatabase::unclicked_urls`] and
+    /// [`UrlDatabase::urls_by_owner_between`]. Walk large listings with
+    /// [`UrlDatabase::list_short_codes_after`] instead.
+    ///
+    /// A `limit` above `pagination.max_page_size` is refused with
+    /// `DatabaseError::PageTooLarge` rather than clamped, as in every
+    /// caller-facing listing: the offset listings above,
+    /// [`UrlDatabase::changed_since`], [`UrlDatabase::recently_accessed`]
+    /// and [`UrlDatabase::list_featured`]. The keyset walk and owner export
+    /// page internally and are not limited.
     async fn list_short_codes(&self, offset: u64, limit: u64)
     -> Result<Vec<String>, DatabaseError>;
+
+    /// Up to `limit` distinct codes and aliases sorted after `after` (from
+    /// the start when `None`); pass the last code of a page to get the next.
+    ///
+    /// Each page seeks straight to its cursor, so unlike
+    /// [`UrlDatabase::list_short_codes`] it costs the same at any depth.
+    async fn list_short_codes_after(
+        &self,
+        after: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<String>, DatabaseError>;
+
+    /// Like [`UrlDatabase::list_short_codes`], restricted to codes (and aliases)
+    /// starting with `prefix`.
+    ///
+    /// `%` and `_` in `prefix` match literally. Pagination follows the same
+    /// `offset`/`limit` contract as the unfiltered listing, including the
+    /// `pagination.max_offset` limit.
+    async fn list_short_codes_with_prefix(
+        &self,
+        prefix: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<String>, DatabaseError>;
+
+    /// Checks which of the given codes are already taken, either as a primary
+    /// code or as an alias.
+    ///
+    /// Every input code appears in the returned map, mapped to `true` when it
+    /// exists. Lookups are issued as chunked `IN (...)` queries (see
+    /// [`BIND_CHUNK_SIZE`]) instead of one query per code.
+    ///
+    /// This method always asks the database; use
+    /// [`BloomState::exists_many`](crate::shortcode::bloom_filter::BloomState::exists_many)
+    /// to skip codes the Bloom filter already rules out.
+    async fn exists_many(&self, codes: &[String]) -> Result<HashMap<String, bool>, DatabaseError>;
+
+    /// Deletes the links with primary code in `codes` in one transaction,
+    /// returning how many were deleted. Their aliases go with them through
+    /// the foreign key; unknown codes and aliases are skipped, not errors.
+    async fn delete_urls_batch(&self, codes: &[String]) -> Result<u64, DatabaseError>;
+
+    /// Sets `expires_at` on the links with primary code in `codes` in one
+    /// transaction, or makes them permanent with `None`, returning how many
+    /// were updated. Unknown codes and aliases are skipped; an alias with an
+    /// expiry of its own keeps it.
+    async fn set_expiry_batch(
+        &self,
+        codes: &[String],
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<u64, DatabaseError>;
+
+    /// Increments the click counter of the link behind `code`.
+    ///
+    /// Aliases count towards the link they point at. Unknown codes are ignored.
+    async fn increment_click_count(&self, code: &str) -> Result<(), DatabaseError> {
+        self.increment_click_count_by(code, 1).await
+    }
+
+    /// Adds `by` clicks to the link behind `code` in one statement.
+    ///
+    /// Same semantics as [`UrlDatabase::increment_click_count`]; used to write
+    /// back counts accumulated by [`BufferedClickDatabase`].
+    async fn increment_click_count_by(&self, code: &str, by: u64) -> Result<(), DatabaseError>;
+
+    /// Applies many `(code, by)` increments in one transaction, with one
+    /// `UPDATE ... CASE` per chunk of links instead of one per code.
+    ///
+    /// Same semantics as [`UrlDatabase::increment_click_count_by`]: aliases
+    /// count towards their link and unknown codes are skipped.
+    async fn increment_clicks_batch(
+        &self,
+        increments: &[(String, u64)],
+    ) -> Result<(), DatabaseError>;
+
+    /// Counts a click on the link behind `code` and returns its destination,
+    /// refusing with `DatabaseError::QuotaExceeded` once `click_count` has
+    /// reached the link's `max_clicks`.
+    ///
+    /// Check and increment are one `UPDATE`, so concurrent requests can never
+    /// exceed the quota. Unlike [`UrlDatabase::increment_click_count`] this
+    /// always writes through, including under [`BufferedClickDatabase`].
+    async fn get_url_and_count(&self, code: &str) -> Result<String, DatabaseError>;
+
+    /// Resolves `code` like [`UrlDatabase::get_redirect_target`], counts the
+    /// click and appends a `click_events` row with `referrer` and
+    /// `user_agent`, all in one transaction, and returns the destination.
+    ///
+    /// Nothing is recorded for a link that would not be served: blocked
+    /// links fail with `DatabaseError::Blocked`, expired ones with
+    /// `DatabaseError::Expired` and used-up quotas with
+    /// `DatabaseError::QuotaExceeded`. Like
+    /// [`UrlDatabase::get_url_and_count`] this always writes through.
+    async fn resolve_and_track(
+        &self,
+        code: &str,
+        referrer: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<String, DatabaseError>;
+
+    /// Whether no links exist yet, as a cheap `EXISTS` probe rather than a
+    /// count. Only `urls` is checked; snapshots and other bookkeeping rows do
+    /// not make a database non-empty.
+    async fn is_empty(&self) -> Result<bool, DatabaseError>;
+
+    /// Computes dataset-wide totals (links, aliases, clicks, expired links) in a
+    /// single round trip.
+    ///
+    /// This is a full aggregate over `urls` and `aliases`; callers serving it on
+    /// a hot path should cache the result.
+    async fn aggregate_stats(&self) -> Result<AggregateStats, DatabaseError>;
+
+    /// Stores `stats` as the newest snapshot, pruning all but the latest
+    /// [`STATS_SNAPSHOT_HISTORY`] rows.
+    async fn save_stats_snapshot(&self, stats: &AggregateStats) -> Result<(), DatabaseError>;
+
+    /// The most recently saved snapshot; a cheap stand-in for
+    /// [`aggregate_stats`](UrlDatabase::aggregate_stats) that is at most one
+    /// snapshot interval old.
+    ///
+    /// Returns `DatabaseError::NotFound` until the first snapshot is saved.
+    async fn latest_stats(&self) -> Result<AggregateStats, DatabaseError>;
+
+    /// Every `url_hash` shared by more than one distinct `url`, with those
+    /// urls sorted.
+    ///
+    /// Run this before switching hashers: while `url_hash` is `UNIQUE` the
+    /// result is always empty, but rows rehashed under a new algorithm can
+    /// collide and must be resolved before uniqueness is enforced again.
+    async fn find_hash_collisions(&self) -> Result<Vec<(Vec<u8>, Vec<String>)>, DatabaseError>;
+
     async fn load_bloom_snapshot(&self, name: &str) -> Result<Option<Vec<u8>>, DatabaseError>;
     async fn save_bloom_snapshot(&self, name: &str, data: &[u8]) -> Result<(), DatabaseError>;
+
+    /// Writes the stored snapshot `name` to `path` as a versioned
+    /// [`bloom_file`], returning the payload size. `DatabaseError::NotFound`
+    /// when no such snapshot exists.
+    async fn export_bloom_to_path(&self, name: &str, path: &Path) -> Result<u64, DatabaseError> {
+        bloom_file::export(self, name, path).await
+    }
+
+    /// Stores the [`bloom_file`] at `path` as snapshot `name`, replacing any
+    /// previous one, and returns the payload size. Files with another magic,
+    /// version or length are refused with `DatabaseError::InvalidSnapshot`.
+    ///
+    /// The running filter is not swapped; the import is picked up when the
+    /// filter is next loaded at startup.
+    async fn import_bloom_from_path(&self, name: &str, path: &Path) -> Result<u64, DatabaseError> {
+        bloom_file::import(self, name, path).await
+    }
+
+    /// Keeps only the newest `keep_latest_per_name` snapshots (by `updated_at`)
+    /// of every name and returns how many rows were deleted.
+    ///
+    /// `bloom_snapshots.name` is the primary key and `save_bloom_snapshot`
+    /// overwrites that one row, so any `keep_latest_per_name >= 1` is a no-op
+    /// that returns `0`; only `0` deletes, dropping every snapshot.
+    async fn prune_bloom_snapshots(
+        &self,
+        keep_latest_per_name: usize,
+    ) -> Result<u64, DatabaseError>;
+
+    /// Verifies the live schema against [`schema`]'s expected indexes,
+    /// uniqueness, views and foreign keys (including that SQLite enforces
+    /// them), returning every discrepancy found.
+    async fn self_check(&self) -> Result<SelfCheckReport, DatabaseError>;
+
+    /// Row counts of [`STORAGE_TABLES`], the size of the whole database and
+    /// the bytes held by bloom snapshots, for capacity planning.
+    ///
+    /// SQLite reports `page_count * page_size`, which includes free pages
+    /// until the file is vacuumed; Postgres reports `pg_database_size`.
+    async fn storage_stats(&self) -> Result<StorageStats, DatabaseError>;
+
+    /// Inserts a throwaway link under a reserved
+    /// [`SELF_TEST_PREFIX`](self_test::SELF_TEST_PREFIX) code, reads it back,
+    /// resolves it through `all_short_codes` and deletes it, timing each step.
+    ///
+    /// Failed operations are reported per step rather than as errors, and the
+    /// delete runs even when earlier steps fail.
+    async fn self_test(&self) -> Result<SelfTestReport, DatabaseError> {
+        self_test::run(self).await
+    }
+
+    /// Inserts `count` sample links for demos and returns how many were
+    /// created. Every fifth link gets a `demo-N` alias and every third a
+    /// `campaign` metadata tag; click counts are random and go through
+    /// [`UrlDatabase::increment_clicks_batch`].
+    ///
+    /// Does nothing when links already exist unless `force` is set; a forced
+    /// run over existing demo links only adds the missing ones. Only built
+    /// with the `demo` feature.
+    #[cfg(feature = "demo")]
+    async fn seed_demo_data(&self, count: usize, force: bool) -> Result<u64, DatabaseError> {
+        demo::seed(self, count, force).await
+    }
+
+    /// Forces all pending buffered writes to the database.
+    ///
+    /// Async work cannot run from `Drop`, so buffering implementations rely on
+    /// callers to flush explicitly; the graceful-shutdown path does so before
+    /// [`UrlDatabase::close`]. Implementations without buffers keep the default
+    /// no-op.
+    async fn flush(&self) -> Result<(), DatabaseError> {
+        Ok(())
+    }
+
+    /// Closes the underlying connection pool. Call [`UrlDatabase::flush`] first.
+    async fn close(&self) {}
+}
+
+/// Lets decorators such as [`BufferedClickDatabase`] wrap a shared
+/// `Arc<dyn UrlDatabase>`.
+#[async_trait]
+impl<T: UrlDatabase + ?Sized> UrlDatabase for std::sync::Arc<T> {
+    async fn insert_url(
+        &self,
+        code: &str,
+        url: &str,
+    ) -> Result<(UpsertResult, Urls), DatabaseError> {
+        (**self).insert_url(code, url).await
+    }
+    async fn insert_alias(&self, alias_code: &str, code_id: i64) -> Result<(), DatabaseError> {
+        (**self).insert_alias(alias_code, code_id).await
+    }
+    async fn insert_url_with_settings(
+        &self,
+        code: &str,
+        url: &str,
+        settings: &LinkSettings,
+    ) -> Result<(UpsertResult, Urls), DatabaseError> {
+        (**self).insert_url_with_settings(code, url, settings).await
+    }
+    async fn insert_url_with_mode(
+        &self,
+        code: &str,
+        url: &str,
+        mode: InsertMode,
+    ) -> Result<(UpsertResult, Urls), DatabaseError> {
+        (**self).insert_url_with_mode(code, url, mode).await
+    }
+    async fn insert_url_with_alias(
+        &self,
+        code: &str,
+        url: &str,
+        alias: Option<&str>,
+        settings: &LinkSettings,
+    ) -> Result<InsertOutcome, DatabaseError> {
+        (**self)
+            .insert_url_with_alias(code, url, alias, settings)
+            .await
+    }
+    async fn get_id_by_url(&self, url: &str) -> Result<Urls, DatabaseError> {
+        (**self).get_id_by_url(url).await
+    }
+    async fn codes_for_urls(
+        &self,
+        urls: &[String],
+    ) -> Result<HashMap<String, Urls>, DatabaseError> {
+        (**self).codes_for_urls(urls).await
+    }
+    async fn alias_counts(&self, codes: &[String]) -> Result<HashMap<String, u64>, DatabaseError> {
+        (**self).alias_counts(codes).await
+    }
+    async fn stats_for_codes(
+        &self,
+        codes: &[String],
+    ) -> Result<HashMap<String, CodeStats>, DatabaseError> {
+        (**self).stats_for_codes(codes).await
+    }
+    async fn next_hop(&self, code: &str) -> Result<Option<String>, DatabaseError> {
+        (**self).next_hop(code).await
+    }
+    async fn resolve_chain(&self, code: &str) -> Result<Vec<String>, DatabaseError> {
+        (**self).resolve_chain(code).await
+    }
+    async fn get_id_by_code(&self, code: &str) -> Result<i64, DatabaseError> {
+        (**self).get_id_by_code(code).await
+    }
+    async fn get_url(&self, id: &str) -> Result<String, DatabaseError> {
+        (**self).get_url(id).await
+    }
+    async fn get_redirect_target(&self, code: &str) -> Result<RedirectTarget, DatabaseError> {
+        (**self).get_redirect_target(code).await
+    }
+    async fn set_link_utm(&self, url_id: i64, utm: &UtmParams) -> Result<(), DatabaseError> {
+        (**self).set_link_utm(url_id, utm).await
+    }
+    async fn set_max_clicks(
+        &self,
+        url_id: i64,
+        max_clicks: Option<u64>,
+    ) -> Result<(), DatabaseError> {
+        (**self).set_max_clicks(url_id, max_clicks).await
+    }
+    async fn set_skip_interstitial(&self, url_id: i64, skip: bool) -> Result<(), DatabaseError> {
+        (**self).set_skip_interstitial(url_id, skip).await
+    }
+    async fn set_link_metadata(
+        &self,
+        url_id: i64,
+        key: &str,
+        value: &str,
+    ) -> Result<(), DatabaseError> {
+        (**self).set_link_metadata(url_id, key, value).await
+    }
+    async fn distinct_metadata_keys(&self) -> Result<Vec<String>, DatabaseError> {
+        (**self).distinct_metadata_keys().await
+    }
+    async fn distinct_metadata_values(&self, key: &str) -> Result<Vec<String>, DatabaseError> {
+        (**self).distinct_metadata_values(key).await
+    }
+    async fn set_link_owner(&self, url_id: i64, owner_key_id: &str) -> Result<(), DatabaseError> {
+        (**self).set_link_owner(url_id, owner_key_id).await
+    }
+    async fn reassign_owner(&self, from_owner: &str, to_owner: &str) -> Result<u64, DatabaseError> {
+        (**self).reassign_owner(from_owner, to_owner).await
+    }
+    async fn get_url_admin(&self, code: &str) -> Result<UrlRecord, DatabaseError> {
+        (**self).get_url_admin(code).await
+    }
+    async fn urls_by_owner_between(
+        &self,
+        owner_key_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        (**self)
+            .urls_by_owner_between(owner_key_id, from, to, offset, limit)
+            .await
+    }
+    async fn unclicked_urls(
+        &self,
+        older_than: DateTime<Utc>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        (**self).unclicked_urls(older_than, offset, limit).await
+    }
+    async fn changed_since(
+        &self,
+        since: DateTime<Utc>,
+        after_id: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        (**self).changed_since(since, after_id, limit).await
+    }
+    async fn recently_accessed(&self, limit: u64) -> Result<Vec<UrlRecord>, DatabaseError> {
+        (**self).recently_accessed(limit).await
+    }
+    async fn export_owner_page(
+        &self,
+        owner_key_id: &str,
+        after_id: i64,
+        limit: u64,
+    ) -> Result<Vec<ExportedLink>, DatabaseError> {
+        (**self)
+            .export_owner_page(owner_key_id, after_id, limit)
+            .await
+    }
+    async fn export_owner(
+        &self,
+        owner_key_id: &str,
+        writer: &mut (dyn AsyncWrite + Send + Unpin),
+    ) -> Result<u64, DatabaseError> {
+        (**self).export_owner(owner_key_id, writer).await
+    }
+    async fn find_by_host(
+        &self,
+        host: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        (**self).find_by_host(host, offset, limit).await
+    }
+    async fn insert_pattern_alias(
+        &self,
+        pattern: &str,
+        target: &str,
+    ) -> Result<PatternAlias, DatabaseError> {
+        (**self).insert_pattern_alias(pattern, target).await
+    }
+    async fn list_pattern_aliases(&self) -> Result<Vec<PatternAlias>, DatabaseError> {
+        (**self).list_pattern_aliases().await
+    }
+    async fn retarget_alias(&self, alias: &str, new_code: &str) -> Result<(), DatabaseError> {
+        (**self).retarget_alias(alias, new_code).await
+    }
+    async fn swap_codes(&self, code_a: &str, code_b: &str) -> Result<(), DatabaseError> {
+        (**self).swap_codes(code_a, code_b).await
+    }
+    async fn block_link(&self, code: &str, reason: &str) -> Result<(), DatabaseError> {
+        (**self).block_link(code, reason).await
+    }
+    async fn unblock_link(&self, code: &str) -> Result<(), DatabaseError> {
+        (**self).unblock_link(code).await
+    }
+    async fn set_featured(&self, code: &str, featured: bool) -> Result<(), DatabaseError> {
+        (**self).set_featured(code, featured).await
+    }
+    async fn list_featured(&self, limit: u64) -> Result<Vec<UrlRecord>, DatabaseError> {
+        (**self).list_featured(limit).await
+    }
+    async fn list_short_codes(
+        &self,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<String>, DatabaseError> {
+        (**self).list_short_codes(offset, limit).await
+    }
+    async fn list_short_codes_after(
+        &self,
+        after: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<String>, DatabaseError> {
+        (**self).list_short_codes_after(after, limit).await
+    }
+    async fn list_short_codes_with_prefix(
+        &self,
+        prefix: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<String>, DatabaseError> {
+        (**self)
+            .list_short_codes_with_prefix(prefix, offset, limit)
+            .await
+    }
+    async fn exists_many(&self, codes: &[String]) -> Result<HashMap<String, bool>, DatabaseError> {
+        (**self).exists_many(codes).await
+    }
+    async fn delete_urls_batch(&self, codes: &[String]) -> Result<u64, DatabaseError> {
+        (**self).delete_urls_batch(codes).await
+    }
+    async fn set_expiry_batch(
+        &self,
+        codes: &[String],
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<u64, DatabaseError> {
+        (**self).set_expiry_batch(codes, expires_at).await
+    }
+    async fn increment_click_count(&self, code: &str) -> Result<(), DatabaseError> {
+        (**self).increment_click_count(code).await
+    }
+    async fn increment_click_count_by(&self, code: &str, by: u64) -> Result<(), DatabaseError> {
+        (**self).increment_click_count_by(code, by).await
+    }
+    async fn increment_clicks_batch(
+        &self,
+        increments: &[(String, u64)],
+    ) -> Result<(), DatabaseError> {
+        (**self).increment_clicks_batch(increments).await
+    }
+    async fn get_url_and_count(&self, code: &str) -> Result<String, DatabaseError> {
+        (**self).get_url_and_count(code).await
+    }
+    async fn resolve_and_track(
+        &self,
+        code: &str,
+        referrer: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<String, DatabaseError> {
+        (**self).resolve_and_track(code, referrer, user_agent).await
+    }
+    async fn is_empty(&self) -> Result<bool, DatabaseError> {
+        (**self).is_empty().await
+    }
+    async fn aggregate_stats(&self) -> Result<AggregateStats, DatabaseError> {
+        (**self).aggregate_stats().await
+    }
+    async fn save_stats_snapshot(&self, stats: &AggregateStats) -> Result<(), DatabaseError> {
+        (**self).save_stats_snapshot(stats).await
+    }
+    async fn latest_stats(&self) -> Result<AggregateStats, DatabaseError> {
+        (**self).latest_stats().await
+    }
+    async fn find_hash_collisions(&self) -> Result<Vec<(Vec<u8>, Vec<String>)>, DatabaseError> {
+        (**self).find_hash_collisions().await
+    }
+    async fn load_bloom_snapshot(&self, name: &str) -> Result<Option<Vec<u8>>, DatabaseError> {
+        (**self).load_bloom_snapshot(name).await
+    }
+    async fn export_bloom_to_path(&self, name: &str, path: &Path) -> Result<u64, DatabaseError> {
+        (**self).export_bloom_to_path(name, path).await
+    }
+    async fn import_bloom_from_path(&self, name: &str, path: &Path) -> Result<u64, DatabaseError> {
+        (**self).import_bloom_from_path(name, path).await
+    }
+    async fn save_bloom_snapshot(&self, name: &str, data: &[u8]) -> Result<(), DatabaseError> {
+        (**self).save_bloom_snapshot(name, data).await
+    }
+    async fn prune_bloom_snapshots(
+        &self,
+        keep_latest_per_name: usize,
+    ) -> Result<u64, DatabaseError> {
+        (**self).prune_bloom_snapshots(keep_latest_per_name).await
+    }
+    async fn self_check(&self) -> Result<SelfCheckReport, DatabaseError> {
+        (**self).self_check().await
+    }
+    async fn storage_stats(&self) -> Result<StorageStats, DatabaseError> {
+        (**self).storage_stats().await
+    }
+    async fn self_test(&self) -> Result<SelfTestReport, DatabaseError> {
+        (**self).self_test().await
+    }
+    async fn flush(&self) -> Result<(), DatabaseError> {
+        (**self).flush().await
+    }
+    async fn close(&self) {
+        (**self).close().await
+    }
 }
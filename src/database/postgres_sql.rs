@@ -57,14 +57,26 @@
 //! This struct is `Send + Sync` and can be safely used across thread boundaries.
 //! The underlying `PgPool` is designed for concurrent access.
 
-use super::{DatabaseError, UrlDatabase};
-use crate::configuration::DatabaseSettings;
-use crate::models::{UpsertResult, Urls};
+use super::{
+    DEFAULT_MAX_OFFSET, DEFAULT_MAX_PAGE_SIZE, DEFAULT_SNAPSHOT_CHUNK_BYTES, DatabaseError,
+    PendingMigration, STATS_SNAPSHOT_HISTORY, SelfCheckReport, UrlDatabase, assemble_export,
+    bind_chunks, check_offset, check_owner_key_id, check_page_size, check_servable, escape_like,
+    group_by_hash, hash_normalized_urls, host_filter, pending_migrations, query_error, schema,
+    source_order, source_rank, table_counts_query,
+};
+use crate::configuration::{CodePrecedence, DatabaseSettings, PrivacySettings};
+use crate::models::{
+    AggregateStats, CodeStats, ExportedLink, LinkSettings, PatternAlias, RedirectTarget,
+    StorageStats, UpsertResult, UrlRecord, Urls, UtmParams,
+};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use sqlx::{
-    Error as SqlxError, PgPool,
+    Error as SqlxError, PgPool, Postgres, QueryBuilder,
+    migrate::Migrator,
     postgres::{PgConnectOptions, PgPoolOptions},
 };
+use std::collections::HashMap;
 use std::str::FromStr;
 
 const MAX_CAP: u32 = 96;
@@ -78,6 +90,16 @@ const MIN_CAP: u32 = 2;
 pub struct PostgresUrlDatabase {
     /// PostgreSQL connection pool for database operations
     pool: PgPool,
+    /// Bloom snapshots above this size are stored in chunks of this size
+    snapshot_chunk_bytes: usize,
+    /// Resolves codes that exist both as primary code and alias
+    precedence: CodePrecedence,
+    /// Largest `OFFSET` the offset listings accept; `0` for no limit
+    max_offset: u64,
+    /// Largest `LIMIT` the caller-facing listings accept; `0` for no limit
+    max_page_size: u64,
+    /// Tracking parameters stripped from destinations before they are hashed
+    privacy: PrivacySettings,
 }
 
 impl PostgresUrlDatabase {
@@ -100,7 +122,50 @@ impl PostgresUrlDatabase {
     /// # }
     /// ```
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            snapshot_chunk_bytes: DEFAULT_SNAPSHOT_CHUNK_BYTES,
+            precedence: CodePrecedence::default(),
+            max_offset: DEFAULT_MAX_OFFSET,
+            max_page_size: DEFAULT_MAX_PAGE_SIZE,
+            privacy: PrivacySettings::default(),
+        }
+    }
+
+    /// Overrides the size above which bloom snapshots are chunked
+    /// (default [`DEFAULT_SNAPSHOT_CHUNK_BYTES`]).
+    pub fn with_snapshot_chunk_bytes(mut self, bytes: usize) -> Self {
+        self.snapshot_chunk_bytes = bytes.max(1);
+        self
+    }
+
+    /// Overrides which side wins for codes that are both a primary code and
+    /// an alias (default [`CodePrecedence::Primary`]).
+    pub fn with_code_precedence(mut self, precedence: CodePrecedence) -> Self {
+        self.precedence = precedence;
+        self
+    }
+
+    /// Overrides the largest `OFFSET` [`UrlDatabase::list_short_codes`]
+    /// accepts (default [`DEFAULT_MAX_OFFSET`]); `0` removes the limit.
+    pub fn with_max_offset(mut self, max_offset: u64) -> Self {
+        self.max_offset = max_offset;
+        self
+    }
+
+    /// Overrides the largest `LIMIT` the listings accept (default
+    /// [`DEFAULT_MAX_PAGE_SIZE`]); `0` removes the limit.
+    pub fn with_max_page_size(mut self, max_page_size: u64) -> Self {
+        self.max_page_size = max_page_size;
+        self
+    }
+
+    /// Sets the `privacy` settings the create endpoints store destinations
+    /// under, so [`UrlDatabase::codes_for_urls`] hashes inputs the same way
+    /// (default: no stripping).
+    pub fn with_privacy(mut self, privacy: PrivacySettings) -> Self {
+        self.privacy = privacy;
+        self
     }
 
     /// Creates a new `PostgresUrlDatabase` from configuration settings.
@@ -174,14 +239,49 @@ impl PostgresUrlDatabase {
     /// # }
     /// ```
     pub async fn migrate(&self) -> Result<(), DatabaseError> {
-        sqlx::migrate!("./migrations/pg")
+        self.migrate_with(&POSTGRES_MIGRATOR).await
+    }
+
+    /// Runs the migrations of `migrator` instead of the built-in set.
+    ///
+    /// Lets embedders that extend the schema ship their own migrations. A
+    /// migrator holding only the extra migrations must allow the built-in
+    /// ones as unknown (`Migrator::set_ignore_missing(true)`), otherwise sqlx
+    /// rejects a database that already ran them. Conversely, once extra
+    /// migrations ran, plain `migrate()` rejects the database, so keep
+    /// migrating through this method.
+    pub async fn migrate_with(&self, migrator: &Migrator) -> Result<(), DatabaseError> {
+        migrator
             .run(&self.pool)
             .await
-            .map_err(|e| DatabaseError::MigrationError(e.to_string()))?;
-        Ok(())
+            .map_err(|e| DatabaseError::MigrationError(e.to_string()))
+    }
+
+    /// Lists the built-in migrations `migrate` would apply, without applying
+    /// them. A database that was never migrated reports all of them.
+    pub async fn migrate_dry_run(&self) -> Result<Vec<PendingMigration>, DatabaseError> {
+        let tracked: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM information_schema.tables WHERE table_schema = current_schema() AND table_name = '_sqlx_migrations'",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| DatabaseError::MigrationError(e.to_string()))?;
+
+        let applied: Vec<i64> = if tracked == 0 {
+            Vec::new()
+        } else {
+            sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| DatabaseError::MigrationError(e.to_string()))?
+        };
+        Ok(pending_migrations(&POSTGRES_MIGRATOR, &applied))
     }
 }
 
+/// The built-in migrations applied by `migrate`, embedded at compile time.
+pub static POSTGRES_MIGRATOR: Migrator = sqlx::migrate!("./migrations/pg");
+
 #[async_trait]
 impl UrlDatabase for PostgresUrlDatabase {
     /// Retrieves the short ID by original URL from the PostgreSQL database.
@@ -192,7 +292,7 @@ impl UrlDatabase for PostgresUrlDatabase {
         .bind(url)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+        .map_err(query_error)?;
 
         match row {
             Some(record) => Ok(record),
@@ -213,11 +313,23 @@ impl UrlDatabase for PostgresUrlDatabase {
         code: &str,
         url: &str,
     ) -> Result<(UpsertResult, Urls), DatabaseError> {
+        self.insert_url_with_settings(code, url, &LinkSettings::default())
+            .await
+    }
+
+    async fn insert_url_with_settings(
+        &self,
+        code: &str,
+        url: &str,
+        settings: &LinkSettings,
+    ) -> Result<(UpsertResult, Urls), DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+
         // First, call the existing SQL function to either insert the URL or get the ID if it exists.
         let upsert_result: UpsertResult = sqlx::query_as("SELECT * FROM upsert_url($1, $2)")
             .bind(code)
             .bind(url)
-            .fetch_one(&self.pool)
+            .fetch_one(&mut *tx)
             .await
             .map_err(|e| {
                 if is_unique_violation(&e) {
@@ -227,6 +339,29 @@ impl UrlDatabase for PostgresUrlDatabase {
                 }
             })?;
 
+        // upsert_url() only knows code and URL, so the settings of a new link
+        // follow in the same transaction
+        if upsert_result.created && *settings != LinkSettings::default() {
+            let utm = settings.utm.as_ref();
+            sqlx::query(
+                "UPDATE urls SET owner_key_id = $1, max_clicks = $2, utm_enabled = $3, \
+                 utm_source = $4, utm_medium = $5, utm_campaign = $6, skip_interstitial = $7 \
+                 WHERE id = $8",
+            )
+            .bind(settings.owner_key_id.as_deref())
+            .bind(settings.max_clicks.map(|n| n as i64))
+            .bind(utm.is_some())
+            .bind(utm.and_then(|utm| utm.source.as_deref()))
+            .bind(utm.and_then(|utm| utm.medium.as_deref()))
+            .bind(utm.and_then(|utm| utm.campaign.as_deref()))
+            .bind(settings.skip_interstitial)
+            .bind(upsert_result.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(query_error)?;
+        }
+        tx.commit().await.map_err(query_error)?;
+
         // If a new record was created, the code is the one we just generated.
         if upsert_result.created {
             let urls = Urls {
@@ -241,11 +376,42 @@ impl UrlDatabase for PostgresUrlDatabase {
             .bind(upsert_result.id)
             .fetch_one(&self.pool)
             .await
-            .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+            .map_err(query_error)?;
 
         Ok((upsert_result, existing_urls))
     }
 
+    async fn next_hop(&self, code: &str) -> Result<Option<String>, DatabaseError> {
+        let row: Option<(String, String)> = sqlx::query_as(&format!(
+            "SELECT c.source, u.code FROM all_short_codes c \
+             JOIN urls u ON u.id = c.target_id WHERE c.code = $1 ORDER BY {} LIMIT 1",
+            source_order(self.precedence)
+        ))
+        .bind(code)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        match row {
+            Some((source, primary)) if source == "alias" => Ok(Some(primary)),
+            Some(_) => Ok(None),
+            None => Err(DatabaseError::NotFound),
+        }
+    }
+
+    async fn get_id_by_code(&self, code: &str) -> Result<i64, DatabaseError> {
+        let id: Option<i64> = sqlx::query_scalar(&format!(
+            "SELECT target_id FROM all_short_codes WHERE code = $1 ORDER BY {} LIMIT 1",
+            source_order(self.precedence)
+        ))
+        .bind(code)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        id.ok_or(DatabaseError::NotFound)
+    }
+
     /// Retrieves a URL by its short ID from the PostgreSQL database.
     ///
     /// Uses a prepared statement with optional result handling
@@ -260,13 +426,14 @@ impl UrlDatabase for PostgresUrlDatabase {
     /// Returns `Ok(String)` with the original URL if found, or
     /// `Err(DatabaseError::NotFound)` if no record exists.
     async fn get_url(&self, code: &str) -> Result<String, DatabaseError> {
-        let row = sqlx::query_as::<_, (String,)>(
-            "SELECT url FROM all_short_codes u WHERE u.code = $1 LIMIT 1;",
-        )
+        let row = sqlx::query_as::<_, (String,)>(&format!(
+            "SELECT url FROM all_short_codes u WHERE u.code = $1 ORDER BY {} LIMIT 1;",
+            source_order(self.precedence)
+        ))
         .bind(code)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+        .map_err(query_error)?;
 
         match row {
             Some(record) => Ok(record.0),
@@ -274,67 +441,1295 @@ impl UrlDatabase for PostgresUrlDatabase {
         }
     }
 
+    async fn get_url_admin(&self, code: &str) -> Result<UrlRecord, DatabaseError> {
+        let row = sqlx::query_as::<_, UrlRecord>(&format!(
+            r#"
+                SELECT u.id, u.code, u.url, u.created_at, u.owner_key_id, u.click_count,
+                       COALESCE(a.expires_at, u.expires_at) AS expires_at, u.blocked_reason
+                FROM all_short_codes c
+                JOIN urls u ON u.id = c.target_id
+                LEFT JOIN aliases a ON c.source = 'alias' AND a.alias = c.code
+                WHERE c.code = $1
+                ORDER BY {}
+                LIMIT 1
+            "#,
+            source_order(self.precedence)
+        ))
+        .bind(code)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        row.ok_or(DatabaseError::NotFound)
+    }
+
+    async fn get_redirect_target(&self, code: &str) -> Result<RedirectTarget, DatabaseError> {
+        let row = sqlx::query_as::<_, RedirectTarget>(&format!(
+            r#"
+                SELECT u.url, u.utm_enabled, u.utm_source, u.utm_medium, u.utm_campaign,
+                       COALESCE(a.expires_at, u.expires_at) AS expires_at, u.blocked_reason,
+                       u.skip_interstitial, u.updated_at, u.max_clicks
+                FROM all_short_codes c
+                JOIN urls u ON u.id = c.target_id
+                LEFT JOIN aliases a ON c.source = 'alias' AND a.alias = c.code
+                WHERE c.code = $1
+                ORDER BY {}
+                LIMIT 1
+            "#,
+            source_order(self.precedence)
+        ))
+        .bind(code)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        row.ok_or(DatabaseError::NotFound)
+    }
+
+    async fn set_link_utm(&self, url_id: i64, utm: &UtmParams) -> Result<(), DatabaseError> {
+        let result = sqlx::query(
+            "UPDATE urls SET utm_enabled = TRUE, utm_source = $1, utm_medium = $2, utm_campaign = $3, \
+             updated_at = now() \
+             WHERE id = $4",
+        )
+        .bind(utm.source.as_deref())
+        .bind(utm.medium.as_deref())
+        .bind(utm.campaign.as_deref())
+        .bind(url_id)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn set_max_clicks(
+        &self,
+        url_id: i64,
+        max_clicks: Option<u64>,
+    ) -> Result<(), DatabaseError> {
+        let result =
+            sqlx::query("UPDATE urls SET max_clicks = $1, updated_at = now() WHERE id = $2")
+                .bind(max_clicks.map(|n| n as i64))
+                .bind(url_id)
+                .execute(&self.pool)
+                .await
+                .map_err(query_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn set_skip_interstitial(&self, url_id: i64, skip: bool) -> Result<(), DatabaseError> {
+        let result =
+            sqlx::query("UPDATE urls SET skip_interstitial = $1, updated_at = now() WHERE id = $2")
+                .bind(skip)
+                .bind(url_id)
+                .execute(&self.pool)
+                .await
+                .map_err(query_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn set_link_metadata(
+        &self,
+        url_id: i64,
+        key: &str,
+        value: &str,
+    ) -> Result<(), DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+        sqlx::query(
+            "INSERT INTO url_metadata (url_id, key, value) VALUES ($1, $2, $3)
+             ON CONFLICT (url_id, key) DO UPDATE SET value = excluded.value",
+        )
+        .bind(url_id)
+        .bind(key)
+        .bind(value)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            if is_foreign_key_violation(&e) {
+                DatabaseError::InvalidReference
+            } else {
+                DatabaseError::QueryError(e.to_string())
+            }
+        })?;
+        sqlx::query("UPDATE urls SET updated_at = now() WHERE id = $1")
+            .bind(url_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(query_error)?;
+        tx.commit().await.map_err(query_error)
+    }
+
+    async fn distinct_metadata_keys(&self) -> Result<Vec<String>, DatabaseError> {
+        sqlx::query_scalar("SELECT DISTINCT key FROM url_metadata ORDER BY key")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(query_error)
+    }
+
+    async fn distinct_metadata_values(&self, key: &str) -> Result<Vec<String>, DatabaseError> {
+        sqlx::query_scalar("SELECT DISTINCT value FROM url_metadata WHERE key = $1 ORDER BY value")
+            .bind(key)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(query_error)
+    }
+
+    async fn set_link_owner(&self, url_id: i64, owner_key_id: &str) -> Result<(), DatabaseError> {
+        let result =
+            sqlx::query("UPDATE urls SET owner_key_id = $1, updated_at = now() WHERE id = $2")
+                .bind(owner_key_id)
+                .bind(url_id)
+                .execute(&self.pool)
+                .await
+                .map_err(query_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn reassign_owner(&self, from_owner: &str, to_owner: &str) -> Result<u64, DatabaseError> {
+        check_owner_key_id(to_owner)?;
+        let result = sqlx::query(
+            "UPDATE urls SET owner_key_id = $1, updated_at = now() WHERE owner_key_id = $2",
+        )
+        .bind(to_owner)
+        .bind(from_owner)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn urls_by_owner_between(
+        &self,
+        owner_key_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        check_offset(offset, self.max_offset)?;
+        check_page_size(limit, self.max_page_size)?;
+        sqlx::query_as::<_, UrlRecord>(
+            r#"
+                SELECT id, code, url, created_at, owner_key_id, click_count, expires_at,
+                       blocked_reason
+                FROM urls
+                WHERE owner_key_id = $1 AND created_at >= $2 AND created_at < $3
+                ORDER BY created_at, id
+                LIMIT $4 OFFSET $5
+            "#,
+        )
+        .bind(owner_key_id)
+        .bind(from)
+        .bind(to)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)
+    }
+
+    async fn unclicked_urls(
+        &self,
+        older_than: DateTime<Utc>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        check_offset(offset, self.max_offset)?;
+        check_page_size(limit, self.max_page_size)?;
+        sqlx::query_as::<_, UrlRecord>(
+            r#"
+                SELECT id, code, url, created_at, owner_key_id, click_count, expires_at,
+                       blocked_reason
+                FROM urls
+                WHERE click_count = 0 AND created_at < $1
+                ORDER BY created_at, id
+                LIMIT $2 OFFSET $3
+            "#,
+        )
+        .bind(older_than)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)
+    }
+
+    async fn changed_since(
+        &self,
+        since: DateTime<Utc>,
+        after_id: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        check_page_size(limit, self.max_page_size)?;
+        sqlx::query_as::<_, UrlRecord>(
+            r#"
+                SELECT id, code, url, created_at, owner_key_id, click_count, expires_at,
+                       blocked_reason, updated_at
+                FROM urls
+                WHERE updated_at >= $1
+                  AND ($2::BIGINT IS NULL OR updated_at > $1 OR id > $2::BIGINT)
+                ORDER BY updated_at, id
+                LIMIT $3
+            "#,
+        )
+        .bind(since)
+        .bind(after_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)
+    }
+
+    async fn recently_accessed(&self, limit: u64) -> Result<Vec<UrlRecord>, DatabaseError> {
+        check_page_size(limit, self.max_page_size)?;
+        sqlx::query_as::<_, UrlRecord>(
+            r#"
+                SELECT id, code, url, created_at, owner_key_id, click_count, expires_at,
+                       blocked_reason
+                FROM urls
+                WHERE last_accessed_at IS NOT NULL
+                ORDER BY last_accessed_at DESC, id DESC
+                LIMIT $1
+            "#,
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)
+    }
+
+    async fn export_owner_page(
+        &self,
+        owner_key_id: &str,
+        after_id: i64,
+        limit: u64,
+    ) -> Result<Vec<ExportedLink>, DatabaseError> {
+        let links = sqlx::query_as::<_, UrlRecord>(
+            r#"
+                SELECT id, code, url, created_at, owner_key_id, click_count, expires_at,
+                       blocked_reason
+                FROM urls
+                WHERE owner_key_id = $1 AND id > $2
+                ORDER BY id
+                LIMIT $3
+            "#,
+        )
+        .bind(owner_key_id)
+        .bind(after_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        let ids: Vec<i64> = links.iter().map(|l| l.id).collect();
+        let mut aliases = Vec::new();
+        let mut metadata = Vec::new();
+        for chunk in bind_chunks(&ids) {
+            let mut query = QueryBuilder::<Postgres>::new(
+                "SELECT target_id, alias FROM aliases WHERE target_id IN (",
+            );
+            let mut separated = query.separated(", ");
+            for id in chunk {
+                separated.push_bind(*id);
+            }
+            separated.push_unseparated(")");
+            aliases.extend(
+                query
+                    .build_query_as::<(i64, String)>()
+                    .fetch_all(&self.pool)
+                    .await
+                    .map_err(query_error)?,
+            );
+
+            let mut query = QueryBuilder::<Postgres>::new(
+                "SELECT url_id, key, value FROM url_metadata WHERE url_id IN (",
+            );
+            let mut separated = query.separated(", ");
+            for id in chunk {
+                separated.push_bind(*id);
+            }
+            separated.push_unseparated(")");
+            metadata.extend(
+                query
+                    .build_query_as::<(i64, String, String)>()
+                    .fetch_all(&self.pool)
+                    .await
+                    .map_err(query_error)?,
+            );
+        }
+
+        Ok(assemble_export(links, aliases, metadata))
+    }
+
+    async fn find_by_host(
+        &self,
+        host: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        check_offset(offset, self.max_offset)?;
+        check_page_size(limit, self.max_page_size)?;
+        let (host, subdomains) = host_filter(host);
+        sqlx::query_as::<_, UrlRecord>(
+            r#"
+                SELECT id, code, url, created_at, owner_key_id, click_count, expires_at,
+                       blocked_reason
+                FROM urls
+                WHERE host = $1 OR host LIKE $2 ESCAPE '\'
+                ORDER BY id
+                LIMIT $3 OFFSET $4
+            "#,
+        )
+        .bind(host)
+        .bind(subdomains)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)
+    }
+
+    async fn insert_pattern_alias(
+        &self,
+        pattern: &str,
+        target: &str,
+    ) -> Result<PatternAlias, DatabaseError> {
+        sqlx::query_as::<_, PatternAlias>(
+            "INSERT INTO alias_patterns (pattern, target) VALUES ($1, $2) \
+             RETURNING id, pattern, target",
+        )
+        .bind(pattern)
+        .bind(target)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                DatabaseError::Duplicate
+            } else {
+                DatabaseError::QueryError(e.to_string())
+            }
+        })
+    }
+
+    async fn list_pattern_aliases(&self) -> Result<Vec<PatternAlias>, DatabaseError> {
+        sqlx::query_as::<_, PatternAlias>(
+            "SELECT id, pattern, target FROM alias_patterns ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)
+    }
+
+    async fn retarget_alias(&self, alias: &str, new_code: &str) -> Result<(), DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+
+        let target_id: Option<i64> = sqlx::query_scalar(&format!(
+            "SELECT target_id FROM all_short_codes WHERE code = $1 ORDER BY {} LIMIT 1",
+            source_order(self.precedence)
+        ))
+        .bind(new_code)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(query_error)?;
+        let target_id = target_id.ok_or(DatabaseError::NotFound)?;
+
+        let result = sqlx::query("UPDATE aliases SET target_id = $1 WHERE alias = $2")
+            .bind(target_id)
+            .bind(alias)
+            .execute(&mut *tx)
+            .await
+            .map_err(query_error)?;
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound);
+        }
+
+        // The alias now redirects differently, even if its new link is older
+        sqlx::query("UPDATE urls SET updated_at = now() WHERE id = $1")
+            .bind(target_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(query_error)?;
+
+        tx.commit().await.map_err(query_error)
+    }
+
+    async fn swap_codes(&self, code_a: &str, code_b: &str) -> Result<(), DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+        let mut ids = Vec::with_capacity(2);
+        for code in [code_a, code_b] {
+            let id: Option<i64> = sqlx::query_scalar("SELECT id FROM urls WHERE code = $1")
+                .bind(code)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(query_error)?;
+            ids.push(id.ok_or(DatabaseError::NotFound)?);
+        }
+
+        // Park `code_a` on a placeholder first so UNIQUE(code) never sees
+        // both links on the same code; codes never contain `/`
+        let placeholder = format!("swap/{}", ids[0]);
+        for (id, code) in [
+            (ids[0], placeholder.as_str()),
+            (ids[1], code_a),
+            (ids[0], code_b),
+        ] {
+            sqlx::query("UPDATE urls SET code = $1, updated_at = now() WHERE id = $2")
+                .bind(code)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(query_error)?;
+        }
+
+        tx.commit().await.map_err(query_error)
+    }
+
+    async fn block_link(&self, code: &str, reason: &str) -> Result<(), DatabaseError> {
+        let result = sqlx::query(&format!(
+            "UPDATE urls SET blocked_reason = $1, updated_at = now() \
+             WHERE id = (SELECT target_id FROM all_short_codes WHERE code = $2 \
+                         ORDER BY {} LIMIT 1)",
+            source_order(self.precedence)
+        ))
+        .bind(reason)
+        .bind(code)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn unblock_link(&self, code: &str) -> Result<(), DatabaseError> {
+        let result = sqlx::query(&format!(
+            "UPDATE urls SET blocked_reason = NULL, updated_at = now() \
+             WHERE id = (SELECT target_id FROM all_short_codes WHERE code = $1 \
+                         ORDER BY {} LIMIT 1)",
+            source_order(self.precedence)
+        ))
+        .bind(code)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn set_featured(&self, code: &str, featured: bool) -> Result<(), DatabaseError> {
+        let result = sqlx::query(&format!(
+            "UPDATE urls SET featured = $1, updated_at = now() \
+             WHERE id = (SELECT target_id FROM all_short_codes WHERE code = $2 \
+                         ORDER BY {} LIMIT 1)",
+            source_order(self.precedence)
+        ))
+        .bind(featured)
+        .bind(code)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn list_featured(&self, limit: u64) -> Result<Vec<UrlRecord>, DatabaseError> {
+        check_page_size(limit, self.max_page_size)?;
+        sqlx::query_as::<_, UrlRecord>(
+            r#"
+                SELECT id, code, url, created_at, owner_key_id, click_count, expires_at,
+                       blocked_reason
+                FROM urls
+                WHERE featured
+                ORDER BY id DESC
+                LIMIT $1
+            "#,
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)
+    }
+
     async fn list_short_codes(
         &self,
         offset: u64,
         limit: u64,
     ) -> Result<Vec<String>, DatabaseError> {
+        check_offset(offset, self.max_offset)?;
+        check_page_size(limit, self.max_page_size)?;
         let codes: Vec<String> =
             sqlx::query_scalar("SELECT code FROM all_short_codes LIMIT $1 OFFSET $2")
                 .bind(limit as i64)
                 .bind(offset as i64)
                 .fetch_all(&self.pool)
                 .await
-                .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+                .map_err(query_error)?;
+
+        Ok(codes)
+    }
+
+    async fn list_short_codes_after(
+        &self,
+        after: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<String>, DatabaseError> {
+        let codes: Vec<String> = sqlx::query_scalar(
+            "SELECT DISTINCT code FROM all_short_codes WHERE $1::text IS NULL OR code > $1 ORDER BY code LIMIT $2",
+        )
+        .bind(after)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        Ok(codes)
+    }
+
+    async fn list_short_codes_with_prefix(
+        &self,
+        prefix: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<String>, DatabaseError> {
+        check_offset(offset, self.max_offset)?;
+        check_page_size(limit, self.max_page_size)?;
+        let codes: Vec<String> = sqlx::query_scalar(
+            r"SELECT code FROM all_short_codes WHERE code LIKE $1 || '%' ESCAPE '\' LIMIT $2 OFFSET $3",
+        )
+        .bind(escape_like(prefix))
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)?;
 
         Ok(codes)
     }
 
+    async fn codes_for_urls(
+        &self,
+        urls: &[String],
+    ) -> Result<HashMap<String, Urls>, DatabaseError> {
+        let by_hash = hash_normalized_urls(urls, &self.privacy);
+        let hashes: Vec<&[u8; 32]> = by_hash.keys().collect();
+        let mut result = HashMap::new();
+
+        for chunk in bind_chunks(&hashes) {
+            let mut query = QueryBuilder::<Postgres>::new(
+                "SELECT id, code, url_hash FROM urls WHERE url_hash IN (",
+            );
+            let mut separated = query.separated(", ");
+            for hash in chunk {
+                separated.push_bind(&hash[..]);
+            }
+            separated.push_unseparated(")");
+
+            let found: Vec<(i64, String, Vec<u8>)> = query
+                .build_query_as()
+                .fetch_all(&self.pool)
+                .await
+                .map_err(query_error)?;
+
+            for (id, code, hash) in found {
+                let Some(inputs) = <[u8; 32]>::try_from(hash.as_slice())
+                    .ok()
+                    .and_then(|hash| by_hash.get(&hash))
+                else {
+                    continue;
+                };
+                for url in inputs {
+                    result.insert(
+                        url.clone(),
+                        Urls {
+                            id,
+                            code: code.clone(),
+                        },
+                    );
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn alias_counts(&self, codes: &[String]) -> Result<HashMap<String, u64>, DatabaseError> {
+        let mut result: HashMap<String, u64> = codes.iter().map(|code| (code.clone(), 0)).collect();
+
+        for chunk in bind_chunks(codes) {
+            let mut query = QueryBuilder::<Postgres>::new(
+                "SELECT u.code, COUNT(a.alias) FROM urls u \
+                 JOIN aliases a ON a.target_id = u.id WHERE u.code IN (",
+            );
+            let mut separated = query.separated(", ");
+            for code in chunk {
+                separated.push_bind(code);
+            }
+            separated.push_unseparated(") GROUP BY u.id, u.code");
+
+            let counts: Vec<(String, i64)> = query
+                .build_query_as()
+                .fetch_all(&self.pool)
+                .await
+                .map_err(query_error)?;
+
+            for (code, count) in counts {
+                result.insert(code, count as u64);
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn stats_for_codes(
+        &self,
+        codes: &[String],
+    ) -> Result<HashMap<String, CodeStats>, DatabaseError> {
+        let mut result = HashMap::new();
+
+        for chunk in bind_chunks(codes) {
+            let mut query = QueryBuilder::<Postgres>::new(
+                "SELECT c.code, u.created_at, u.click_count, u.last_accessed_at \
+                 FROM all_short_codes c JOIN urls u ON u.id = c.target_id \
+                 WHERE c.code IN (",
+            );
+            let mut separated = query.separated(", ");
+            for code in chunk {
+                separated.push_bind(code);
+            }
+            separated.push_unseparated(")");
+
+            let found: Vec<(String, Option<DateTime<Utc>>, i64, Option<DateTime<Utc>>)> = query
+                .build_query_as()
+                .fetch_all(&self.pool)
+                .await
+                .map_err(query_error)?;
+
+            for (code, created_at, click_count, last_accessed_at) in found {
+                result.insert(
+                    code,
+                    CodeStats {
+                        created_at,
+                        click_count,
+                        last_accessed_at,
+                    },
+                );
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn delete_urls_batch(&self, codes: &[String]) -> Result<u64, DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+        let mut deleted = 0;
+
+        for chunk in bind_chunks(codes) {
+            let mut query = QueryBuilder::<Postgres>::new("DELETE FROM urls WHERE code IN (");
+            let mut separated = query.separated(", ");
+            for code in chunk {
+                separated.push_bind(code);
+            }
+            separated.push_unseparated(")");
+
+            deleted += query
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(query_error)?
+                .rows_affected();
+        }
+
+        tx.commit().await.map_err(query_error)?;
+        Ok(deleted)
+    }
+
+    async fn set_expiry_batch(
+        &self,
+        codes: &[String],
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<u64, DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+        let mut updated = 0;
+
+        for chunk in bind_chunks(codes) {
+            let mut query = QueryBuilder::<Postgres>::new("UPDATE urls SET expires_at = ");
+            query.push_bind(expires_at);
+            query.push(", updated_at = now() WHERE code IN (");
+            let mut separated = query.separated(", ");
+            for code in chunk {
+                separated.push_bind(code);
+            }
+            separated.push_unseparated(")");
+
+            updated += query
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(query_error)?
+                .rows_affected();
+        }
+
+        tx.commit().await.map_err(query_error)?;
+        Ok(updated)
+    }
+
+    async fn exists_many(&self, codes: &[String]) -> Result<HashMap<String, bool>, DatabaseError> {
+        let mut result: HashMap<String, bool> =
+            codes.iter().map(|code| (code.clone(), false)).collect();
+
+        for chunk in bind_chunks(codes) {
+            let mut query =
+                QueryBuilder::<Postgres>::new("SELECT code FROM all_short_codes WHERE code IN (");
+            let mut separated = query.separated(", ");
+            for code in chunk {
+                separated.push_bind(code);
+            }
+            separated.push_unseparated(")");
+
+            let found: Vec<String> = query
+                .build_query_scalar()
+                .fetch_all(&self.pool)
+                .await
+                .map_err(query_error)?;
+
+            for code in found {
+                result.insert(code, true);
+            }
+        }
+
+        Ok(result)
+    }
+
     async fn insert_alias(&self, alias_code: &str, code_id: i64) -> Result<(), DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
         sqlx::query("INSERT INTO aliases (alias, target_id) VALUES ($1, $2)")
             .bind(alias_code)
             .bind(code_id)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await
             .map_err(|e| {
                 if is_unique_violation(&e) {
                     DatabaseError::Duplicate
+                } else if is_foreign_key_violation(&e) {
+                    DatabaseError::InvalidReference
                 } else {
                     DatabaseError::QueryError(e.to_string())
                 }
             })?;
+        sqlx::query("UPDATE urls SET updated_at = now() WHERE id = $1")
+            .bind(code_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(query_error)?;
+        tx.commit().await.map_err(query_error)
+    }
+
+    async fn increment_click_count_by(&self, code: &str, by: u64) -> Result<(), DatabaseError> {
+        sqlx::query(&format!(
+            "UPDATE urls SET click_count = click_count + $1, last_accessed_at = now() \
+             WHERE id = (SELECT target_id FROM all_short_codes WHERE code = $2 \
+                         ORDER BY {} LIMIT 1)",
+            source_order(self.precedence)
+        ))
+        .bind(by as i64)
+        .bind(code)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+
         Ok(())
     }
 
-    async fn load_bloom_snapshot(&self, name: &str) -> Result<Option<Vec<u8>>, DatabaseError> {
-        let data = sqlx::query_scalar::<_, Vec<u8>>(
-            "SELECT data FROM bloom_snapshots WHERE name = $1 LIMIT 1",
+    async fn increment_clicks_batch(
+        &self,
+        increments: &[(String, u64)],
+    ) -> Result<(), DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+
+        for chunk in bind_chunks(increments) {
+            let mut lookup = QueryBuilder::<Postgres>::new(
+                "SELECT code, target_id, source FROM all_short_codes WHERE code IN (",
+            );
+            let mut separated = lookup.separated(", ");
+            for (code, _) in chunk {
+                separated.push_bind(code);
+            }
+            separated.push_unseparated(")");
+            let rows: Vec<(String, i64, String)> = lookup
+                .build_query_as()
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(query_error)?;
+
+            // Overlapping codes count for the link their lookup would resolve to
+            let mut targets: HashMap<String, (u8, i64)> = HashMap::new();
+            for (code, target_id, source) in rows {
+                let rank = source_rank(self.precedence, &source);
+                match targets.get(&code) {
+                    Some((best, _)) if *best <= rank => {}
+                    _ => {
+                        targets.insert(code, (rank, target_id));
+                    }
+                }
+            }
+            let mut deltas: HashMap<i64, i64> = HashMap::new();
+            for (code, by) in chunk {
+                if let Some((_, target_id)) = targets.get(code) {
+                    *deltas.entry(*target_id).or_default() += *by as i64;
+                }
+            }
+            if deltas.is_empty() {
+                continue;
+            }
+
+            let mut update = QueryBuilder::<Postgres>::new(
+                "UPDATE urls SET click_count = click_count + CASE id",
+            );
+            for (id, by) in &deltas {
+                update
+                    .push(" WHEN ")
+                    .push_bind(*id)
+                    .push(" THEN ")
+                    .push_bind(*by);
+            }
+            update.push(" ELSE 0 END, last_accessed_at = now() WHERE id IN (");
+            let mut separated = update.separated(", ");
+            for id in deltas.keys() {
+                separated.push_bind(*id);
+            }
+            separated.push_unseparated(")");
+            update
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(query_error)?;
+        }
+
+        tx.commit().await.map_err(query_error)
+    }
+
+    async fn get_url_and_count(&self, code: &str) -> Result<String, DatabaseError> {
+        let url: Option<String> = sqlx::query_scalar(&format!(
+            "UPDATE urls SET click_count = click_count + 1, last_accessed_at = now() \
+             WHERE id = (SELECT target_id FROM all_short_codes WHERE code = $1 \
+                         ORDER BY {} LIMIT 1) \
+               AND (max_clicks IS NULL OR click_count < max_clicks) \
+             RETURNING url",
+            source_order(self.precedence)
+        ))
+        .bind(code)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        match url {
+            Some(url) => Ok(url),
+            None => self
+                .get_id_by_code(code)
+                .await
+                .and(Err(DatabaseError::QuotaExceeded)),
+        }
+    }
+
+    async fn resolve_and_track(
+        &self,
+        code: &str,
+        referrer: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<String, DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+        let row: Option<(i64, String, Option<DateTime<Utc>>, Option<String>)> =
+            sqlx::query_as(&format!(
+                r#"
+                    SELECT u.id, u.url, COALESCE(a.expires_at, u.expires_at), u.blocked_reason
+                    FROM all_short_codes c
+                    JOIN urls u ON u.id = c.target_id
+                    LEFT JOIN aliases a ON c.source = 'alias' AND a.alias = c.code
+                    WHERE c.code = $1
+                    ORDER BY {}
+                    LIMIT 1
+                "#,
+                source_order(self.precedence)
+            ))
+            .bind(code)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(query_error)?;
+        let (url_id, url, expires_at, blocked_reason) = row.ok_or(DatabaseError::NotFound)?;
+        check_servable(expires_at, blocked_reason)?;
+
+        let counted = sqlx::query(
+            "UPDATE urls SET click_count = click_count + 1, last_accessed_at = now() \
+             WHERE id = $1 AND (max_clicks IS NULL OR click_count < max_clicks)",
+        )
+        .bind(url_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(query_error)?;
+        if counted.rows_affected() == 0 {
+            return Err(DatabaseError::QuotaExceeded);
+        }
+
+        sqlx::query(
+            "INSERT INTO click_events (url_id, code, referrer, user_agent) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(url_id)
+        .bind(code)
+        .bind(referrer)
+        .bind(user_agent)
+        .execute(&mut *tx)
+        .await
+        .map_err(query_error)?;
+
+        tx.commit().await.map_err(query_error)?;
+        Ok(url)
+    }
+
+    async fn is_empty(&self) -> Result<bool, DatabaseError> {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM urls LIMIT 1)")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(query_error)?;
+
+        Ok(!exists)
+    }
+
+    async fn aggregate_stats(&self) -> Result<AggregateStats, DatabaseError> {
+        sqlx::query_as::<_, AggregateStats>(
+            r#"
+                SELECT
+                    (SELECT COUNT(*) FROM urls) AS total_links,
+                    (SELECT COUNT(*) FROM aliases) AS total_aliases,
+                    (SELECT COALESCE(SUM(click_count), 0)::BIGINT FROM urls) AS total_clicks,
+                    (SELECT COUNT(*) FROM urls
+                        WHERE blocked_reason IS NOT NULL
+                           OR (expires_at IS NOT NULL AND expires_at <= NOW())) AS expired_links
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(query_error)
+    }
+
+    async fn save_stats_snapshot(&self, stats: &AggregateStats) -> Result<(), DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+
+        sqlx::query(
+            "INSERT INTO stats_snapshots (total_links, total_aliases, total_clicks, expired_links) \
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(stats.total_links)
+        .bind(stats.total_aliases)
+        .bind(stats.total_clicks)
+        .bind(stats.expired_links)
+        .execute(&mut *tx)
+        .await
+        .map_err(query_error)?;
+
+        sqlx::query(
+            "DELETE FROM stats_snapshots WHERE id <= \
+             (SELECT id FROM stats_snapshots ORDER BY id DESC LIMIT 1 OFFSET $1)",
+        )
+        .bind(STATS_SNAPSHOT_HISTORY)
+        .execute(&mut *tx)
+        .await
+        .map_err(query_error)?;
+
+        tx.commit().await.map_err(query_error)
+    }
+
+    async fn latest_stats(&self) -> Result<AggregateStats, DatabaseError> {
+        sqlx::query_as::<_, AggregateStats>(
+            "SELECT total_links, total_aliases, total_clicks, expired_links \
+             FROM stats_snapshots ORDER BY id DESC LIMIT 1",
         )
-        .bind(name)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+        .map_err(query_error)?
+        .ok_or(DatabaseError::NotFound)
+    }
+
+    async fn find_hash_collisions(&self) -> Result<Vec<(Vec<u8>, Vec<String>)>, DatabaseError> {
+        let rows: Vec<(Vec<u8>, String)> = sqlx::query_as(
+            r#"
+                SELECT DISTINCT url_hash, url
+                FROM urls
+                WHERE url_hash IN (
+                    SELECT url_hash FROM urls
+                    GROUP BY url_hash
+                    HAVING COUNT(DISTINCT url) > 1
+                )
+                ORDER BY url_hash, url
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        Ok(group_by_hash(rows))
+    }
+
+    async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    async fn load_bloom_snapshot(&self, name: &str) -> Result<Option<Vec<u8>>, DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+        // One snapshot for the header and every chunk read
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ")
+            .execute(&mut *tx)
+            .await
+            .map_err(query_error)?;
+
+        let header: Option<(Vec<u8>, i32)> =
+            sqlx::query_as("SELECT data, chunk_count FROM bloom_snapshots WHERE name = $1 LIMIT 1")
+                .bind(name)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(query_error)?;
+
+        let Some((data, chunk_count)) = header else {
+            return Ok(None);
+        };
+        if chunk_count == 0 {
+            return Ok(Some(data));
+        }
 
-        Ok(data)
+        // Read chunk by chunk so only the reassembled buffer is held
+        let mut data = Vec::new();
+        for index in 0..chunk_count {
+            let chunk: Vec<u8> = sqlx::query_scalar(
+                "SELECT data FROM bloom_snapshot_chunks WHERE name = $1 AND chunk_index = $2",
+            )
+            .bind(name)
+            .bind(index)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(query_error)?
+            .ok_or_else(|| {
+                DatabaseError::QueryError(format!("bloom snapshot {name} is missing chunk {index}"))
+            })?;
+            data.extend_from_slice(&chunk);
+        }
+        Ok(Some(data))
     }
 
     async fn save_bloom_snapshot(&self, name: &str, data: &[u8]) -> Result<(), DatabaseError> {
+        let chunks: Vec<&[u8]> = if data.len() > self.snapshot_chunk_bytes {
+            data.chunks(self.snapshot_chunk_bytes).collect()
+        } else {
+            Vec::new()
+        };
+        let inline: &[u8] = if chunks.is_empty() { data } else { &[] };
+
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+
         sqlx::query(
             r#"
-                INSERT INTO bloom_snapshots (name, data)
-                VALUES ($1, $2)
+                INSERT INTO bloom_snapshots (name, data, chunk_count)
+                VALUES ($1, $2, $3)
                 ON CONFLICT (name)
                 DO UPDATE
                 SET data = EXCLUDED.data,
+                    chunk_count = EXCLUDED.chunk_count,
                     updated_at = NOW()
             "#,
         )
         .bind(name)
-        .bind(data)
+        .bind(inline)
+        .bind(chunks.len() as i32)
+        .execute(&mut *tx)
+        .await
+        .map_err(query_error)?;
+
+        sqlx::query("DELETE FROM bloom_snapshot_chunks WHERE name = $1")
+            .bind(name)
+            .execute(&mut *tx)
+            .await
+            .map_err(query_error)?;
+
+        for (index, chunk) in chunks.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO bloom_snapshot_chunks (name, chunk_index, data) VALUES ($1, $2, $3)",
+            )
+            .bind(name)
+            .bind(index as i32)
+            .bind(*chunk)
+            .execute(&mut *tx)
+            .await
+            .map_err(query_error)?;
+        }
+
+        tx.commit().await.map_err(query_error)
+    }
+
+    async fn prune_bloom_snapshots(
+        &self,
+        keep_latest_per_name: usize,
+    ) -> Result<u64, DatabaseError> {
+        let result = sqlx::query(
+            r#"
+                DELETE FROM bloom_snapshots
+                WHERE ctid IN (
+                    SELECT ctid FROM (
+                        SELECT ctid, ROW_NUMBER() OVER (
+                            PARTITION BY name ORDER BY updated_at DESC
+                        ) AS rank
+                        FROM bloom_snapshots
+                    ) ranked
+                    WHERE rank > $1
+                )
+            "#,
+        )
+        .bind(keep_latest_per_name as i64)
         .execute(&self.pool)
         .await
-        .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
-        Ok(())
+        .map_err(query_error)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn storage_stats(&self) -> Result<StorageStats, DatabaseError> {
+        let tables = sqlx::query_as::<_, (String, i64)>(&table_counts_query())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(query_error)?
+            .into_iter()
+            .collect();
+        let database_bytes: i64 = sqlx::query_scalar("SELECT pg_database_size(current_database())")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(query_error)?;
+        let bloom_snapshot_bytes: i64 = sqlx::query_scalar(
+            "SELECT (SELECT COALESCE(SUM(octet_length(data)), 0)::BIGINT FROM bloom_snapshots) \
+                  + (SELECT COALESCE(SUM(octet_length(data)), 0)::BIGINT FROM bloom_snapshot_chunks)",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        Ok(StorageStats {
+            tables,
+            database_bytes,
+            bloom_snapshot_bytes,
+        })
+    }
+
+    async fn self_check(&self) -> Result<SelfCheckReport, DatabaseError> {
+        let mut report = SelfCheckReport::default();
+
+        for (table, index) in schema::EXPECTED_INDEXES {
+            let found: Option<String> = sqlx::query_scalar(
+                "SELECT indexname::text FROM pg_indexes \
+                 WHERE schemaname = current_schema() AND tablename = $1 AND indexname = $2",
+            )
+            .bind(table)
+            .bind(index)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(query_error)?;
+            if found.is_none() {
+                report.push(format!("index `{index}` on `{table}` is missing"));
+            }
+        }
+
+        for (table, column) in schema::EXPECTED_UNIQUE {
+            let unique: i64 = sqlx::query_scalar(
+                r#"
+                    SELECT COUNT(*) FROM pg_index i
+                    JOIN pg_class t ON t.oid = i.indrelid
+                    JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = i.indkey[0]
+                    WHERE t.relname = $1
+                      AND t.relnamespace = (SELECT oid FROM pg_namespace WHERE nspname = current_schema())
+                      AND i.indisunique
+                      AND i.indnkeyatts = 1
+                      AND a.attname = $2
+                "#,
+            )
+            .bind(table)
+            .bind(column)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(query_error)?;
+            if unique == 0 {
+                report.push(format!("`{table}.{column}` is not unique"));
+            }
+        }
+
+        for (view, columns) in schema::EXPECTED_VIEWS {
+            let actual: Vec<String> = sqlx::query_scalar(
+                r#"
+                    SELECT c.column_name::text FROM information_schema.columns c
+                    JOIN information_schema.views v
+                      ON v.table_schema = c.table_schema AND v.table_name = c.table_name
+                    WHERE c.table_schema = current_schema() AND c.table_name = $1
+                    ORDER BY c.ordinal_position
+                "#,
+            )
+            .bind(view)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(query_error)?;
+            report.check_view(view, columns, &actual);
+        }
+
+        // Postgres always enforces declared foreign keys; check that they are declared
+        for (table, column, referenced) in schema::EXPECTED_FOREIGN_KEYS {
+            let found: i64 = sqlx::query_scalar(
+                r#"
+                    SELECT COUNT(*) FROM information_schema.table_constraints tc
+                    JOIN information_schema.key_column_usage kcu
+                      ON kcu.constraint_name = tc.constraint_name
+                     AND kcu.table_schema = tc.table_schema
+                    JOIN information_schema.constraint_column_usage ccu
+                      ON ccu.constraint_name = tc.constraint_name
+                     AND ccu.table_schema = tc.table_schema
+                    WHERE tc.constraint_type = 'FOREIGN KEY'
+                      AND tc.table_schema = current_schema()
+                      AND tc.table_name = $1
+                      AND kcu.column_name = $2
+                      AND ccu.table_name = $3
+                "#,
+            )
+            .bind(table)
+            .bind(column)
+            .bind(referenced)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(query_error)?;
+            if found == 0 {
+                report.push(format!(
+                    "foreign key `{table}.{column}` -> `{referenced}` is missing"
+                ));
+            }
+        }
+
+        Ok(report)
     }
 }
 /// Creates a PostgreSQL connection pool from configuration settings.
@@ -413,6 +1808,16 @@ fn is_unique_violation(e: &SqlxError) -> bool {
     }
 }
 
+/// Returns true if the provided `sqlx::Error` corresponds to a foreign key
+/// violation (PostgreSQL error code `23503`).
+fn is_foreign_key_violation(e: &SqlxError) -> bool {
+    if let SqlxError::Database(db_err) = e {
+        db_err.code().map(|c| c == "23503").unwrap_or(false)
+    } else {
+        false
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
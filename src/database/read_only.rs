@@ -0,0 +1,438 @@
+//! Maintenance-window decorator that rejects writes.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::{DatabaseError, SelfCheckReport, UrlDatabase};
+use crate::models::{
+    AggregateStats, CodeStats, ExportedLink, LinkSettings, PatternAlias, RedirectTarget,
+    StorageStats, UpsertResult, UrlRecord, Urls, UtmParams,
+};
+use chrono::{DateTime, Utc};
+
+/// Shared on/off switch for [`ReadOnlyUrlDatabase`].
+///
+/// Cloning shares the flag, so a handle kept in the application state flips
+/// read-only mode for the running database without a restart.
+#[derive(Clone, Debug, Default)]
+pub struct ReadOnlySwitch(Arc<AtomicBool>);
+
+impl ReadOnlySwitch {
+    pub fn new(enabled: bool) -> Self {
+        Self(Arc::new(AtomicBool::new(enabled)))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    pub fn set(&self, enabled: bool) {
+        self.0.store(enabled, Ordering::Release);
+    }
+}
+
+/// Rejects every mutation with [`DatabaseError::ReadOnly`] while its
+/// [`ReadOnlySwitch`] is on; reads are forwarded unchanged.
+///
+/// Click counting and Bloom snapshot saves are mutations too, so redirects
+/// keep working but are not counted during a maintenance window. Flushing a
+/// wrapped buffer is still allowed.
+pub struct ReadOnlyUrlDatabase<D> {
+    inner: D,
+    switch: ReadOnlySwitch,
+}
+
+impl<D: UrlDatabase> ReadOnlyUrlDatabase<D> {
+    pub fn new(inner: D, switch: ReadOnlySwitch) -> Self {
+        Self { inner, switch }
+    }
+
+    fn writable(&self) -> Result<(), DatabaseError> {
+        if self.switch.is_enabled() {
+            Err(DatabaseError::ReadOnly)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[async_trait]
+impl<D: UrlDatabase> UrlDatabase for ReadOnlyUrlDatabase<D> {
+    async fn insert_url(
+        &self,
+        code: &str,
+        url: &str,
+    ) -> Result<(UpsertResult, Urls), DatabaseError> {
+        self.writable()?;
+        self.inner.insert_url(code, url).await
+    }
+
+    async fn insert_url_with_settings(
+        &self,
+        code: &str,
+        url: &str,
+        settings: &LinkSettings,
+    ) -> Result<(UpsertResult, Urls), DatabaseError> {
+        self.writable()?;
+        self.inner
+            .insert_url_with_settings(code, url, settings)
+            .await
+    }
+
+    async fn insert_alias(&self, alias_code: &str, code_id: i64) -> Result<(), DatabaseError> {
+        self.writable()?;
+        self.inner.insert_alias(alias_code, code_id).await
+    }
+
+    async fn get_id_by_url(&self, url: &str) -> Result<Urls, DatabaseError> {
+        self.inner.get_id_by_url(url).await
+    }
+
+    async fn codes_for_urls(
+        &self,
+        urls: &[String],
+    ) -> Result<HashMap<String, Urls>, DatabaseError> {
+        self.inner.codes_for_urls(urls).await
+    }
+
+    async fn alias_counts(&self, codes: &[String]) -> Result<HashMap<String, u64>, DatabaseError> {
+        self.inner.alias_counts(codes).await
+    }
+
+    async fn stats_for_codes(
+        &self,
+        codes: &[String],
+    ) -> Result<HashMap<String, CodeStats>, DatabaseError> {
+        self.inner.stats_for_codes(codes).await
+    }
+
+    async fn next_hop(&self, code: &str) -> Result<Option<String>, DatabaseError> {
+        self.inner.next_hop(code).await
+    }
+
+    async fn get_id_by_code(&self, code: &str) -> Result<i64, DatabaseError> {
+        self.inner.get_id_by_code(code).await
+    }
+
+    async fn get_url(&self, id: &str) -> Result<String, DatabaseError> {
+        self.inner.get_url(id).await
+    }
+
+    async fn get_redirect_target(&self, code: &str) -> Result<RedirectTarget, DatabaseError> {
+        self.inner.get_redirect_target(code).await
+    }
+
+    async fn set_link_utm(&self, url_id: i64, utm: &UtmParams) -> Result<(), DatabaseError> {
+        self.writable()?;
+        self.inner.set_link_utm(url_id, utm).await
+    }
+
+    async fn set_max_clicks(
+        &self,
+        url_id: i64,
+        max_clicks: Option<u64>,
+    ) -> Result<(), DatabaseError> {
+        self.writable()?;
+        self.inner.set_max_clicks(url_id, max_clicks).await
+    }
+
+    async fn set_skip_interstitial(&self, url_id: i64, skip: bool) -> Result<(), DatabaseError> {
+        self.writable()?;
+        self.inner.set_skip_interstitial(url_id, skip).await
+    }
+
+    async fn set_link_metadata(
+        &self,
+        url_id: i64,
+        key: &str,
+        value: &str,
+    ) -> Result<(), DatabaseError> {
+        self.writable()?;
+        self.inner.set_link_metadata(url_id, key, value).await
+    }
+
+    async fn distinct_metadata_keys(&self) -> Result<Vec<String>, DatabaseError> {
+        self.inner.distinct_metadata_keys().await
+    }
+
+    async fn distinct_metadata_values(&self, key: &str) -> Result<Vec<String>, DatabaseError> {
+        self.inner.distinct_metadata_values(key).await
+    }
+
+    async fn set_link_owner(&self, url_id: i64, owner_key_id: &str) -> Result<(), DatabaseError> {
+        self.writable()?;
+        self.inner.set_link_owner(url_id, owner_key_id).await
+    }
+
+    async fn reassign_owner(&self, from_owner: &str, to_owner: &str) -> Result<u64, DatabaseError> {
+        self.writable()?;
+        self.inner.reassign_owner(from_owner, to_owner).await
+    }
+
+    async fn get_url_admin(&self, code: &str) -> Result<UrlRecord, DatabaseError> {
+        self.inner.get_url_admin(code).await
+    }
+
+    async fn urls_by_owner_between(
+        &self,
+        owner_key_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        self.inner
+            .urls_by_owner_between(owner_key_id, from, to, offset, limit)
+            .await
+    }
+
+    async fn unclicked_urls(
+        &self,
+        older_than: DateTime<Utc>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        self.inner.unclicked_urls(older_than, offset, limit).await
+    }
+
+    async fn changed_since(
+        &self,
+        since: DateTime<Utc>,
+        after_id: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        self.inner.changed_since(since, after_id, limit).await
+    }
+
+    async fn recently_accessed(&self, limit: u64) -> Result<Vec<UrlRecord>, DatabaseError> {
+        self.inner.recently_accessed(limit).await
+    }
+
+    async fn export_owner_page(
+        &self,
+        owner_key_id: &str,
+        after_id: i64,
+        limit: u64,
+    ) -> Result<Vec<ExportedLink>, DatabaseError> {
+        self.inner
+            .export_owner_page(owner_key_id, after_id, limit)
+            .await
+    }
+
+    async fn find_by_host(
+        &self,
+        host: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        self.inner.find_by_host(host, offset, limit).await
+    }
+
+    async fn insert_pattern_alias(
+        &self,
+        pattern: &str,
+        target: &str,
+    ) -> Result<PatternAlias, DatabaseError> {
+        self.writable()?;
+        self.inner.insert_pattern_alias(pattern, target).await
+    }
+
+    async fn list_pattern_aliases(&self) -> Result<Vec<PatternAlias>, DatabaseError> {
+        self.inner.list_pattern_aliases().await
+    }
+
+    async fn retarget_alias(&self, alias: &str, new_code: &str) -> Result<(), DatabaseError> {
+        self.writable()?;
+        self.inner.retarget_alias(alias, new_code).await
+    }
+
+    async fn swap_codes(&self, code_a: &str, code_b: &str) -> Result<(), DatabaseError> {
+        self.writable()?;
+        self.inner.swap_codes(code_a, code_b).await
+    }
+
+    async fn block_link(&self, code: &str, reason: &str) -> Result<(), DatabaseError> {
+        self.writable()?;
+        self.inner.block_link(code, reason).await
+    }
+
+    async fn unblock_link(&self, code: &str) -> Result<(), DatabaseError> {
+        self.writable()?;
+        self.inner.unblock_link(code).await
+    }
+
+    async fn set_featured(&self, code: &str, featured: bool) -> Result<(), DatabaseError> {
+        self.writable()?;
+        self.inner.set_featured(code, featured).await
+    }
+
+    async fn list_featured(&self, limit: u64) -> Result<Vec<UrlRecord>, DatabaseError> {
+        self.inner.list_featured(limit).await
+    }
+
+    async fn list_short_codes(
+        &self,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<String>, DatabaseError> {
+        self.inner.list_short_codes(offset, limit).await
+    }
+
+    async fn list_short_codes_after(
+        &self,
+        after: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<String>, DatabaseError> {
+        self.inner.list_short_codes_after(after, limit).await
+    }
+
+    async fn list_short_codes_with_prefix(
+        &self,
+        prefix: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<String>, DatabaseError> {
+        self.inner
+            .list_short_codes_with_prefix(prefix, offset, limit)
+            .await
+    }
+
+    async fn exists_many(&self, codes: &[String]) -> Result<HashMap<String, bool>, DatabaseError> {
+        self.inner.exists_many(codes).await
+    }
+
+    async fn delete_urls_batch(&self, codes: &[String]) -> Result<u64, DatabaseError> {
+        self.writable()?;
+        self.inner.delete_urls_batch(codes).await
+    }
+
+    async fn set_expiry_batch(
+        &self,
+        codes: &[String],
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<u64, DatabaseError> {
+        self.writable()?;
+        self.inner.set_expiry_batch(codes, expires_at).await
+    }
+
+    async fn increment_click_count(&self, code: &str) -> Result<(), DatabaseError> {
+        self.writable()?;
+        self.inner.increment_click_count(code).await
+    }
+
+    async fn increment_click_count_by(&self, code: &str, by: u64) -> Result<(), DatabaseError> {
+        self.writable()?;
+        self.inner.increment_click_count_by(code, by).await
+    }
+
+    async fn increment_clicks_batch(
+        &self,
+        increments: &[(String, u64)],
+    ) -> Result<(), DatabaseError> {
+        self.writable()?;
+        self.inner.increment_clicks_batch(increments).await
+    }
+
+    async fn get_url_and_count(&self, code: &str) -> Result<String, DatabaseError> {
+        self.writable()?;
+        self.inner.get_url_and_count(code).await
+    }
+
+    async fn resolve_and_track(
+        &self,
+        code: &str,
+        referrer: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<String, DatabaseError> {
+        self.writable()?;
+        self.inner
+            .resolve_and_track(code, referrer, user_agent)
+            .await
+    }
+
+    async fn is_empty(&self) -> Result<bool, DatabaseError> {
+        self.inner.is_empty().await
+    }
+
+    async fn aggregate_stats(&self) -> Result<AggregateStats, DatabaseError> {
+        self.inner.aggregate_stats().await
+    }
+
+    async fn save_stats_snapshot(&self, stats: &AggregateStats) -> Result<(), DatabaseError> {
+        self.writable()?;
+        self.inner.save_stats_snapshot(stats).await
+    }
+
+    async fn latest_stats(&self) -> Result<AggregateStats, DatabaseError> {
+        self.inner.latest_stats().await
+    }
+
+    async fn find_hash_collisions(&self) -> Result<Vec<(Vec<u8>, Vec<String>)>, DatabaseError> {
+        self.inner.find_hash_collisions().await
+    }
+
+    async fn load_bloom_snapshot(&self, name: &str) -> Result<Option<Vec<u8>>, DatabaseError> {
+        self.inner.load_bloom_snapshot(name).await
+    }
+
+    async fn save_bloom_snapshot(&self, name: &str, data: &[u8]) -> Result<(), DatabaseError> {
+        self.writable()?;
+        self.inner.save_bloom_snapshot(name, data).await
+    }
+
+    async fn prune_bloom_snapshots(
+        &self,
+        keep_latest_per_name: usize,
+    ) -> Result<u64, DatabaseError> {
+        self.writable()?;
+        self.inner.prune_bloom_snapshots(keep_latest_per_name).await
+    }
+
+    async fn self_check(&self) -> Result<SelfCheckReport, DatabaseError> {
+        self.inner.self_check().await
+    }
+
+    async fn storage_stats(&self) -> Result<StorageStats, DatabaseError> {
+        self.inner.storage_stats().await
+    }
+
+    async fn flush(&self) -> Result<(), DatabaseError> {
+        self.inner.flush().await
+    }
+
+    async fn close(&self) {
+        self.inner.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::sqlite::test_db;
+
+    #[tokio::test]
+    async fn switch_blocks_writes_but_not_reads() {
+        let switch = ReadOnlySwitch::default();
+        let db = ReadOnlyUrlDatabase::new(test_db().await, switch.clone());
+        let (upsert, _) = db
+            .insert_url("Abc1234", "https://example.com/")
+            .await
+            .unwrap();
+
+        switch.set(true);
+        assert!(matches!(
+            db.insert_url("Def5678", "https://example.com/other").await,
+            Err(DatabaseError::ReadOnly)
+        ));
+        assert!(matches!(
+            db.insert_alias("myAlias", upsert.id).await,
+            Err(DatabaseError::ReadOnly)
+        ));
+        assert_eq!(db.get_url("Abc1234").await.unwrap(), "https://example.com/");
+
+        switch.set(false);
+        db.insert_alias("myAlias", upsert.id).await.unwrap();
+    }
+}
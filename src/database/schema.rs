@@ -0,0 +1,84 @@
+//! Expected schema objects and the boot-time self-check report.
+//!
+//! Every object [`UrlDatabase::self_check`](super::UrlDatabase::self_check)
+//! verifies is listed here, shared by all backends. Add new indexes, views or
+//! foreign keys to these lists alongside the migration that creates them.
+
+use std::fmt;
+
+/// Indexes that must exist, as `(table, index name)`.
+pub const EXPECTED_INDEXES: &[(&str, &str)] = &[
+    ("aliases", "aliases_target_id_idx"),
+    ("urls", "urls_owner_created_idx"),
+    ("urls", "urls_host_idx"),
+    ("urls", "urls_unclicked_idx"),
+    ("urls", "urls_last_accessed_idx"),
+    ("urls", "urls_featured_idx"),
+    ("urls", "urls_updated_idx"),
+    ("url_metadata", "url_metadata_key_value_idx"),
+    ("click_events", "click_events_url_clicked_idx"),
+];
+
+/// Single-column uniqueness that must be enforced, as `(table, column)`.
+///
+/// Checked by column rather than index name because the backing index is
+/// named by the backend (`sqlite_autoindex_*`, `*_key`).
+pub const EXPECTED_UNIQUE: &[(&str, &str)] = &[("urls", "code"), ("urls", "url_hash")];
+
+/// Views and their exact column list, in order.
+pub const EXPECTED_VIEWS: &[(&str, &[&str])] =
+    &[("all_short_codes", &["code", "target_id", "url", "source"])];
+
+/// Foreign keys that must exist, as `(table, column, referenced table)`.
+pub const EXPECTED_FOREIGN_KEYS: &[(&str, &str, &str)] = &[
+    ("aliases", "target_id", "urls"),
+    ("bloom_snapshot_chunks", "name", "bloom_snapshots"),
+    ("url_metadata", "url_id", "urls"),
+    ("click_events", "url_id", "urls"),
+];
+
+/// Discrepancies found by a schema self-check; empty when the schema matches.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SelfCheckReport {
+    pub discrepancies: Vec<String>,
+}
+
+impl SelfCheckReport {
+    pub fn is_ok(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+
+    pub(crate) fn push(&mut self, discrepancy: impl Into<String>) {
+        self.discrepancies.push(discrepancy.into());
+    }
+
+    /// Compares a view's actual columns with the expected list.
+    pub(crate) fn check_view(&mut self, view: &str, expected: &[&str], actual: &[String]) {
+        if actual.is_empty() {
+            self.push(format!("view `{view}` is missing"));
+        } else if actual
+            .iter()
+            .map(String::as_str)
+            .ne(expected.iter().copied())
+        {
+            self.push(format!(
+                "view `{view}` has columns [{}], expected [{}]",
+                actual.join(", "),
+                expected.join(", ")
+            ));
+        }
+    }
+}
+
+impl fmt::Display for SelfCheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            return write!(f, "schema self-check passed");
+        }
+        write!(
+            f,
+            "schema self-check found: {}",
+            self.discrepancies.join("; ")
+        )
+    }
+}
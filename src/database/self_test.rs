@@ -0,0 +1,135 @@
+//! End-to-end smoke test of the link pipeline; see
+//! [`UrlDatabase::self_test`](super::UrlDatabase::self_test).
+
+use std::future::Future;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use super::{DatabaseError, UrlDatabase};
+
+/// Prefix of the throwaway codes; `~` is outside every code alphabet, so no
+/// generated code or user alias can collide with it.
+pub const SELF_TEST_PREFIX: &str = "~selftest-";
+
+/// Host of the throwaway destinations; `.invalid` never resolves.
+const SELF_TEST_HOST: &str = "self-test.invalid";
+
+/// Outcome of one operation of a self-test.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SelfTestStep {
+    pub name: &'static str,
+    pub passed: bool,
+    pub elapsed_ms: f64,
+    /// Why the step failed, or that it was skipped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Every step of a self-test, in the order they ran.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SelfTestReport {
+    /// The throwaway code the test used
+    pub code: String,
+    pub steps: Vec<SelfTestStep>,
+}
+
+impl SelfTestReport {
+    pub fn is_ok(&self) -> bool {
+        self.steps.iter().all(|step| step.passed)
+    }
+
+    async fn step<F>(&mut self, name: &'static str, op: F) -> bool
+    where
+        F: Future<Output = Result<(), String>>,
+    {
+        let started = Instant::now();
+        let result = op.await;
+        let passed = result.is_ok();
+        self.steps.push(SelfTestStep {
+            name,
+            passed,
+            elapsed_ms: started.elapsed().as_secs_f64() * 1_000.0,
+            error: result.err(),
+        });
+        passed
+    }
+
+    fn skip(&mut self, name: &'static str, after: &str) {
+        self.steps.push(SelfTestStep {
+            name,
+            passed: false,
+            elapsed_ms: 0.0,
+            error: Some(format!("skipped: {after} failed")),
+        });
+    }
+}
+
+pub(super) async fn run<D: UrlDatabase + ?Sized>(db: &D) -> Result<SelfTestReport, DatabaseError> {
+    let nonce = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let code = format!("{SELF_TEST_PREFIX}{nonce:x}");
+    let url = format!("https://{SELF_TEST_HOST}/{code}");
+    let mut report = SelfTestReport {
+        code: code.clone(),
+        steps: Vec::with_capacity(4),
+    };
+
+    let inserted = report
+        .step("insert", async {
+            let (result, stored) = db
+                .insert_url(&code, &url)
+                .await
+                .map_err(|e| e.to_string())?;
+            match (result.created, stored.code == code) {
+                (true, true) => Ok(()),
+                _ => Err(format!("link was not created, got `{}`", stored.code)),
+            }
+        })
+        .await;
+
+    if inserted {
+        report
+            .step("read", async {
+                match db.get_url(&code).await.map_err(|e| e.to_string())? {
+                    stored if stored == url => Ok(()),
+                    stored => Err(format!("read back `{stored}`")),
+                }
+            })
+            .await;
+        report
+            .step("resolve", async {
+                let target = db
+                    .get_redirect_target(&code)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                match (target.url == url, target.blocked_reason) {
+                    (true, None) => Ok(()),
+                    (false, _) => Err(format!("resolved to `{}`", target.url)),
+                    (true, Some(reason)) => Err(format!("resolved as blocked: {reason}")),
+                }
+            })
+            .await;
+    } else {
+        report.skip("read", "insert");
+        report.skip("resolve", "insert");
+    }
+
+    // Runs even when the insert failed, in case it failed after writing.
+    report
+        .step("delete", async {
+            let deleted = db
+                .delete_urls_batch(std::slice::from_ref(&code))
+                .await
+                .map_err(|e| e.to_string())?;
+            match (deleted, inserted) {
+                (1, _) | (0, false) => Ok(()),
+                (n, _) => Err(format!("deleted {n} links")),
+            }
+        })
+        .await;
+
+    Ok(report)
+}
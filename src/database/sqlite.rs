@@ -49,13 +49,24 @@
 //! # }
 //! ```
 
-use super::{DatabaseError, UrlDatabase};
-use crate::configuration::DatabaseSettings;
-use crate::models::{UpsertResult, Urls};
+use super::{
+    DEFAULT_MAX_OFFSET, DEFAULT_MAX_PAGE_SIZE, DEFAULT_SNAPSHOT_CHUNK_BYTES, DatabaseError,
+    PendingMigration, STATS_SNAPSHOT_HISTORY, SelfCheckReport, UrlDatabase, assemble_export,
+    bind_chunks, check_offset, check_owner_key_id, check_page_size, check_servable, escape_like,
+    group_by_hash, hash_normalized_urls, host_filter, pending_migrations, query_error, schema,
+    sha256_bytes, source_order, source_rank, table_counts_query, url_host,
+};
+use crate::configuration::{CodePrecedence, DatabaseSettings, PrivacySettings};
+use crate::models::{
+    AggregateStats, CodeStats, ExportedLink, LinkSettings, PatternAlias, RedirectTarget,
+    StorageStats, UpsertResult, UrlRecord, Urls, UtmParams,
+};
 use async_trait::async_trait;
-use sha2::{Digest, Sha256};
+use chrono::{DateTime, Utc};
+use sqlx::migrate::Migrator;
 use sqlx::sqlite::SqlitePoolOptions;
-use sqlx::{SqlitePool, sqlite::SqliteConnectOptions};
+use sqlx::{QueryBuilder, Sqlite, SqlitePool, sqlite::SqliteConnectOptions};
+use std::collections::HashMap;
 use std::str::FromStr;
 
 const MAX_CAP: u32 = 64;
@@ -94,6 +105,16 @@ const MIN_CAP: u32 = 1;
 pub struct SqliteUrlDatabase {
     /// SQLite connection pool for database operations
     pool: SqlitePool,
+    /// Bloom snapshots above this size are stored in chunks of this size
+    snapshot_chunk_bytes: usize,
+    /// Resolves codes that exist both as primary code and alias
+    precedence: CodePrecedence,
+    /// Largest `OFFSET` the offset listings accept; `0` for no limit
+    max_offset: u64,
+    /// Largest `LIMIT` the caller-facing listings accept; `0` for no limit
+    max_page_size: u64,
+    /// Tracking parameters stripped from destinations before they are hashed
+    privacy: PrivacySettings,
 }
 
 impl SqliteUrlDatabase {
@@ -116,7 +137,50 @@ impl SqliteUrlDatabase {
     /// # }
     /// ```
     pub fn new(pool: SqlitePool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            snapshot_chunk_bytes: DEFAULT_SNAPSHOT_CHUNK_BYTES,
+            precedence: CodePrecedence::default(),
+            max_offset: DEFAULT_MAX_OFFSET,
+            max_page_size: DEFAULT_MAX_PAGE_SIZE,
+            privacy: PrivacySettings::default(),
+        }
+    }
+
+    /// Overrides the size above which bloom snapshots are chunked
+    /// (default [`DEFAULT_SNAPSHOT_CHUNK_BYTES`]).
+    pub fn with_snapshot_chunk_bytes(mut self, bytes: usize) -> Self {
+        self.snapshot_chunk_bytes = bytes.max(1);
+        self
+    }
+
+    /// Overrides which side wins for codes that are both a primary code and
+    /// an alias (default [`CodePrecedence::Primary`]).
+    pub fn with_code_precedence(mut self, precedence: CodePrecedence) -> Self {
+        self.precedence = precedence;
+        self
+    }
+
+    /// Overrides the largest `OFFSET` [`UrlDatabase::list_short_codes`]
+    /// accepts (default [`DEFAULT_MAX_OFFSET`]); `0` removes the limit.
+    pub fn with_max_offset(mut self, max_offset: u64) -> Self {
+        self.max_offset = max_offset;
+        self
+    }
+
+    /// Overrides the largest `LIMIT` the listings accept (default
+    /// [`DEFAULT_MAX_PAGE_SIZE`]); `0` removes the limit.
+    pub fn with_max_page_size(mut self, max_page_size: u64) -> Self {
+        self.max_page_size = max_page_size;
+        self
+    }
+
+    /// Sets the `privacy` settings the create endpoints store destinations
+    /// under, so [`UrlDatabase::codes_for_urls`] hashes inputs the same way
+    /// (default: no stripping).
+    pub fn with_privacy(mut self, privacy: PrivacySettings) -> Self {
+        self.privacy = privacy;
+        self
     }
 
     /// Creates a new `SqliteUrlDatabase` from configuration settings.
@@ -185,15 +249,49 @@ impl SqliteUrlDatabase {
     /// # }
     /// ```
     pub async fn migrate(&self) -> Result<(), DatabaseError> {
-        sqlx::migrate!("./migrations")
+        self.migrate_with(&SQLITE_MIGRATOR).await
+    }
+
+    /// Runs the migrations of `migrator` instead of the built-in set.
+    ///
+    /// Lets embedders that extend the schema ship their own migrations. A
+    /// migrator holding only the extra migrations must allow the built-in
+    /// ones as unknown (`Migrator::set_ignore_missing(true)`), otherwise sqlx
+    /// rejects a database that already ran them. Conversely, once extra
+    /// migrations ran, plain `migrate()` rejects the database, so keep
+    /// migrating through this method.
+    pub async fn migrate_with(&self, migrator: &Migrator) -> Result<(), DatabaseError> {
+        migrator
             .run(&self.pool)
             .await
-            .map_err(|e| DatabaseError::MigrationError(e.to_string()))?;
+            .map_err(|e| DatabaseError::MigrationError(e.to_string()))
+    }
 
-        Ok(())
+    /// Lists the built-in migrations `migrate` would apply, without applying
+    /// them. A database that was never migrated reports all of them.
+    pub async fn migrate_dry_run(&self) -> Result<Vec<PendingMigration>, DatabaseError> {
+        let tracked: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| DatabaseError::MigrationError(e.to_string()))?;
+
+        let applied: Vec<i64> = if tracked == 0 {
+            Vec::new()
+        } else {
+            sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| DatabaseError::MigrationError(e.to_string()))?
+        };
+        Ok(pending_migrations(&SQLITE_MIGRATOR, &applied))
     }
 }
 
+/// The built-in migrations applied by `migrate`, embedded at compile time.
+pub static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
 #[async_trait]
 impl UrlDatabase for SqliteUrlDatabase {
     /// Retrieves the short ID by original URL from the SQLite database.
@@ -204,7 +302,7 @@ impl UrlDatabase for SqliteUrlDatabase {
             .bind(&hash[..]) // BLOB
             .fetch_optional(&self.pool)
             .await
-            .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+            .map_err(query_error)?;
 
         match row {
             Some(record) => Ok(record),
@@ -244,13 +342,27 @@ impl UrlDatabase for SqliteUrlDatabase {
         &self,
         code: &str,
         url: &str,
+    ) -> Result<(UpsertResult, Urls), DatabaseError> {
+        self.insert_url_with_settings(code, url, &LinkSettings::default())
+            .await
+    }
+
+    async fn insert_url_with_settings(
+        &self,
+        code: &str,
+        url: &str,
+        settings: &LinkSettings,
     ) -> Result<(UpsertResult, Urls), DatabaseError> {
         let hash = sha256_bytes(url);
+        let utm = settings.utm.as_ref();
 
         let inserted: Option<(i64,)> = sqlx::query_as(
             r#"
-                INSERT INTO urls(code, url, url_hash)
-                VALUES (?1, ?2, ?3)
+                INSERT INTO urls(code, url, url_hash, host, created_at, updated_at,
+                                 owner_key_id, max_clicks, utm_enabled, utm_source,
+                                 utm_medium, utm_campaign, skip_interstitial)
+                VALUES (?1, ?2, ?3, ?4, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP,
+                        ?5, ?6, ?7, ?8, ?9, ?10, ?11)
                 ON CONFLICT(url_hash) DO NOTHING
                 RETURNING id;
             "#,
@@ -258,12 +370,21 @@ impl UrlDatabase for SqliteUrlDatabase {
         .bind(code)
         .bind(url)
         .bind(&hash[..]) // BLOB
+        .bind(url_host(url))
+        .bind(settings.owner_key_id.as_deref())
+        .bind(settings.max_clicks.map(|n| n as i64))
+        .bind(utm.is_some())
+        .bind(utm.and_then(|utm| utm.source.as_deref()))
+        .bind(utm.and_then(|utm| utm.medium.as_deref()))
+        .bind(utm.and_then(|utm| utm.campaign.as_deref()))
+        .bind(settings.skip_interstitial)
         .fetch_optional(&self.pool)
         .await
         .map_err(|e| {
-            // `code` UNIQUE violation -> Duplicate id
+            // `code` UNIQUE violation or a code taken by an alias -> Duplicate id
             if e.to_string()
                 .contains("UNIQUE constraint failed: urls.code")
+                || is_code_overlap(&e)
             {
                 DatabaseError::Duplicate
             } else {
@@ -285,7 +406,7 @@ impl UrlDatabase for SqliteUrlDatabase {
                 .bind(&hash[..])
                 .fetch_one(&self.pool)
                 .await
-                .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+                .map_err(query_error)?;
 
         let upsert_result = UpsertResult {
             id: existing_urls.id,
@@ -294,6 +415,37 @@ impl UrlDatabase for SqliteUrlDatabase {
         Ok((upsert_result, existing_urls))
     }
 
+    async fn next_hop(&self, code: &str) -> Result<Option<String>, DatabaseError> {
+        let row: Option<(String, String)> = sqlx::query_as(&format!(
+            "SELECT c.source, u.code FROM all_short_codes c \
+             JOIN urls u ON u.id = c.target_id WHERE c.code = ? ORDER BY {} LIMIT 1",
+            source_order(self.precedence)
+        ))
+        .bind(code)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        match row {
+            Some((source, primary)) if source == "alias" => Ok(Some(primary)),
+            Some(_) => Ok(None),
+            None => Err(DatabaseError::NotFound),
+        }
+    }
+
+    async fn get_id_by_code(&self, code: &str) -> Result<i64, DatabaseError> {
+        let id: Option<i64> = sqlx::query_scalar(&format!(
+            "SELECT target_id FROM all_short_codes WHERE code = ? ORDER BY {} LIMIT 1",
+            source_order(self.precedence)
+        ))
+        .bind(code)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        id.ok_or(DatabaseError::NotFound)
+    }
+
     /// Retrieves a URL by its short ID from the SQLite database.
     ///
     /// This implementation uses a prepared statement with optional result handling
@@ -324,13 +476,14 @@ impl UrlDatabase for SqliteUrlDatabase {
     /// # }
     /// ```
     async fn get_url(&self, id: &str) -> Result<String, DatabaseError> {
-        let row = sqlx::query_as::<_, (String,)>(
-            "SELECT url FROM all_short_codes u WHERE u.code = ? LIMIT 1;",
-        )
+        let row = sqlx::query_as::<_, (String,)>(&format!(
+            "SELECT url FROM all_short_codes u WHERE u.code = ? ORDER BY {} LIMIT 1;",
+            source_order(self.precedence)
+        ))
         .bind(id)
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+        .map_err(query_error)?;
 
         match row {
             Some(record) => Ok(record.0),
@@ -338,125 +491,2897 @@ impl UrlDatabase for SqliteUrlDatabase {
         }
     }
 
-    async fn list_short_codes(
+    async fn get_url_admin(&self, code: &str) -> Result<UrlRecord, DatabaseError> {
+        let row = sqlx::query_as::<_, UrlRecord>(&format!(
+            r#"
+                SELECT u.id, u.code, u.url, u.created_at, u.owner_key_id, u.click_count,
+                       COALESCE(a.expires_at, u.expires_at) AS expires_at, u.blocked_reason
+                FROM all_short_codes c
+                JOIN urls u ON u.id = c.target_id
+                LEFT JOIN aliases a ON c.source = 'alias' AND a.alias = c.code
+                WHERE c.code = ?
+                ORDER BY {}
+                LIMIT 1
+            "#,
+            source_order(self.precedence)
+        ))
+        .bind(code)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        row.ok_or(DatabaseError::NotFound)
+    }
+
+    async fn get_redirect_target(&self, code: &str) -> Result<RedirectTarget, DatabaseError> {
+        let row = sqlx::query_as::<_, RedirectTarget>(&format!(
+            r#"
+                SELECT u.url, u.utm_enabled, u.utm_source, u.utm_medium, u.utm_campaign,
+                       COALESCE(a.expires_at, u.expires_at) AS expires_at, u.blocked_reason,
+                       u.skip_interstitial, u.updated_at, u.max_clicks
+                FROM all_short_codes c
+                JOIN urls u ON u.id = c.target_id
+                LEFT JOIN aliases a ON c.source = 'alias' AND a.alias = c.code
+                WHERE c.code = ?
+                ORDER BY {}
+                LIMIT 1
+            "#,
+            source_order(self.precedence)
+        ))
+        .bind(code)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        row.ok_or(DatabaseError::NotFound)
+    }
+
+    async fn set_link_utm(&self, url_id: i64, utm: &UtmParams) -> Result<(), DatabaseError> {
+        let result = sqlx::query(
+            "UPDATE urls SET utm_enabled = 1, utm_source = ?, utm_medium = ?, utm_campaign = ?, \
+             updated_at = CURRENT_TIMESTAMP \
+             WHERE id = ?",
+        )
+        .bind(utm.source.as_deref())
+        .bind(utm.medium.as_deref())
+        .bind(utm.campaign.as_deref())
+        .bind(url_id)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn set_max_clicks(
         &self,
-        offset: u64,
-        limit: u64,
-    ) -> Result<Vec<String>, DatabaseError> {
-        let codes: Vec<String> =
-            sqlx::query_scalar("SELECT code FROM all_short_codes LIMIT ? OFFSET ?")
-                .bind(limit as i64)
-                .bind(offset as i64)
-                .fetch_all(&self.pool)
-                .await
-                .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+        url_id: i64,
+        max_clicks: Option<u64>,
+    ) -> Result<(), DatabaseError> {
+        let result = sqlx::query(
+            "UPDATE urls SET max_clicks = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+        )
+        .bind(max_clicks.map(|n| n as i64))
+        .bind(url_id)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
 
-        Ok(codes)
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound);
+        }
+        Ok(())
     }
 
-    async fn insert_alias(&self, alias_code: &str, code_id: i64) -> Result<(), DatabaseError> {
-        sqlx::query("INSERT INTO aliases (alias, target_id) VALUES (?, ?)")
-            .bind(alias_code)
-            .bind(code_id)
-            .execute(&self.pool)
+    async fn set_skip_interstitial(&self, url_id: i64, skip: bool) -> Result<(), DatabaseError> {
+        let result = sqlx::query(
+            "UPDATE urls SET skip_interstitial = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+        )
+        .bind(skip)
+        .bind(url_id)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn set_link_metadata(
+        &self,
+        url_id: i64,
+        key: &str,
+        value: &str,
+    ) -> Result<(), DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+        sqlx::query(
+            "INSERT INTO url_metadata (url_id, key, value) VALUES (?, ?, ?)
+             ON CONFLICT (url_id, key) DO UPDATE SET value = excluded.value",
+        )
+        .bind(url_id)
+        .bind(key)
+        .bind(value)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            if is_foreign_key_violation(&e) {
+                DatabaseError::InvalidReference
+            } else {
+                DatabaseError::QueryError(e.to_string())
+            }
+        })?;
+        sqlx::query("UPDATE urls SET updated_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(url_id)
+            .execute(&mut *tx)
             .await
-            .map_err(|e| {
-                if e.to_string()
-                    .contains("UNIQUE constraint failed: aliases.alias")
-                {
-                    DatabaseError::Duplicate
-                } else {
-                    DatabaseError::QueryError(e.to_string())
-                }
-            })?;
+            .map_err(query_error)?;
+        tx.commit().await.map_err(query_error)
+    }
+
+    async fn distinct_metadata_keys(&self) -> Result<Vec<String>, DatabaseError> {
+        sqlx::query_scalar("SELECT DISTINCT key FROM url_metadata ORDER BY key")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(query_error)
+    }
+
+    async fn distinct_metadata_values(&self, key: &str) -> Result<Vec<String>, DatabaseError> {
+        sqlx::query_scalar("SELECT DISTINCT value FROM url_metadata WHERE key = ? ORDER BY value")
+            .bind(key)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(query_error)
+    }
+
+    async fn set_link_owner(&self, url_id: i64, owner_key_id: &str) -> Result<(), DatabaseError> {
+        let result = sqlx::query(
+            "UPDATE urls SET owner_key_id = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+        )
+        .bind(owner_key_id)
+        .bind(url_id)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound);
+        }
         Ok(())
     }
 
-    async fn load_bloom_snapshot(&self, name: &str) -> Result<Option<Vec<u8>>, DatabaseError> {
-        let data = sqlx::query_scalar::<_, Vec<u8>>(
-            "SELECT data FROM bloom_snapshots WHERE name = ? LIMIT 1",
+    async fn reassign_owner(&self, from_owner: &str, to_owner: &str) -> Result<u64, DatabaseError> {
+        check_owner_key_id(to_owner)?;
+        let result = sqlx::query(
+            "UPDATE urls SET owner_key_id = ?, updated_at = CURRENT_TIMESTAMP \
+             WHERE owner_key_id = ?",
         )
-        .bind(name)
-        .fetch_optional(&self.pool)
+        .bind(to_owner)
+        .bind(from_owner)
+        .execute(&self.pool)
         .await
-        .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+        .map_err(query_error)?;
 
-        Ok(data)
+        Ok(result.rows_affected())
     }
 
-    async fn save_bloom_snapshot(&self, name: &str, data: &[u8]) -> Result<(), DatabaseError> {
-        sqlx::query(
+    async fn urls_by_owner_between(
+        &self,
+        owner_key_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        check_offset(offset, self.max_offset)?;
+        check_page_size(limit, self.max_page_size)?;
+        // Bound in the `CURRENT_TIMESTAMP` format so the index range scan
+        // compares like with like
+        sqlx::query_as::<_, UrlRecord>(
             r#"
-                INSERT INTO bloom_snapshots (name, data, updated_at)
-                VALUES (?1, ?2, CURRENT_TIMESTAMP)
-                ON CONFLICT(name)
-                DO UPDATE SET
-                    data = excluded.data,
-                    updated_at = CURRENT_TIMESTAMP
+                SELECT id, code, url, created_at, owner_key_id, click_count, expires_at,
+                       blocked_reason
+                FROM urls
+                WHERE owner_key_id = ? AND created_at >= ? AND created_at < ?
+                ORDER BY created_at, id
+                LIMIT ? OFFSET ?
             "#,
         )
-        .bind(name)
-        .bind(data)
-        .execute(&self.pool)
+        .bind(owner_key_id)
+        .bind(sqlite_timestamp(from))
+        .bind(sqlite_timestamp(to))
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
         .await
-        .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+        .map_err(query_error)
+    }
 
-        Ok(())
+    async fn unclicked_urls(
+        &self,
+        older_than: DateTime<Utc>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        check_offset(offset, self.max_offset)?;
+        check_page_size(limit, self.max_page_size)?;
+        sqlx::query_as::<_, UrlRecord>(
+            r#"
+                SELECT id, code, url, created_at, owner_key_id, click_count, expires_at,
+                       blocked_reason
+                FROM urls
+                WHERE click_count = 0 AND created_at < ?
+                ORDER BY created_at, id
+                LIMIT ? OFFSET ?
+            "#,
+        )
+        .bind(sqlite_timestamp(older_than))
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)
     }
-}
 
-/// Creates a SQLite connection pool from configuration settings.
-///
-/// This function sets up the SQLite connection with the appropriate options,
-/// including creating the database file if specified in the configuration.
-///
-/// # Arguments
-///
-/// * `config` - Database configuration settings
-///
-/// # Returns
-///
-/// Returns `Ok(SqlitePool)` if the connection pool is successfully created,
-/// or `Err(sqlx::Error)` if connection setup fails.
-///
-/// # Examples
-///
-/// ```rust,no_run
-/// use url_shortener_ztm_lib::DatabaseType;
-/// use url_shortener_ztm_lib::database::get_connection_pool;
-/// use url_shortener_ztm_lib::configuration::DatabaseSettings;
-///
-/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-/// let config = DatabaseSettings {
-///     r#type: DatabaseType::Sqlite,
-///     url: "database.db".to_string(),
-///     create_if_missing: true,
-///     max_connections: Some(16),
-///     min_connections: Some(4),
-/// };
-/// let pool = get_connection_pool(&config).await?;
-/// # Ok(())
-/// # }
-/// ```
-pub async fn get_connection_pool(config: &DatabaseSettings) -> Result<SqlitePool, sqlx::Error> {
-    let options = SqliteConnectOptions::from_str(&config.connection_string())?
-        .create_if_missing(config.create_if_missing)
-        .foreign_keys(true);
+    async fn changed_since(
+        &self,
+        since: DateTime<Utc>,
+        after_id: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        check_page_size(limit, self.max_page_size)?;
+        sqlx::query_as::<_, UrlRecord>(
+            r#"
+                SELECT id, code, url, created_at, owner_key_id, click_count, expires_at,
+                       blocked_reason, updated_at
+                FROM urls
+                WHERE updated_at >= ?1
+                  AND (?2 IS NULL OR updated_at > ?1 OR id > ?2)
+                ORDER BY updated_at, id
+                LIMIT ?3
+            "#,
+        )
+        .bind(sqlite_timestamp(since))
+        .bind(after_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)
+    }
 
-    let cores = num_cpus::get().max(MIN_CAP as usize);
-    let default_max = cores.saturating_mul(2).max(4) as u32; // minimum 4
-    let mut max_conn = config.max_connections.unwrap_or(default_max);
+    async fn recently_accessed(&self, limit: u64) -> Result<Vec<UrlRecord>, DatabaseError> {
+        check_page_size(limit, self.max_page_size)?;
+        sqlx::query_as::<_, UrlRecord>(
+            r#"
+                SELECT id, code, url, created_at, owner_key_id, click_count, expires_at,
+                       blocked_reason
+                FROM urls
+                WHERE last_accessed_at IS NOT NULL
+                ORDER BY last_accessed_at DESC, id DESC
+                LIMIT ?
+            "#,
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)
+    }
 
-    max_conn = max_conn.clamp(MIN_CAP, MAX_CAP);
+    async fn export_owner_page(
+        &self,
+        owner_key_id: &str,
+        after_id: i64,
+        limit: u64,
+    ) -> Result<Vec<ExportedLink>, DatabaseError> {
+        let links = sqlx::query_as::<_, UrlRecord>(
+            r#"
+                SELECT id, code, url, created_at, owner_key_id, click_count, expires_at,
+                       blocked_reason
+                FROM urls
+                WHERE owner_key_id = ? AND id > ?
+                ORDER BY id
+                LIMIT ?
+            "#,
+        )
+        .bind(owner_key_id)
+        .bind(after_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)?;
 
-    SqlitePoolOptions::new()
-        .max_connections(max_conn)
-        .connect_with(options)
+        let ids: Vec<i64> = links.iter().map(|l| l.id).collect();
+        let mut aliases = Vec::new();
+        let mut metadata = Vec::new();
+        for chunk in bind_chunks(&ids) {
+            let mut query = QueryBuilder::<Sqlite>::new(
+                "SELECT target_id, alias FROM aliases WHERE target_id IN (",
+            );
+            let mut separated = query.separated(", ");
+            for id in chunk {
+                separated.push_bind(*id);
+            }
+            separated.push_unseparated(")");
+            aliases.extend(
+                query
+                    .build_query_as::<(i64, String)>()
+                    .fetch_all(&self.pool)
+                    .await
+                    .map_err(query_error)?,
+            );
+
+            let mut query = QueryBuilder::<Sqlite>::new(
+                "SELECT url_id, key, value FROM url_metadata WHERE url_id IN (",
+            );
+            let mut separated = query.separated(", ");
+            for id in chunk {
+                separated.push_bind(*id);
+            }
+            separated.push_unseparated(")");
+            metadata.extend(
+                query
+                    .build_query_as::<(i64, String, String)>()
+                    .fetch_all(&self.pool)
+                    .await
+                    .map_err(query_error)?,
+            );
+        }
+
+        Ok(assemble_export(links, aliases, metadata))
+    }
+
+    async fn find_by_host(
+        &self,
+        host: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<UrlRecord>, DatabaseError> {
+        check_offset(offset, self.max_offset)?;
+        check_page_size(limit, self.max_page_size)?;
+        let (host, subdomains) = host_filter(host);
+        sqlx::query_as::<_, UrlRecord>(
+            r#"
+                SELECT id, code, url, created_at, owner_key_id, click_count, expires_at,
+                       blocked_reason
+                FROM urls
+                WHERE host = ? OR host LIKE ? ESCAPE '\'
+                ORDER BY id
+                LIMIT ? OFFSET ?
+            "#,
+        )
+        .bind(host)
+        .bind(subdomains)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
         .await
-}
+        .map_err(query_error)
+    }
+
+    async fn insert_pattern_alias(
+        &self,
+        pattern: &str,
+        target: &str,
+    ) -> Result<PatternAlias, DatabaseError> {
+        sqlx::query_as::<_, PatternAlias>(
+            "INSERT INTO alias_patterns (pattern, target) VALUES (?, ?) \
+             RETURNING id, pattern, target",
+        )
+        .bind(pattern)
+        .bind(target)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            if e.to_string()
+                .contains("UNIQUE constraint failed: alias_patterns.pattern")
+            {
+                DatabaseError::Duplicate
+            } else {
+                DatabaseError::QueryError(e.to_string())
+            }
+        })
+    }
+
+    async fn list_pattern_aliases(&self) -> Result<Vec<PatternAlias>, DatabaseError> {
+        sqlx::query_as::<_, PatternAlias>(
+            "SELECT id, pattern, target FROM alias_patterns ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)
+    }
+
+    async fn retarget_alias(&self, alias: &str, new_code: &str) -> Result<(), DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
 
-fn sha256_bytes(s: &str) -> [u8; 32] {
-    let mut hasher = Sha256::new();
-    hasher.update(s.as_bytes());
-    hasher.finalize().into()
+        let target_id: Option<i64> = sqlx::query_scalar(&format!(
+            "SELECT target_id FROM all_short_codes WHERE code = ? ORDER BY {} LIMIT 1",
+            source_order(self.precedence)
+        ))
+        .bind(new_code)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(query_error)?;
+        let target_id = target_id.ok_or(DatabaseError::NotFound)?;
+
+        let result = sqlx::query("UPDATE aliases SET target_id = ? WHERE alias = ?")
+            .bind(target_id)
+            .bind(alias)
+            .execute(&mut *tx)
+            .await
+            .map_err(query_error)?;
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound);
+        }
+
+        // The alias now redirects differently, even if its new link is older
+        sqlx::query("UPDATE urls SET updated_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(target_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(query_error)?;
+
+        tx.commit().await.map_err(query_error)
+    }
+
+    async fn swap_codes(&self, code_a: &str, code_b: &str) -> Result<(), DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+        let mut ids = Vec::with_capacity(2);
+        for code in [code_a, code_b] {
+            let id: Option<i64> = sqlx::query_scalar("SELECT id FROM urls WHERE code = ?")
+                .bind(code)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(query_error)?;
+            ids.push(id.ok_or(DatabaseError::NotFound)?);
+        }
+
+        // Park `code_a` on a placeholder first so UNIQUE(code) never sees
+        // both links on the same code; codes never contain `/`
+        let placeholder = format!("swap/{}", ids[0]);
+        for (id, code) in [
+            (ids[0], placeholder.as_str()),
+            (ids[1], code_a),
+            (ids[0], code_b),
+        ] {
+            sqlx::query("UPDATE urls SET code = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?")
+                .bind(code)
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(query_error)?;
+        }
+
+        tx.commit().await.map_err(query_error)
+    }
+
+    async fn block_link(&self, code: &str, reason: &str) -> Result<(), DatabaseError> {
+        let result = sqlx::query(&format!(
+            "UPDATE urls SET blocked_reason = ?, updated_at = CURRENT_TIMESTAMP \
+             WHERE id = (SELECT target_id FROM all_short_codes WHERE code = ? \
+                         ORDER BY {} LIMIT 1)",
+            source_order(self.precedence)
+        ))
+        .bind(reason)
+        .bind(code)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn unblock_link(&self, code: &str) -> Result<(), DatabaseError> {
+        let result = sqlx::query(&format!(
+            "UPDATE urls SET blocked_reason = NULL, updated_at = CURRENT_TIMESTAMP \
+             WHERE id = (SELECT target_id FROM all_short_codes WHERE code = ? \
+                         ORDER BY {} LIMIT 1)",
+            source_order(self.precedence)
+        ))
+        .bind(code)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn set_featured(&self, code: &str, featured: bool) -> Result<(), DatabaseError> {
+        let result = sqlx::query(&format!(
+            "UPDATE urls SET featured = ?, updated_at = CURRENT_TIMESTAMP \
+             WHERE id = (SELECT target_id FROM all_short_codes WHERE code = ? \
+                         ORDER BY {} LIMIT 1)",
+            source_order(self.precedence)
+        ))
+        .bind(featured)
+        .bind(code)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        if result.rows_affected() == 0 {
+            return Err(DatabaseError::NotFound);
+        }
+        Ok(())
+    }
+
+    async fn list_featured(&self, limit: u64) -> Result<Vec<UrlRecord>, DatabaseError> {
+        check_page_size(limit, self.max_page_size)?;
+        sqlx::query_as::<_, UrlRecord>(
+            r#"
+                SELECT id, code, url, created_at, owner_key_id, click_count, expires_at,
+                       blocked_reason
+                FROM urls
+                WHERE featured = 1
+                ORDER BY id DESC
+                LIMIT ?
+            "#,
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)
+    }
+
+    async fn list_short_codes(
+        &self,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<String>, DatabaseError> {
+        check_offset(offset, self.max_offset)?;
+        check_page_size(limit, self.max_page_size)?;
+        let codes: Vec<String> =
+            sqlx::query_scalar("SELECT code FROM all_short_codes LIMIT ? OFFSET ?")
+                .bind(limit as i64)
+                .bind(offset as i64)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(query_error)?;
+
+        Ok(codes)
+    }
+
+    async fn list_short_codes_after(
+        &self,
+        after: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<String>, DatabaseError> {
+        let codes: Vec<String> = sqlx::query_scalar(
+            "SELECT DISTINCT code FROM all_short_codes WHERE ? IS NULL OR code > ? ORDER BY code LIMIT ?",
+        )
+        .bind(after)
+        .bind(after)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        Ok(codes)
+    }
+
+    async fn list_short_codes_with_prefix(
+        &self,
+        prefix: &str,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<String>, DatabaseError> {
+        check_offset(offset, self.max_offset)?;
+        check_page_size(limit, self.max_page_size)?;
+        // SQLite's LIKE ignores ASCII case, codes do not: re-check the prefix exactly
+        let codes: Vec<String> = sqlx::query_scalar(
+            r"SELECT code FROM all_short_codes
+              WHERE code LIKE ? || '%' ESCAPE '\' AND substr(code, 1, ?) = ?
+              LIMIT ? OFFSET ?",
+        )
+        .bind(escape_like(prefix))
+        .bind(prefix.chars().count() as i64)
+        .bind(prefix)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        Ok(codes)
+    }
+
+    async fn codes_for_urls(
+        &self,
+        urls: &[String],
+    ) -> Result<HashMap<String, Urls>, DatabaseError> {
+        let by_hash = hash_normalized_urls(urls, &self.privacy);
+        let hashes: Vec<&[u8; 32]> = by_hash.keys().collect();
+        let mut result = HashMap::new();
+
+        for chunk in bind_chunks(&hashes) {
+            let mut query = QueryBuilder::<Sqlite>::new(
+                "SELECT id, code, url_hash FROM urls WHERE url_hash IN (",
+            );
+            let mut separated = query.separated(", ");
+            for hash in chunk {
+                separated.push_bind(&hash[..]);
+            }
+            separated.push_unseparated(")");
+
+            let found: Vec<(i64, String, Vec<u8>)> = query
+                .build_query_as()
+                .fetch_all(&self.pool)
+                .await
+                .map_err(query_error)?;
+
+            for (id, code, hash) in found {
+                let Some(inputs) = <[u8; 32]>::try_from(hash.as_slice())
+                    .ok()
+                    .and_then(|hash| by_hash.get(&hash))
+                else {
+                    continue;
+                };
+                for url in inputs {
+                    result.insert(
+                        url.clone(),
+                        Urls {
+                            id,
+                            code: code.clone(),
+                        },
+                    );
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn alias_counts(&self, codes: &[String]) -> Result<HashMap<String, u64>, DatabaseError> {
+        let mut result: HashMap<String, u64> = codes.iter().map(|code| (code.clone(), 0)).collect();
+
+        for chunk in bind_chunks(codes) {
+            let mut query = QueryBuilder::<Sqlite>::new(
+                "SELECT u.code, COUNT(a.alias) FROM urls u \
+                 JOIN aliases a ON a.target_id = u.id WHERE u.code IN (",
+            );
+            let mut separated = query.separated(", ");
+            for code in chunk {
+                separated.push_bind(code);
+            }
+            separated.push_unseparated(") GROUP BY u.id, u.code");
+
+            let counts: Vec<(String, i64)> = query
+                .build_query_as()
+                .fetch_all(&self.pool)
+                .await
+                .map_err(query_error)?;
+
+            for (code, count) in counts {
+                result.insert(code, count as u64);
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn stats_for_codes(
+        &self,
+        codes: &[String],
+    ) -> Result<HashMap<String, CodeStats>, DatabaseError> {
+        let mut result = HashMap::new();
+
+        for chunk in bind_chunks(codes) {
+            let mut query = QueryBuilder::<Sqlite>::new(
+                "SELECT c.code, u.created_at, u.click_count, u.last_accessed_at \
+                 FROM all_short_codes c JOIN urls u ON u.id = c.target_id \
+                 WHERE c.code IN (",
+            );
+            let mut separated = query.separated(", ");
+            for code in chunk {
+                separated.push_bind(code);
+            }
+            separated.push_unseparated(")");
+
+            let found: Vec<(String, Option<DateTime<Utc>>, i64, Option<DateTime<Utc>>)> = query
+                .build_query_as()
+                .fetch_all(&self.pool)
+                .await
+                .map_err(query_error)?;
+
+            for (code, created_at, click_count, last_accessed_at) in found {
+                result.insert(
+                    code,
+                    CodeStats {
+                        created_at,
+                        click_count,
+                        last_accessed_at,
+                    },
+                );
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn delete_urls_batch(&self, codes: &[String]) -> Result<u64, DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+        let mut deleted = 0;
+
+        for chunk in bind_chunks(codes) {
+            let mut query = QueryBuilder::<Sqlite>::new("DELETE FROM urls WHERE code IN (");
+            let mut separated = query.separated(", ");
+            for code in chunk {
+                separated.push_bind(code);
+            }
+            separated.push_unseparated(")");
+
+            deleted += query
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(query_error)?
+                .rows_affected();
+        }
+
+        tx.commit().await.map_err(query_error)?;
+        Ok(deleted)
+    }
+
+    async fn set_expiry_batch(
+        &self,
+        codes: &[String],
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<u64, DatabaseError> {
+        let expires_at = expires_at.map(sqlite_timestamp);
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+        let mut updated = 0;
+
+        for chunk in bind_chunks(codes) {
+            let mut query = QueryBuilder::<Sqlite>::new("UPDATE urls SET expires_at = ");
+            query.push_bind(expires_at.clone());
+            query.push(", updated_at = CURRENT_TIMESTAMP WHERE code IN (");
+            let mut separated = query.separated(", ");
+            for code in chunk {
+                separated.push_bind(code);
+            }
+            separated.push_unseparated(")");
+
+            updated += query
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(query_error)?
+                .rows_affected();
+        }
+
+        tx.commit().await.map_err(query_error)?;
+        Ok(updated)
+    }
+
+    async fn exists_many(&self, codes: &[String]) -> Result<HashMap<String, bool>, DatabaseError> {
+        let mut result: HashMap<String, bool> =
+            codes.iter().map(|code| (code.clone(), false)).collect();
+
+        for chunk in bind_chunks(codes) {
+            let mut query =
+                QueryBuilder::<Sqlite>::new("SELECT code FROM all_short_codes WHERE code IN (");
+            let mut separated = query.separated(", ");
+            for code in chunk {
+                separated.push_bind(code);
+            }
+            separated.push_unseparated(")");
+
+            let found: Vec<String> = query
+                .build_query_scalar()
+                .fetch_all(&self.pool)
+                .await
+                .map_err(query_error)?;
+
+            for code in found {
+                result.insert(code, true);
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn insert_alias(&self, alias_code: &str, code_id: i64) -> Result<(), DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+        sqlx::query("INSERT INTO aliases (alias, target_id) VALUES (?, ?)")
+            .bind(alias_code)
+            .bind(code_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| {
+                if e.to_string()
+                    .contains("UNIQUE constraint failed: aliases.alias")
+                    || is_code_overlap(&e)
+                {
+                    DatabaseError::Duplicate
+                } else if is_foreign_key_violation(&e) {
+                    DatabaseError::InvalidReference
+                } else {
+                    DatabaseError::QueryError(e.to_string())
+                }
+            })?;
+        sqlx::query("UPDATE urls SET updated_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(code_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(query_error)?;
+        tx.commit().await.map_err(query_error)
+    }
+
+    async fn increment_click_count_by(&self, code: &str, by: u64) -> Result<(), DatabaseError> {
+        sqlx::query(&format!(
+            "UPDATE urls SET click_count = click_count + ?, last_accessed_at = CURRENT_TIMESTAMP \
+             WHERE id = (SELECT target_id FROM all_short_codes WHERE code = ? \
+                         ORDER BY {} LIMIT 1)",
+            source_order(self.precedence)
+        ))
+        .bind(by as i64)
+        .bind(code)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        Ok(())
+    }
+
+    async fn increment_clicks_batch(
+        &self,
+        increments: &[(String, u64)],
+    ) -> Result<(), DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+
+        for chunk in bind_chunks(increments) {
+            let mut lookup = QueryBuilder::<Sqlite>::new(
+                "SELECT code, target_id, source FROM all_short_codes WHERE code IN (",
+            );
+            let mut separated = lookup.separated(", ");
+            for (code, _) in chunk {
+                separated.push_bind(code);
+            }
+            separated.push_unseparated(")");
+            let rows: Vec<(String, i64, String)> = lookup
+                .build_query_as()
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(query_error)?;
+
+            // Overlapping codes count for the link their lookup would resolve to
+            let mut targets: HashMap<String, (u8, i64)> = HashMap::new();
+            for (code, target_id, source) in rows {
+                let rank = source_rank(self.precedence, &source);
+                match targets.get(&code) {
+                    Some((best, _)) if *best <= rank => {}
+                    _ => {
+                        targets.insert(code, (rank, target_id));
+                    }
+                }
+            }
+            let mut deltas: HashMap<i64, i64> = HashMap::new();
+            for (code, by) in chunk {
+                if let Some((_, target_id)) = targets.get(code) {
+                    *deltas.entry(*target_id).or_default() += *by as i64;
+                }
+            }
+            if deltas.is_empty() {
+                continue;
+            }
+
+            let mut update =
+                QueryBuilder::<Sqlite>::new("UPDATE urls SET click_count = click_count + CASE id");
+            for (id, by) in &deltas {
+                update
+                    .push(" WHEN ")
+                    .push_bind(*id)
+                    .push(" THEN ")
+                    .push_bind(*by);
+            }
+            update.push(" ELSE 0 END, last_accessed_at = CURRENT_TIMESTAMP WHERE id IN (");
+            let mut separated = update.separated(", ");
+            for id in deltas.keys() {
+                separated.push_bind(*id);
+            }
+            separated.push_unseparated(")");
+            update
+                .build()
+                .execute(&mut *tx)
+                .await
+                .map_err(query_error)?;
+        }
+
+        tx.commit().await.map_err(query_error)
+    }
+
+    async fn get_url_and_count(&self, code: &str) -> Result<String, DatabaseError> {
+        let url: Option<String> = sqlx::query_scalar(&format!(
+            "UPDATE urls SET click_count = click_count + 1, last_accessed_at = CURRENT_TIMESTAMP \
+             WHERE id = (SELECT target_id FROM all_short_codes WHERE code = ? \
+                         ORDER BY {} LIMIT 1) \
+               AND (max_clicks IS NULL OR click_count < max_clicks) \
+             RETURNING url",
+            source_order(self.precedence)
+        ))
+        .bind(code)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        match url {
+            Some(url) => Ok(url),
+            None => self
+                .get_id_by_code(code)
+                .await
+                .and(Err(DatabaseError::QuotaExceeded)),
+        }
+    }
+
+    async fn resolve_and_track(
+        &self,
+        code: &str,
+        referrer: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<String, DatabaseError> {
+        // IMMEDIATE takes the write lock up front, so the checks read the
+        // state the update then changes
+        let mut tx = self
+            .pool
+            .begin_with("BEGIN IMMEDIATE")
+            .await
+            .map_err(query_error)?;
+        let row: Option<(i64, String, Option<DateTime<Utc>>, Option<String>)> =
+            sqlx::query_as(&format!(
+                r#"
+                    SELECT u.id, u.url, COALESCE(a.expires_at, u.expires_at), u.blocked_reason
+                    FROM all_short_codes c
+                    JOIN urls u ON u.id = c.target_id
+                    LEFT JOIN aliases a ON c.source = 'alias' AND a.alias = c.code
+                    WHERE c.code = ?
+                    ORDER BY {}
+                    LIMIT 1
+                "#,
+                source_order(self.precedence)
+            ))
+            .bind(code)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(query_error)?;
+        let (url_id, url, expires_at, blocked_reason) = row.ok_or(DatabaseError::NotFound)?;
+        check_servable(expires_at, blocked_reason)?;
+
+        let counted = sqlx::query(
+            "UPDATE urls SET click_count = click_count + 1, last_accessed_at = CURRENT_TIMESTAMP \
+             WHERE id = ? AND (max_clicks IS NULL OR click_count < max_clicks)",
+        )
+        .bind(url_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(query_error)?;
+        if counted.rows_affected() == 0 {
+            return Err(DatabaseError::QuotaExceeded);
+        }
+
+        sqlx::query(
+            "INSERT INTO click_events (url_id, code, referrer, user_agent) VALUES (?, ?, ?, ?)",
+        )
+        .bind(url_id)
+        .bind(code)
+        .bind(referrer)
+        .bind(user_agent)
+        .execute(&mut *tx)
+        .await
+        .map_err(query_error)?;
+
+        tx.commit().await.map_err(query_error)?;
+        Ok(url)
+    }
+
+    async fn is_empty(&self) -> Result<bool, DatabaseError> {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM urls LIMIT 1)")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(query_error)?;
+
+        Ok(!exists)
+    }
+
+    async fn aggregate_stats(&self) -> Result<AggregateStats, DatabaseError> {
+        sqlx::query_as::<_, AggregateStats>(
+            r#"
+                SELECT
+                    (SELECT COUNT(*) FROM urls) AS total_links,
+                    (SELECT COUNT(*) FROM aliases) AS total_aliases,
+                    (SELECT COALESCE(SUM(click_count), 0) FROM urls) AS total_clicks,
+                    (SELECT COUNT(*) FROM urls
+                        WHERE blocked_reason IS NOT NULL
+                           OR (expires_at IS NOT NULL
+                               AND julianday(expires_at) <= julianday('now'))) AS expired_links
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(query_error)
+    }
+
+    async fn save_stats_snapshot(&self, stats: &AggregateStats) -> Result<(), DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+
+        sqlx::query(
+            "INSERT INTO stats_snapshots (total_links, total_aliases, total_clicks, expired_links) \
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(stats.total_links)
+        .bind(stats.total_aliases)
+        .bind(stats.total_clicks)
+        .bind(stats.expired_links)
+        .execute(&mut *tx)
+        .await
+        .map_err(query_error)?;
+
+        sqlx::query(
+            "DELETE FROM stats_snapshots WHERE id <= \
+             (SELECT id FROM stats_snapshots ORDER BY id DESC LIMIT 1 OFFSET ?)",
+        )
+        .bind(STATS_SNAPSHOT_HISTORY)
+        .execute(&mut *tx)
+        .await
+        .map_err(query_error)?;
+
+        tx.commit().await.map_err(query_error)
+    }
+
+    async fn latest_stats(&self) -> Result<AggregateStats, DatabaseError> {
+        sqlx::query_as::<_, AggregateStats>(
+            "SELECT total_links, total_aliases, total_clicks, expired_links \
+             FROM stats_snapshots ORDER BY id DESC LIMIT 1",
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(query_error)?
+        .ok_or(DatabaseError::NotFound)
+    }
+
+    async fn find_hash_collisions(&self) -> Result<Vec<(Vec<u8>, Vec<String>)>, DatabaseError> {
+        let rows: Vec<(Vec<u8>, String)> = sqlx::query_as(
+            r#"
+                SELECT DISTINCT url_hash, url
+                FROM urls
+                WHERE url_hash IN (
+                    SELECT url_hash FROM urls
+                    GROUP BY url_hash
+                    HAVING COUNT(DISTINCT url) > 1
+                )
+                ORDER BY url_hash, url
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        Ok(group_by_hash(rows))
+    }
+
+    async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    async fn load_bloom_snapshot(&self, name: &str) -> Result<Option<Vec<u8>>, DatabaseError> {
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+
+        let header: Option<(Vec<u8>, i32)> =
+            sqlx::query_as("SELECT data, chunk_count FROM bloom_snapshots WHERE name = ? LIMIT 1")
+                .bind(name)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(query_error)?;
+
+        let Some((data, chunk_count)) = header else {
+            return Ok(None);
+        };
+        if chunk_count == 0 {
+            return Ok(Some(data));
+        }
+
+        // Read chunk by chunk so only the reassembled buffer is held
+        let mut data = Vec::new();
+        for index in 0..chunk_count {
+            let chunk: Vec<u8> = sqlx::query_scalar(
+                "SELECT data FROM bloom_snapshot_chunks WHERE name = ? AND chunk_index = ?",
+            )
+            .bind(name)
+            .bind(index)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(query_error)?
+            .ok_or_else(|| {
+                DatabaseError::QueryError(format!("bloom snapshot {name} is missing chunk {index}"))
+            })?;
+            data.extend_from_slice(&chunk);
+        }
+        Ok(Some(data))
+    }
+
+    async fn save_bloom_snapshot(&self, name: &str, data: &[u8]) -> Result<(), DatabaseError> {
+        let chunks: Vec<&[u8]> = if data.len() > self.snapshot_chunk_bytes {
+            data.chunks(self.snapshot_chunk_bytes).collect()
+        } else {
+            Vec::new()
+        };
+        let inline: &[u8] = if chunks.is_empty() { data } else { &[] };
+
+        let mut tx = self.pool.begin().await.map_err(query_error)?;
+
+        sqlx::query(
+            r#"
+                INSERT INTO bloom_snapshots (name, data, chunk_count, updated_at)
+                VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)
+                ON CONFLICT(name)
+                DO UPDATE SET
+                    data = excluded.data,
+                    chunk_count = excluded.chunk_count,
+                    updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(name)
+        .bind(inline)
+        .bind(chunks.len() as i32)
+        .execute(&mut *tx)
+        .await
+        .map_err(query_error)?;
+
+        sqlx::query("DELETE FROM bloom_snapshot_chunks WHERE name = ?")
+            .bind(name)
+            .execute(&mut *tx)
+            .await
+            .map_err(query_error)?;
+
+        for (index, chunk) in chunks.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO bloom_snapshot_chunks (name, chunk_index, data) VALUES (?, ?, ?)",
+            )
+            .bind(name)
+            .bind(index as i32)
+            .bind(*chunk)
+            .execute(&mut *tx)
+            .await
+            .map_err(query_error)?;
+        }
+
+        tx.commit().await.map_err(query_error)
+    }
+
+    async fn prune_bloom_snapshots(
+        &self,
+        keep_latest_per_name: usize,
+    ) -> Result<u64, DatabaseError> {
+        let result = sqlx::query(
+            r#"
+                DELETE FROM bloom_snapshots
+                WHERE rowid IN (
+                    SELECT rowid FROM (
+                        SELECT rowid, ROW_NUMBER() OVER (
+                            PARTITION BY name ORDER BY updated_at DESC, rowid DESC
+                        ) AS rank
+                        FROM bloom_snapshots
+                    )
+                    WHERE rank > ?
+                )
+            "#,
+        )
+        .bind(keep_latest_per_name as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn storage_stats(&self) -> Result<StorageStats, DatabaseError> {
+        let tables = sqlx::query_as::<_, (String, i64)>(&table_counts_query())
+            .fetch_all(&self.pool)
+            .await
+            .map_err(query_error)?
+            .into_iter()
+            .collect();
+        let database_bytes: i64 = sqlx::query_scalar(
+            "SELECT (SELECT page_count FROM pragma_page_count()) \
+                  * (SELECT page_size FROM pragma_page_size())",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(query_error)?;
+        let bloom_snapshot_bytes: i64 = sqlx::query_scalar(
+            "SELECT (SELECT COALESCE(SUM(length(data)), 0) FROM bloom_snapshots) \
+                  + (SELECT COALESCE(SUM(length(data)), 0) FROM bloom_snapshot_chunks)",
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(query_error)?;
+
+        Ok(StorageStats {
+            tables,
+            database_bytes,
+            bloom_snapshot_bytes,
+        })
+    }
+
+    async fn self_check(&self) -> Result<SelfCheckReport, DatabaseError> {
+        let mut report = SelfCheckReport::default();
+
+        let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(query_error)?;
+        if foreign_keys != 1 {
+            report.push("foreign key enforcement is off (PRAGMA foreign_keys = 0)");
+        }
+
+        for (table, index) in schema::EXPECTED_INDEXES {
+            let found: Option<String> = sqlx::query_scalar(
+                "SELECT name FROM sqlite_master WHERE type = 'index' AND tbl_name = ? AND name = ?",
+            )
+            .bind(table)
+            .bind(index)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(query_error)?;
+            if found.is_none() {
+                report.push(format!("index `{index}` on `{table}` is missing"));
+            }
+        }
+
+        for (table, column) in schema::EXPECTED_UNIQUE {
+            let unique: i64 = sqlx::query_scalar(
+                r#"
+                    SELECT COUNT(*) FROM pragma_index_list(?1) il
+                    WHERE il."unique" = 1
+                      AND (SELECT COUNT(*) FROM pragma_index_info(il.name)) = 1
+                      AND (SELECT name FROM pragma_index_info(il.name)) = ?2
+                "#,
+            )
+            .bind(table)
+            .bind(column)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(query_error)?;
+            if unique == 0 {
+                report.push(format!("`{table}.{column}` is not unique"));
+            }
+        }
+
+        for (view, columns) in schema::EXPECTED_VIEWS {
+            let actual: Vec<String> =
+                sqlx::query_scalar("SELECT name FROM pragma_table_info(?) ORDER BY cid")
+                    .bind(view)
+                    .fetch_all(&self.pool)
+                    .await
+                    .map_err(query_error)?;
+            report.check_view(view, columns, &actual);
+        }
+
+        for (table, column, referenced) in schema::EXPECTED_FOREIGN_KEYS {
+            let found: i64 = sqlx::query_scalar(
+                r#"SELECT COUNT(*) FROM pragma_foreign_key_list(?) WHERE "from" = ? AND "table" = ?"#,
+            )
+            .bind(table)
+            .bind(column)
+            .bind(referenced)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(query_error)?;
+            if found == 0 {
+                report.push(format!(
+                    "foreign key `{table}.{column}` -> `{referenced}` is missing"
+                ));
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Creates a SQLite connection pool from configuration settings.
+///
+/// This function sets up the SQLite connection with the appropriate options,
+/// including creating the database file if specified in the configuration.
+///
+/// # Arguments
+///
+/// * `config` - Database configuration settings
+///
+/// # Returns
+///
+/// Returns `Ok(SqlitePool)` if the connection pool is successfully created,
+/// or `Err(sqlx::Error)` if connection setup fails.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use url_shortener_ztm_lib::DatabaseType;
+/// use url_shortener_ztm_lib::database::get_connection_pool;
+/// use url_shortener_ztm_lib::configuration::DatabaseSettings;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let config = DatabaseSettings {
+///     r#type: DatabaseType::Sqlite,
+///     url: "database.db".to_string(),
+///     create_if_missing: true,
+///     max_connections: Some(16),
+///     min_connections: Some(4),
+/// };
+/// let pool = get_connection_pool(&config).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn get_connection_pool(config: &DatabaseSettings) -> Result<SqlitePool, sqlx::Error> {
+    let options = SqliteConnectOptions::from_str(&config.connection_string())?
+        .create_if_missing(config.create_if_missing)
+        .foreign_keys(true);
+
+    let cores = num_cpus::get().max(MIN_CAP as usize);
+    let default_max = cores.saturating_mul(2).max(4) as u32; // minimum 4
+    let mut max_conn = config.max_connections.unwrap_or(default_max);
+
+    max_conn = max_conn.clamp(MIN_CAP, MAX_CAP);
+
+    SqlitePoolOptions::new()
+        .max_connections(max_conn)
+        .connect_with(options)
+        .await
+}
+
+/// Returns true if the provided `sqlx::Error` is a foreign key constraint
+/// failure (SQLite extended result code `787`).
+fn is_foreign_key_violation(e: &sqlx::Error) -> bool {
+    if let sqlx::Error::Database(db_err) = e {
+        db_err.code().map(|c| c == "787").unwrap_or(false)
+    } else {
+        false
+    }
+}
+
+/// Returns true if the provided `sqlx::Error` was raised by the triggers that
+/// keep primary codes and aliases disjoint (`*_block_overlap_*`).
+fn is_code_overlap(e: &sqlx::Error) -> bool {
+    let message = e.to_string();
+    message.contains("alias conflicts with existing primary code")
+        || message.contains("code conflicts with existing alias")
+}
+
+/// `YYYY-MM-DD HH:MM:SS`, the format SQLite's `CURRENT_TIMESTAMP` writes.
+fn sqlite_timestamp(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Builds a migrated, shared in-memory database for a single test.
+#[cfg(test)]
+pub(crate) async fn test_db() -> SqliteUrlDatabase {
+    let db = SqliteUrlDatabase::from_config(&DatabaseSettings::default())
+        .await
+        .expect("failed to open in-memory database");
+    db.migrate().await.expect("migrations failed");
+    db
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::destination::canonical_destination;
+    use crate::database::BIND_CHUNK_SIZE;
+    use crate::models::{AliasOutcome, InsertMode};
+
+    #[tokio::test]
+    async fn redirect_target_prefers_alias_expiry_over_link_expiry() {
+        let db = test_db().await;
+        let (upsert, _) = db
+            .insert_url("Exp1234", "https://example.com/")
+            .await
+            .unwrap();
+        db.insert_alias("expalias", upsert.id).await.unwrap();
+        assert_eq!(
+            db.get_redirect_target("Exp1234").await.unwrap().expires_at,
+            None
+        );
+
+        sqlx::query("UPDATE urls SET expires_at = '2030-01-01T00:00:00Z' WHERE id = ?")
+            .bind(upsert.id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE aliases SET expires_at = '2020-01-01T00:00:00Z' WHERE alias = ?")
+            .bind("expalias")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let link = db.get_redirect_target("Exp1234").await.unwrap();
+        let alias = db.get_redirect_target("expalias").await.unwrap();
+        assert_eq!(
+            link.expires_at.unwrap().to_rfc3339(),
+            "2030-01-01T00:00:00+00:00"
+        );
+        assert_eq!(
+            alias.expires_at.unwrap().to_rfc3339(),
+            "2020-01-01T00:00:00+00:00"
+        );
+    }
+
+    #[tokio::test]
+    async fn migrate_with_applies_additional_migrations() {
+        let db = test_db().await;
+        let dir = std::env::temp_dir().join(format!("extra-migrations-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("20990101000000_add_link_notes.sql"),
+            "CREATE TABLE link_notes (url_id INTEGER NOT NULL REFERENCES urls(id), note TEXT);",
+        )
+        .unwrap();
+
+        let mut extra = Migrator::new(dir.clone()).await.unwrap();
+        extra.set_ignore_missing(true);
+        db.migrate_with(&extra).await.unwrap();
+        // The built-in set alone now sees an unknown applied version
+        assert!(db.migrate().await.is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let tables: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'link_notes'",
+        )
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+        assert_eq!(tables, 1);
+    }
+
+    #[tokio::test]
+    async fn insert_url_with_settings_writes_them_only_on_create() {
+        let db = test_db().await;
+        let settings = LinkSettings {
+            owner_key_id: Some("0123456789abcdef".to_string()),
+            max_clicks: Some(3),
+            utm: Some(UtmParams {
+                source: Some("news".to_string()),
+                ..UtmParams::default()
+            }),
+            skip_interstitial: true,
+        };
+        let (created, _) = db
+            .insert_url_with_settings("Set0001", "https://example.com/set", &settings)
+            .await
+            .unwrap();
+        let (existing, _) = db
+            .insert_url_with_settings(
+                "Set0002",
+                "https://example.com/set",
+                &LinkSettings::default(),
+            )
+            .await
+            .unwrap();
+
+        assert!(created.created);
+        assert!(!existing.created);
+        let target = db.get_redirect_target("Set0001").await.unwrap();
+        assert_eq!(target.max_clicks, Some(3));
+        assert_eq!(target.utm(), settings.utm);
+        assert!(target.skip_interstitial);
+        let record = db.get_url_admin("Set0001").await.unwrap();
+        assert_eq!(record.owner_key_id, settings.owner_key_id);
+    }
+
+    #[tokio::test]
+    async fn insert_url_with_alias_reports_the_alias_outcome() {
+        let db = test_db().await;
+        let created = db
+            .insert_url_with_alias(
+                "Cmb0001",
+                "https://one.example/",
+                Some("combo"),
+                &LinkSettings::default(),
+            )
+            .await
+            .unwrap();
+        assert!(created.created);
+        assert_eq!(created.alias, Some(AliasOutcome::Created("combo".into())));
+        assert_eq!(db.get_url("combo").await.unwrap(), "https://one.example/");
+
+        let again = db
+            .insert_url_with_alias(
+                "Cmb0002",
+                "https://one.example/",
+                Some("combo"),
+                &LinkSettings::default(),
+            )
+            .await
+            .unwrap();
+        assert!(!again.created);
+        assert_eq!(again.record.code, "Cmb0001");
+        assert_eq!(again.alias, Some(AliasOutcome::Existing("combo".into())));
+
+        let taken = db
+            .insert_url_with_alias(
+                "Cmb0003",
+                "https://two.example/",
+                Some("combo"),
+                &LinkSettings::default(),
+            )
+            .await
+            .unwrap();
+        assert!(taken.created);
+        assert_eq!(taken.alias, Some(AliasOutcome::Taken("combo".into())));
+
+        let plain = db
+            .insert_url_with_alias(
+                "Cmb0004",
+                "https://three.example/",
+                None,
+                &LinkSettings::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(plain.alias, None);
+    }
+
+    #[tokio::test]
+    async fn dangling_link_ids_are_invalid_references() {
+        let db = test_db().await;
+
+        assert!(matches!(
+            db.insert_alias("Ghost01", 9_999).await,
+            Err(DatabaseError::InvalidReference)
+        ));
+        assert!(matches!(
+            db.set_link_metadata(9_999, "team", "growth").await,
+            Err(DatabaseError::InvalidReference)
+        ));
+    }
+
+    #[tokio::test]
+    async fn aggregate_stats_counts_expired_and_blocked_links() {
+        let db = test_db().await;
+        for (code, url) in [
+            ("Agg0001", "https://example.com/live"),
+            ("Agg0002", "https://example.com/expired"),
+            ("Agg0003", "https://example.com/blocked"),
+        ] {
+            db.insert_url(code, url).await.unwrap();
+        }
+        db.set_expiry_batch(
+            &["Agg0002".to_string()],
+            Some(Utc::now() - chrono::Duration::hours(1)),
+        )
+        .await
+        .unwrap();
+        db.block_link("Agg0003", "Court order").await.unwrap();
+
+        let stats = db.aggregate_stats().await.unwrap();
+
+        assert_eq!(stats.total_links, 3);
+        assert_eq!(stats.expired_links, 2);
+    }
+
+    #[tokio::test]
+    async fn latest_stats_returns_the_newest_snapshot() {
+        let db = test_db().await;
+        assert!(matches!(
+            db.latest_stats().await,
+            Err(DatabaseError::NotFound)
+        ));
+
+        db.insert_url("Stat001", "https://example.com/a")
+            .await
+            .unwrap();
+        let first = db.aggregate_stats().await.unwrap();
+        db.save_stats_snapshot(&first).await.unwrap();
+        db.insert_url("Stat002", "https://example.com/b")
+            .await
+            .unwrap();
+        let second = db.aggregate_stats().await.unwrap();
+        db.save_stats_snapshot(&second).await.unwrap();
+
+        assert_eq!(db.latest_stats().await.unwrap().total_links, 2);
+    }
+
+    #[tokio::test]
+    async fn migrate_dry_run_lists_unapplied_migrations() {
+        let db = SqliteUrlDatabase::new(
+            SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect("sqlite::memory:")
+                .await
+                .unwrap(),
+        );
+
+        let pending = db.migrate_dry_run().await.unwrap();
+        assert_eq!(
+            pending.len(),
+            SQLITE_MIGRATOR
+                .iter()
+                .filter(|m| !m.migration_type.is_down_migration())
+                .count()
+        );
+        assert!(pending.windows(2).all(|w| w[0].version < w[1].version));
+
+        db.migrate().await.unwrap();
+        assert!(db.migrate_dry_run().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn distinct_metadata_lists_keys_and_values() {
+        let db = test_db().await;
+        let (first, _) = db
+            .insert_url("Meta001", "https://example.com/a")
+            .await
+            .unwrap();
+        let (second, _) = db
+            .insert_url("Meta002", "https://example.com/b")
+            .await
+            .unwrap();
+
+        db.set_link_metadata(first.id, "team", "growth")
+            .await
+            .unwrap();
+        db.set_link_metadata(first.id, "channel", "email")
+            .await
+            .unwrap();
+        db.set_link_metadata(second.id, "team", "growth")
+            .await
+            .unwrap();
+        db.set_link_metadata(second.id, "team", "infra")
+            .await
+            .unwrap();
+        db.set_link_metadata(first.id, "team", "ads").await.unwrap();
+
+        assert_eq!(
+            db.distinct_metadata_keys().await.unwrap(),
+            ["channel", "team"]
+        );
+        assert_eq!(
+            db.distinct_metadata_values("team").await.unwrap(),
+            ["ads", "infra"]
+        );
+        assert!(
+            db.distinct_metadata_values("missing")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn codes_for_urls_matches_normalized_spellings() {
+        let db = test_db().await;
+        let (upsert, _) = db
+            .insert_url("Pre0001", "https://example.com/known")
+            .await
+            .unwrap();
+
+        let found = db
+            .codes_for_urls(&[
+                "https://example.com/known".to_string(),
+                "https://EXAMPLE.com/known#frag".to_string(),
+                "https://example.com/unknown".to_string(),
+                "not a url".to_string(),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(found.len(), 2);
+        assert_eq!(found["https://example.com/known"].id, upsert.id);
+        assert_eq!(found["https://EXAMPLE.com/known#frag"].code, "Pre0001");
+    }
+
+    #[tokio::test]
+    async fn codes_for_urls_strips_tracking_params_like_create() {
+        let privacy = PrivacySettings {
+            strip_tracking_params: true,
+            ..PrivacySettings::default()
+        };
+        let stored =
+            canonical_destination("https://example.com/campaign?utm_source=x", &privacy).unwrap();
+        let db = test_db().await.with_privacy(privacy);
+        db.insert_url("Trk0001", &stored).await.unwrap();
+
+        let found = db
+            .codes_for_urls(&[
+                "https://example.com/campaign?utm_source=newsletter&fbclid=abc".to_string(),
+                "https://example.com/campaign".to_string(),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(found.len(), 2);
+        assert!(found.values().all(|record| record.code == "Trk0001"));
+    }
+
+    #[tokio::test]
+    async fn codes_and_aliases_cannot_overlap() {
+        let db = test_db().await;
+        let (first, _) = db
+            .insert_url("Ovl0001", "https://example.com/first")
+            .await
+            .unwrap();
+        db.insert_alias("ovlAlias", first.id).await.unwrap();
+
+        assert!(matches!(
+            db.insert_alias("Ovl0001", first.id).await,
+            Err(DatabaseError::Duplicate)
+        ));
+        assert!(matches!(
+            db.insert_url("ovlAlias", "https://example.com/second")
+                .await,
+            Err(DatabaseError::Duplicate)
+        ));
+    }
+
+    #[tokio::test]
+    async fn overlapping_codes_resolve_by_precedence() {
+        let db = test_db().await;
+        let (first, _) = db
+            .insert_url("Shadow1", "https://example.com/primary")
+            .await
+            .unwrap();
+        let (second, _) = db
+            .insert_url("Other01", "https://example.com/aliased")
+            .await
+            .unwrap();
+        // Rows from before the triggers existed can still overlap
+        sqlx::query("DROP TRIGGER aliases_block_overlap_ins")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        db.insert_alias("Shadow1", second.id).await.unwrap();
+
+        assert_eq!(
+            db.get_url("Shadow1").await.unwrap(),
+            "https://example.com/primary"
+        );
+        assert_eq!(db.get_id_by_code("Shadow1").await.unwrap(), first.id);
+
+        let db = db.with_code_precedence(CodePrecedence::Alias);
+        assert_eq!(
+            db.get_url("Shadow1").await.unwrap(),
+            "https://example.com/aliased"
+        );
+        assert_eq!(
+            db.get_redirect_target("Shadow1").await.unwrap().url,
+            "https://example.com/aliased"
+        );
+    }
+
+    #[tokio::test]
+    async fn increment_clicks_batch_applies_mixed_batch() {
+        let db = test_db().await;
+        let (link, _) = db
+            .insert_url("Bat0001", "https://example.com/one")
+            .await
+            .unwrap();
+        db.insert_url("Bat0002", "https://example.com/two")
+            .await
+            .unwrap();
+        db.insert_alias("batAlias", link.id).await.unwrap();
+
+        db.increment_clicks_batch(&[
+            ("Bat0001".to_string(), 2),
+            ("batAlias".to_string(), 3),
+            ("Bat0002".to_string(), 1),
+            ("Missing".to_string(), 7),
+        ])
+        .await
+        .unwrap();
+
+        let stats = db
+            .stats_for_codes(&["Bat0001".to_string(), "Bat0002".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(stats["Bat0001"].click_count, 5);
+        assert_eq!(stats["Bat0002"].click_count, 1);
+        assert!(stats["Bat0002"].last_accessed_at.is_some());
+        assert_eq!(db.aggregate_stats().await.unwrap().total_clicks, 6);
+    }
+
+    #[tokio::test]
+    async fn stats_for_codes_reports_links_and_aliases() {
+        let db = test_db().await;
+        let (link, _) = db
+            .insert_url("Rep0001", "https://example.com/report")
+            .await
+            .unwrap();
+        db.insert_url("Rep0002", "https://example.com/idle")
+            .await
+            .unwrap();
+        db.insert_alias("repAlias", link.id).await.unwrap();
+        db.increment_click_count_by("repAlias", 3).await.unwrap();
+
+        let stats = db
+            .stats_for_codes(&[
+                "Rep0001".to_string(),
+                "repAlias".to_string(),
+                "Rep0002".to_string(),
+                "Missing".to_string(),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(stats.len(), 3);
+        assert_eq!(stats["Rep0001"].click_count, 3);
+        assert_eq!(stats["repAlias"].click_count, 3);
+        assert!(stats["Rep0001"].created_at.is_some());
+        assert!(stats["Rep0001"].last_accessed_at.is_some());
+        assert_eq!(stats["Rep0002"].click_count, 0);
+        assert!(stats["Rep0002"].last_accessed_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn delete_urls_batch_counts_deleted_links_and_drops_aliases() {
+        let db = test_db().await;
+        let (first, _) = db
+            .insert_url("Del0001", "https://example.com/a")
+            .await
+            .unwrap();
+        db.insert_url("Del0002", "https://example.com/b")
+            .await
+            .unwrap();
+        db.insert_url("Keep001", "https://example.com/c")
+            .await
+            .unwrap();
+        db.insert_alias("delAlias", first.id).await.unwrap();
+
+        let deleted = db
+            .delete_urls_batch(&[
+                "Del0001".to_string(),
+                "Del0002".to_string(),
+                "Missing".to_string(),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(deleted, 2);
+        let exists = db
+            .exists_many(&["delAlias".to_string(), "Keep001".to_string()])
+            .await
+            .unwrap();
+        assert!(!exists["delAlias"]);
+        assert!(exists["Keep001"]);
+    }
+
+    #[tokio::test]
+    async fn exists_many_reports_codes_and_aliases() {
+        let db = test_db().await;
+        let (upsert, _) = db
+            .insert_url("Abc1234", "https://example.com/")
+            .await
+            .unwrap();
+        db.insert_alias("my-alias", upsert.id).await.unwrap();
+
+        let codes = vec![
+            "Abc1234".to_string(),
+            "my-alias".to_string(),
+            "missing".to_string(),
+        ];
+        let result = db.exists_many(&codes).await.unwrap();
+
+        assert_eq!(result.len(), 3);
+        assert!(result["Abc1234"]);
+        assert!(result["my-alias"]);
+        assert!(!result["missing"]);
+    }
+
+    #[tokio::test]
+    async fn exists_many_spans_multiple_chunks() {
+        let db = test_db().await;
+        db.insert_url("Zzz9999", "https://example.com/")
+            .await
+            .unwrap();
+
+        let mut codes: Vec<String> = (0..BIND_CHUNK_SIZE * 2).map(|i| format!("c{i}")).collect();
+        codes.push("Zzz9999".to_string());
+
+        let result = db.exists_many(&codes).await.unwrap();
+
+        assert_eq!(result.len(), codes.len());
+        assert_eq!(result.values().filter(|exists| **exists).count(), 1);
+        assert!(result["Zzz9999"]);
+    }
+
+    #[tokio::test]
+    async fn deep_offsets_are_refused_and_keyset_pages_walk_everything() {
+        let db = test_db().await.with_max_offset(2);
+        for (i, code) in ["Key0003", "Key0001", "Key0002"].iter().enumerate() {
+            let (_, urls) = db
+                .insert_url(code, &format!("https://example.com/{i}"))
+                .await
+                .unwrap();
+            if i == 0 {
+                db.insert_alias("Key0000", urls.id).await.unwrap();
+            }
+        }
+
+        assert_eq!(db.list_short_codes(2, 10).await.unwrap().len(), 2);
+        assert!(matches!(
+            db.list_short_codes(3, 10).await,
+            Err(DatabaseError::OffsetTooLarge { offset: 3, max: 2 })
+        ));
+        let too_deep = |result: Result<usize, DatabaseError>| {
+            matches!(
+                result,
+                Err(DatabaseError::OffsetTooLarge { offset: 3, max: 2 })
+            )
+        };
+        assert!(too_deep(
+            db.list_short_codes_with_prefix("Key", 3, 10)
+                .await
+                .map(|v| v.len())
+        ));
+        assert!(too_deep(
+            db.find_by_host("example.com", 3, 10).await.map(|v| v.len())
+        ));
+        assert!(too_deep(
+            db.unclicked_urls(Utc::now(), 3, 10).await.map(|v| v.len())
+        ));
+        assert!(too_deep(
+            db.urls_by_owner_between("key-a", Utc::now(), Utc::now(), 3, 10)
+                .await
+                .map(|v| v.len())
+        ));
+
+        let first = db.list_short_codes_after(None, 3).await.unwrap();
+        assert_eq!(first, ["Key0000", "Key0001", "Key0002"]);
+        let rest = db
+            .list_short_codes_after(first.last().map(String::as_str), 3)
+            .await
+            .unwrap();
+        assert_eq!(rest, ["Key0003"]);
+    }
+
+    #[tokio::test]
+    async fn is_empty_only_counts_links() {
+        let db = test_db().await;
+        db.save_bloom_snapshot("s2l", b"bits").await.unwrap();
+        assert!(db.is_empty().await.unwrap());
+
+        db.insert_url("Empty01", "https://example.com/")
+            .await
+            .unwrap();
+        assert!(!db.is_empty().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn get_url_and_count_stops_at_the_quota() {
+        let db = test_db().await;
+        let (limited, _) = db
+            .insert_url("Quota01", "https://example.com/limited")
+            .await
+            .unwrap();
+        db.insert_alias("quota-alias", limited.id).await.unwrap();
+        db.set_max_clicks(limited.id, Some(2)).await.unwrap();
+
+        assert_eq!(
+            db.get_url_and_count("Quota01").await.unwrap(),
+            "https://example.com/limited"
+        );
+        db.get_url_and_count("quota-alias").await.unwrap();
+        assert!(matches!(
+            db.get_url_and_count("Quota01").await,
+            Err(DatabaseError::QuotaExceeded)
+        ));
+        assert_eq!(db.get_url_admin("Quota01").await.unwrap().click_count, 2);
+        assert!(matches!(
+            db.get_url_and_count("Missing").await,
+            Err(DatabaseError::NotFound)
+        ));
+
+        db.set_max_clicks(limited.id, None).await.unwrap();
+        db.get_url_and_count("Quota01").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn unclicked_urls_lists_old_links_without_clicks() {
+        let db = test_db().await;
+        for (code, created_at, clicks) in [
+            ("Dead001", "2025-01-01 10:00:00", 0),
+            ("Dead002", "2025-01-02 10:00:00", 0),
+            ("Used001", "2025-01-01 11:00:00", 3),
+            ("Newer01", "2025-06-01 10:00:00", 0),
+        ] {
+            let (upsert, _) = db
+                .insert_url(code, &format!("https://example.com/{code}"))
+                .await
+                .unwrap();
+            sqlx::query("UPDATE urls SET created_at = ?, click_count = ? WHERE id = ?")
+                .bind(created_at)
+                .bind(clicks)
+                .bind(upsert.id)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+        }
+        let cutoff = chrono::NaiveDate::from_ymd_opt(2025, 3, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+
+        let codes = |rows: Vec<UrlRecord>| rows.into_iter().map(|r| r.code).collect::<Vec<_>>();
+        assert_eq!(
+            codes(db.unclicked_urls(cutoff, 0, 10).await.unwrap()),
+            ["Dead001", "Dead002"]
+        );
+        assert_eq!(
+            codes(db.unclicked_urls(cutoff, 1, 10).await.unwrap()),
+            ["Dead002"]
+        );
+    }
+
+    #[tokio::test]
+    async fn changed_since_pages_changes_in_update_order() {
+        let db = test_db().await;
+        for (code, updated_at) in [
+            ("Sync001", "2025-11-02 10:00:00"),
+            ("Sync002", "2025-11-01 10:00:00"),
+            ("Sync003", "2025-11-02 10:00:00"),
+            ("Stale01", "2025-10-01 10:00:00"),
+        ] {
+            let (upsert, _) = db
+                .insert_url(code, &format!("https://example.com/{code}"))
+                .await
+                .unwrap();
+            let reason = (code == "Sync003").then_some("takedown");
+            sqlx::query("UPDATE urls SET updated_at = ?, blocked_reason = ? WHERE id = ?")
+                .bind(updated_at)
+                .bind(reason)
+                .bind(upsert.id)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+        }
+        let since = chrono::NaiveDate::from_ymd_opt(2025, 10, 15)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+
+        let changed = db.changed_since(since, None, 10).await.unwrap();
+        let codes = changed.iter().map(|r| r.code.as_str()).collect::<Vec<_>>();
+        assert_eq!(codes, ["Sync002", "Sync001", "Sync003"]);
+        assert_eq!(changed[2].blocked_reason.as_deref(), Some("takedown"));
+        assert_eq!(
+            changed[2].updated_at.map(sqlite_timestamp).as_deref(),
+            Some("2025-11-02 10:00:00")
+        );
+
+        let codes = |rows: Vec<UrlRecord>| rows.into_iter().map(|r| r.code).collect::<Vec<_>>();
+        let first = db.changed_since(since, None, 2).await.unwrap();
+        let last = first.last().unwrap();
+        let (at, id) = (last.updated_at.unwrap(), last.id);
+        assert_eq!(codes(first), ["Sync002", "Sync001"]);
+        assert_eq!(
+            codes(db.changed_since(at, Some(id), 2).await.unwrap()),
+            ["Sync003"]
+        );
+
+        // Resuming a sync repeats the last second rather than skipping it
+        assert_eq!(
+            codes(db.changed_since(at, None, 10).await.unwrap()),
+            ["Sync001", "Sync003"]
+        );
+    }
+
+    #[tokio::test]
+    async fn owner_metadata_and_alias_changes_count_as_link_changes() {
+        let db = test_db().await;
+        let (upsert, _) = db
+            .insert_url("Touch01", "https://example.com/touch")
+            .await
+            .unwrap();
+        let cutoff = chrono::NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+        let reset = || async {
+            sqlx::query("UPDATE urls SET updated_at = '2024-01-01 00:00:00' WHERE id = ?")
+                .bind(upsert.id)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+            assert!(db.changed_since(cutoff, None, 10).await.unwrap().is_empty());
+        };
+
+        reset().await;
+        db.set_link_owner(upsert.id, "0123456789abcdef")
+            .await
+            .unwrap();
+        assert_eq!(db.changed_since(cutoff, None, 10).await.unwrap().len(), 1);
+
+        reset().await;
+        db.reassign_owner("0123456789abcdef", "fedcba9876543210")
+            .await
+            .unwrap();
+        assert_eq!(db.changed_since(cutoff, None, 10).await.unwrap().len(), 1);
+
+        reset().await;
+        db.set_link_metadata(upsert.id, "team", "growth")
+            .await
+            .unwrap();
+        assert_eq!(db.changed_since(cutoff, None, 10).await.unwrap().len(), 1);
+
+        reset().await;
+        db.insert_alias("touch-alias", upsert.id).await.unwrap();
+        assert_eq!(db.changed_since(cutoff, None, 10).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn recently_accessed_orders_by_last_click_and_skips_unused_links() {
+        let db = test_db().await;
+        for (code, accessed_at) in [
+            ("Seen001", Some("2025-11-01 10:00:00")),
+            ("Seen002", Some("2025-11-03 10:00:00")),
+            ("Seen003", Some("2025-11-02 10:00:00")),
+            ("Unseen1", None),
+        ] {
+            let (upsert, _) = db
+                .insert_url(code, &format!("https://example.com/{code}"))
+                .await
+                .unwrap();
+            sqlx::query("UPDATE urls SET last_accessed_at = ? WHERE id = ?")
+                .bind(accessed_at)
+                .bind(upsert.id)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+        }
+
+        let codes: Vec<_> = db
+            .recently_accessed(10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.code)
+            .collect();
+        assert_eq!(codes, ["Seen002", "Seen003", "Seen001"]);
+        assert_eq!(db.recently_accessed(1).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn alias_counts_covers_every_requested_code() {
+        let db = test_db().await;
+        let (many, _) = db
+            .insert_url("Count01", "https://example.com/many")
+            .await
+            .unwrap();
+        db.insert_url("Count02", "https://example.com/none")
+            .await
+            .unwrap();
+        for alias in ["count-a", "count-b"] {
+            db.insert_alias(alias, many.id).await.unwrap();
+        }
+
+        let codes = ["Count01", "Count02", "Missing"].map(String::from);
+        let counts = db.alias_counts(&codes).await.unwrap();
+        assert_eq!(
+            counts,
+            HashMap::from([
+                ("Count01".to_string(), 2),
+                ("Count02".to_string(), 0),
+                ("Missing".to_string(), 0),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn insert_url_with_mode_handles_existing_destinations() {
+        let db = test_db().await;
+        let url = "https://mode.example/";
+        let (first, _) = db
+            .insert_url_with_mode("Mode001", url, InsertMode::Dedup)
+            .await
+            .unwrap();
+        assert!(first.created);
+
+        let (again, record) = db
+            .insert_url_with_mode("Mode002", url, InsertMode::Dedup)
+            .await
+            .unwrap();
+        assert!(!again.created);
+        assert_eq!((again.id, record.code.as_str()), (first.id, "Mode001"));
+
+        assert!(matches!(
+            db.insert_url_with_mode("Mode003", url, InsertMode::FailIfExists)
+                .await,
+            Err(DatabaseError::Duplicate)
+        ));
+        assert!(matches!(
+            db.get_id_by_code("Mode003").await,
+            Err(DatabaseError::NotFound)
+        ));
+
+        let (fresh, record) = db
+            .insert_url_with_mode("Mode004", url, InsertMode::AlwaysNew)
+            .await
+            .unwrap();
+        assert!(fresh.created);
+        assert_eq!((fresh.id, record.code.as_str()), (first.id, "Mode004"));
+        assert_eq!(db.get_url("Mode004").await.unwrap(), url);
+
+        let (other, _) = db
+            .insert_url_with_mode(
+                "Mode005",
+                "https://other.example/",
+                InsertMode::FailIfExists,
+            )
+            .await
+            .unwrap();
+        assert!(other.created);
+    }
+
+    #[tokio::test]
+    async fn storage_stats_counts_rows_and_bloom_bytes() {
+        let db = test_db().await;
+        db.insert_url("Stor001", "https://one.example/")
+            .await
+            .unwrap();
+        let (two, _) = db
+            .insert_url("Stor002", "https://two.example/")
+            .await
+            .unwrap();
+        db.insert_alias("stor-alias", two.id).await.unwrap();
+        db.save_bloom_snapshot("storage", &[7u8; 300])
+            .await
+            .unwrap();
+
+        let stats = db.storage_stats().await.unwrap();
+        assert_eq!(stats.tables.len(), crate::database::STORAGE_TABLES.len());
+        assert_eq!(stats.tables["urls"], 2);
+        assert_eq!(stats.tables["aliases"], 1);
+        assert_eq!(stats.tables["bloom_snapshots"], 1);
+        assert_eq!(stats.bloom_snapshot_bytes, 300);
+        assert!(stats.database_bytes > 0);
+    }
+
+    #[tokio::test]
+    async fn set_expiry_batch_sets_and_clears_expiry_on_primary_codes() {
+        let db = test_db().await;
+        let (first, _) = db
+            .insert_url("Ttl0001", "https://ttl.example/a")
+            .await
+            .unwrap();
+        db.insert_url("Ttl0002", "https://ttl.example/b")
+            .await
+            .unwrap();
+        db.insert_url("Ttl0003", "https://ttl.example/c")
+            .await
+            .unwrap();
+        db.insert_alias("ttl-alias", first.id).await.unwrap();
+        let at = "2031-05-06T07:08:09Z".parse::<DateTime<Utc>>().unwrap();
+        let codes: Vec<String> = ["Ttl0001", "Ttl0002", "ttl-alias", "Missing"]
+            .map(String::from)
+            .to_vec();
+
+        assert_eq!(db.set_expiry_batch(&codes, Some(at)).await.unwrap(), 2);
+        for code in ["Ttl0001", "Ttl0002", "ttl-alias"] {
+            let target = db.get_redirect_target(code).await.unwrap();
+            assert_eq!(target.expires_at, Some(at), "{code}");
+        }
+        assert_eq!(
+            db.get_redirect_target("Ttl0003").await.unwrap().expires_at,
+            None
+        );
+
+        assert_eq!(db.set_expiry_batch(&codes, None).await.unwrap(), 2);
+        assert_eq!(
+            db.get_redirect_target("Ttl0001").await.unwrap().expires_at,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_and_track_records_only_served_clicks() {
+        let db = test_db().await;
+        let (link, _) = db
+            .insert_url("Track01", "https://track.example/")
+            .await
+            .unwrap();
+        db.insert_alias("track-alias", link.id).await.unwrap();
+        let events = |db: &SqliteUrlDatabase| {
+            let pool = db.pool.clone();
+            async move {
+                sqlx::query_as::<_, (String, Option<String>, Option<String>)>(
+                    "SELECT code, referrer, user_agent FROM click_events ORDER BY id",
+                )
+                .fetch_all(&pool)
+                .await
+                .unwrap()
+            }
+        };
+
+        let url = db
+            .resolve_and_track("track-alias", Some("https://ref.example/"), Some("curl/8"))
+            .await
+            .unwrap();
+        assert_eq!(url, "https://track.example/");
+        db.resolve_and_track("Track01", None, None).await.unwrap();
+        assert_eq!(db.get_url_admin("Track01").await.unwrap().click_count, 2);
+        assert_eq!(
+            events(&db).await,
+            [
+                (
+                    "track-alias".to_string(),
+                    Some("https://ref.example/".to_string()),
+                    Some("curl/8".to_string())
+                ),
+                ("Track01".to_string(), None, None),
+            ]
+        );
+
+        db.set_max_clicks(link.id, Some(2)).await.unwrap();
+        assert!(matches!(
+            db.resolve_and_track("Track01", None, None).await,
+            Err(DatabaseError::QuotaExceeded)
+        ));
+        db.set_max_clicks(link.id, None).await.unwrap();
+
+        sqlx::query("UPDATE aliases SET expires_at = '2020-01-01T00:00:00Z' WHERE alias = ?")
+            .bind("track-alias")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert!(matches!(
+            db.resolve_and_track("track-alias", None, None).await,
+            Err(DatabaseError::Expired)
+        ));
+
+        db.block_link("Track01", "takedown").await.unwrap();
+        assert!(matches!(
+            db.resolve_and_track("Track01", None, None).await,
+            Err(DatabaseError::Blocked(reason)) if reason == "takedown"
+        ));
+        assert!(matches!(
+            db.resolve_and_track("Missing", None, None).await,
+            Err(DatabaseError::NotFound)
+        ));
+
+        assert_eq!(db.get_url_admin("Track01").await.unwrap().click_count, 2);
+        assert_eq!(events(&db).await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn oversized_pages_are_refused_by_every_listing() {
+        let db = test_db().await.with_max_page_size(5);
+        db.insert_url("Page001", "https://page.example/")
+            .await
+            .unwrap();
+        let cutoff = Utc::now();
+
+        assert_eq!(db.list_short_codes(0, 5).await.unwrap().len(), 1);
+        let too_large = |result: Result<usize, DatabaseError>| {
+            matches!(
+                result,
+                Err(DatabaseError::PageTooLarge { limit: 6, max: 5 })
+            )
+        };
+        assert!(too_large(db.list_short_codes(0, 6).await.map(|v| v.len())));
+        assert!(too_large(
+            db.list_short_codes_with_prefix("Page", 0, 6)
+                .await
+                .map(|v| v.len())
+        ));
+        assert!(too_large(
+            db.find_by_host("page.example", 0, 6).await.map(|v| v.len())
+        ));
+        assert!(too_large(
+            db.unclicked_urls(cutoff, 0, 6).await.map(|v| v.len())
+        ));
+        assert!(too_large(
+            db.urls_by_owner_between("key-a", cutoff, cutoff, 0, 6)
+                .await
+                .map(|v| v.len())
+        ));
+        assert!(too_large(db.recently_accessed(6).await.map(|v| v.len())));
+        assert!(too_large(db.list_featured(6).await.map(|v| v.len())));
+
+        // The keyset walk pages internally and is not limited
+        assert_eq!(db.list_short_codes_after(None, 50).await.unwrap().len(), 1);
+        let unlimited = db.with_max_page_size(0);
+        assert_eq!(unlimited.list_short_codes(0, 1_000).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn featured_links_are_listed_until_unfeatured_or_deleted() {
+        let db = test_db().await;
+        let (first, _) = db
+            .insert_url("Feat001", "https://featured.example/a")
+            .await
+            .unwrap();
+        db.insert_url("Feat002", "https://featured.example/b")
+            .await
+            .unwrap();
+        db.insert_url("Plain01", "https://featured.example/c")
+            .await
+            .unwrap();
+        db.insert_alias("feat-alias", first.id).await.unwrap();
+
+        db.set_featured("feat-alias", true).await.unwrap();
+        db.set_featured("Feat002", true).await.unwrap();
+        let codes =
+            |records: Vec<UrlRecord>| records.into_iter().map(|r| r.code).collect::<Vec<_>>();
+        assert_eq!(
+            codes(db.list_featured(10).await.unwrap()),
+            ["Feat002", "Feat001"]
+        );
+        assert_eq!(codes(db.list_featured(1).await.unwrap()), ["Feat002"]);
+
+        db.set_featured("Feat002", false).await.unwrap();
+        assert_eq!(codes(db.list_featured(10).await.unwrap()), ["Feat001"]);
+        db.delete_urls_batch(&["Feat001".to_string()])
+            .await
+            .unwrap();
+        assert!(db.list_featured(10).await.unwrap().is_empty());
+        assert!(matches!(
+            db.set_featured("Missing", true).await,
+            Err(DatabaseError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn reassign_owner_moves_every_link_once() {
+        let db = test_db().await;
+        let (leaver, team) = ("00000000000000aa", "00000000000000bb");
+        for (code, owner) in [("Own0001", leaver), ("Own0002", leaver), ("Own0003", team)] {
+            let (upsert, _) = db
+                .insert_url(code, &format!("https://owner.example/{code}"))
+                .await
+                .unwrap();
+            db.set_link_owner(upsert.id, owner).await.unwrap();
+        }
+
+        assert_eq!(db.reassign_owner(leaver, team).await.unwrap(), 2);
+        assert_eq!(db.reassign_owner(leaver, team).await.unwrap(), 0);
+        for code in ["Own0001", "Own0002", "Own0003"] {
+            let owner = db.get_url_admin(code).await.unwrap().owner_key_id;
+            assert_eq!(owner.as_deref(), Some(team), "{code}");
+        }
+        for bad in ["", "not-a-key", "00000000000000BB"] {
+            assert!(matches!(
+                db.reassign_owner(team, bad).await,
+                Err(DatabaseError::InvalidReference)
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn list_short_codes_with_prefix_matches_wildcards_literally() {
+        let db = test_db().await;
+        for (code, url) in [
+            ("m_abc", "https://example.com/1"),
+            ("m_def", "https://example.com/2"),
+            ("mXabc", "https://example.com/3"),
+            ("M_xyz", "https://example.com/4"),
+            ("other", "https://example.com/5"),
+        ] {
+            db.insert_url(code, url).await.unwrap();
+        }
+
+        let mut codes = db.list_short_codes_with_prefix("m_", 0, 10).await.unwrap();
+        codes.sort();
+        assert_eq!(codes, vec!["m_abc".to_string(), "m_def".to_string()]);
+
+        let first = db.list_short_codes_with_prefix("m", 0, 2).await.unwrap();
+        let rest = db.list_short_codes_with_prefix("m", 2, 2).await.unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(rest.len(), 1);
+
+        assert!(
+            db.list_short_codes_with_prefix("%", 0, 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_chain_follows_alias_to_primary_code() {
+        let db = test_db().await;
+        let (upsert, _) = db
+            .insert_url("Chain01", "https://example.com/chain")
+            .await
+            .unwrap();
+        db.insert_alias("chainAlias", upsert.id).await.unwrap();
+
+        assert_eq!(
+            db.resolve_chain("chainAlias").await.unwrap(),
+            ["chainAlias", "Chain01"]
+        );
+        assert_eq!(db.resolve_chain("Chain01").await.unwrap(), ["Chain01"]);
+        assert!(matches!(
+            db.resolve_chain("missing").await,
+            Err(DatabaseError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn resolve_chain_reports_induced_cycles() {
+        let db = test_db().await;
+        db.insert_url("LoopA01", "https://example.com/a")
+            .await
+            .unwrap();
+        db.insert_url("LoopB01", "https://example.com/b")
+            .await
+            .unwrap();
+
+        // The schema cannot express alias-to-alias links, so shadow the view
+        // with one where each code forwards to the other
+        sqlx::query(
+            "CREATE TEMP VIEW all_short_codes AS \
+             SELECT a.code AS code, b.id AS target_id, b.url AS url, 'alias' AS source \
+             FROM urls a JOIN urls b ON a.code <> b.code",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+        match db.resolve_chain("LoopA01").await {
+            Err(DatabaseError::CyclicAlias(chain)) => {
+                assert_eq!(chain, ["LoopA01", "LoopB01", "LoopA01"]);
+            }
+            other => panic!("expected a cycle, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn get_id_by_code_resolves_codes_and_aliases() {
+        let db = test_db().await;
+        let (upsert, _) = db
+            .insert_url("Abc1234", "https://example.com/")
+            .await
+            .unwrap();
+        db.insert_alias("myAlias", upsert.id).await.unwrap();
+
+        assert_eq!(db.get_id_by_code("Abc1234").await.unwrap(), upsert.id);
+        assert_eq!(db.get_id_by_code("myAlias").await.unwrap(), upsert.id);
+        assert!(matches!(
+            db.get_id_by_code("missing").await,
+            Err(DatabaseError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn urls_by_owner_between_filters_by_owner_and_window() {
+        let db = test_db().await;
+        for (code, owner, created_at) in [
+            ("Own0001", "key-a", "2025-11-01 10:00:00"),
+            ("Own0002", "key-a", "2025-11-01 10:30:00"),
+            ("Own0003", "key-a", "2025-11-01 12:00:00"),
+            ("Own0004", "key-b", "2025-11-01 10:15:00"),
+        ] {
+            let (upsert, _) = db
+                .insert_url(code, &format!("https://example.com/{code}"))
+                .await
+                .unwrap();
+            db.set_link_owner(upsert.id, owner).await.unwrap();
+            sqlx::query("UPDATE urls SET created_at = ? WHERE id = ?")
+                .bind(created_at)
+                .bind(upsert.id)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+        }
+
+        let at = |s: &str| {
+            chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+                .unwrap()
+                .and_utc()
+        };
+        let from = at("2025-11-01 10:00:00");
+        let to = at("2025-11-01 11:00:00");
+
+        let rows = db
+            .urls_by_owner_between("key-a", from, to, 0, 10)
+            .await
+            .unwrap();
+        let codes: Vec<_> = rows.iter().map(|r| r.code.as_str()).collect();
+        assert_eq!(codes, ["Own0001", "Own0002"]);
+        assert_eq!(rows[0].created_at, Some(from));
+        assert_eq!(rows[0].owner_key_id.as_deref(), Some("key-a"));
+
+        let page = db
+            .urls_by_owner_between("key-a", from, to, 1, 10)
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].code, "Own0002");
+    }
+
+    #[tokio::test]
+    async fn find_by_host_matches_the_host_component() {
+        let db = test_db().await;
+        for (code, url) in [
+            ("Host001", "https://evil.example/a"),
+            ("Host002", "https://user@EVIL.example:8443/b"),
+            ("Host003", "https://cdn.evil.example/c"),
+            (
+                "Host004",
+                "https://good.example/?next=https://evil.example/",
+            ),
+            ("Host005", "https://notevil.example/d"),
+        ] {
+            db.insert_url(code, url).await.unwrap();
+        }
+        let codes = |rows: Vec<UrlRecord>| rows.into_iter().map(|r| r.code).collect::<Vec<_>>();
+
+        let exact = db.find_by_host("Evil.Example", 0, 10).await.unwrap();
+        assert_eq!(codes(exact), ["Host001", "Host002"]);
+
+        let wildcard = db.find_by_host("*.evil.example", 0, 10).await.unwrap();
+        assert_eq!(codes(wildcard), ["Host001", "Host002", "Host003"]);
+
+        let page = db.find_by_host("*.evil.example", 1, 1).await.unwrap();
+        assert_eq!(codes(page), ["Host002"]);
+    }
+
+    #[tokio::test]
+    async fn export_owner_writes_only_that_owners_links() {
+        let db = test_db().await;
+        for (code, owner) in [
+            ("Exp0001", "key-a"),
+            ("Exp0002", "key-b"),
+            ("Exp0003", "key-a"),
+        ] {
+            let (upsert, _) = db
+                .insert_url(code, &format!("https://example.com/{code}"))
+                .await
+                .unwrap();
+            db.set_link_owner(upsert.id, owner).await.unwrap();
+            db.insert_alias(&format!("{code}-alias"), upsert.id)
+                .await
+                .unwrap();
+            db.set_link_metadata(upsert.id, "team", owner)
+                .await
+                .unwrap();
+        }
+
+        let mut out = Vec::new();
+        let written = db.export_owner("key-a", &mut out).await.unwrap();
+        assert_eq!(written, 2);
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["code"], "Exp0001");
+        assert_eq!(lines[0]["aliases"], serde_json::json!(["Exp0001-alias"]));
+        assert_eq!(lines[0]["metadata"]["team"], "key-a");
+        assert_eq!(lines[1]["code"], "Exp0003");
+        let owner_key_id = if cfg!(feature = "camel-case-json") {
+            "ownerKeyId"
+        } else {
+            "owner_key_id"
+        };
+        assert!(lines.iter().all(|l| l[owner_key_id] == "key-a"));
+
+        let mut empty = Vec::new();
+        assert_eq!(db.export_owner("key-c", &mut empty).await.unwrap(), 0);
+        assert!(empty.is_empty());
+    }
+
+    #[tokio::test]
+    async fn find_hash_collisions_groups_urls_sharing_a_hash() {
+        let db = test_db().await;
+        db.insert_url("Hash001", "https://unique.example/")
+            .await
+            .unwrap();
+        assert!(db.find_hash_collisions().await.unwrap().is_empty());
+
+        // `url_hash` is UNIQUE in the real table, so shadow it with a temp
+        // table the way a hasher migration would leave it
+        sqlx::query(
+            "CREATE TEMP TABLE urls (id INTEGER PRIMARY KEY, code TEXT, url TEXT, url_hash BLOB)",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        for (code, url, hash) in [
+            ("Hash001", "https://a.example/", &b"same"[..]),
+            ("Hash002", "https://b.example/", &b"same"[..]),
+            ("Hash003", "https://a.example/", &b"same"[..]),
+            ("Hash004", "https://c.example/", &b"other"[..]),
+        ] {
+            sqlx::query("INSERT INTO urls(code, url, url_hash) VALUES (?, ?, ?)")
+                .bind(code)
+                .bind(url)
+                .bind(hash)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+        }
+
+        let collisions = db.find_hash_collisions().await.unwrap();
+        assert_eq!(
+            collisions,
+            vec![(
+                b"same".to_vec(),
+                vec![
+                    "https://a.example/".to_string(),
+                    "https://b.example/".to_string()
+                ]
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn swap_codes_exchanges_destinations() {
+        let db = test_db().await;
+        db.insert_url("SwapAAA", "https://a.example/")
+            .await
+            .unwrap();
+        let (b, _) = db
+            .insert_url("SwapBBB", "https://b.example/")
+            .await
+            .unwrap();
+        db.insert_alias("swapAlias", b.id).await.unwrap();
+
+        db.swap_codes("SwapAAA", "SwapBBB").await.unwrap();
+
+        assert_eq!(db.get_url("SwapAAA").await.unwrap(), "https://b.example/");
+        assert_eq!(db.get_url("SwapBBB").await.unwrap(), "https://a.example/");
+        assert_eq!(db.get_url("swapAlias").await.unwrap(), "https://b.example/");
+        assert!(matches!(
+            db.swap_codes("SwapAAA", "missing").await,
+            Err(DatabaseError::NotFound)
+        ));
+        assert!(matches!(
+            db.swap_codes("swapAlias", "SwapBBB").await,
+            Err(DatabaseError::NotFound)
+        ));
+        assert_eq!(db.get_url("SwapAAA").await.unwrap(), "https://b.example/");
+    }
+
+    #[tokio::test]
+    async fn retarget_alias_moves_the_alias_to_the_new_link() {
+        let db = test_db().await;
+        let (old, _) = db
+            .insert_url("Old1234", "https://old.example/")
+            .await
+            .unwrap();
+        let (new, _) = db
+            .insert_url("New1234", "https://new.example/")
+            .await
+            .unwrap();
+        db.insert_alias("vanity", old.id).await.unwrap();
+
+        db.retarget_alias("vanity", "New1234").await.unwrap();
+        assert_eq!(db.get_url("vanity").await.unwrap(), "https://new.example/");
+        assert_eq!(db.get_id_by_code("vanity").await.unwrap(), new.id);
+
+        assert!(matches!(
+            db.retarget_alias("missing", "New1234").await,
+            Err(DatabaseError::NotFound)
+        ));
+        assert!(matches!(
+            db.retarget_alias("vanity", "Nope123").await,
+            Err(DatabaseError::NotFound)
+        ));
+        assert_eq!(db.get_url("vanity").await.unwrap(), "https://new.example/");
+    }
+
+    #[tokio::test]
+    async fn bloom_snapshots_above_the_threshold_round_trip_in_chunks() {
+        let db = test_db().await.with_snapshot_chunk_bytes(4);
+        let big: Vec<u8> = (0..10).collect();
+
+        db.save_bloom_snapshot("s2l", &big).await.unwrap();
+        let chunks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM bloom_snapshot_chunks")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(chunks, 3);
+        assert_eq!(db.load_bloom_snapshot("s2l").await.unwrap(), Some(big));
+
+        // Shrinking back below the threshold stores it inline again
+        db.save_bloom_snapshot("s2l", &[7, 8]).await.unwrap();
+        let chunks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM bloom_snapshot_chunks")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(chunks, 0);
+        assert_eq!(
+            db.load_bloom_snapshot("s2l").await.unwrap(),
+            Some(vec![7, 8])
+        );
+        assert_eq!(db.load_bloom_snapshot("missing").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn bloom_snapshot_files_round_trip_and_reject_foreign_versions() {
+        let db = test_db().await;
+        let path = std::env::temp_dir().join(format!("bloom-{}.snap", std::process::id()));
+        let payload = [&3u32.to_be_bytes()[..], &[0xAB; 16]].concat();
+        db.save_bloom_snapshot("s2l", &payload).await.unwrap();
+
+        assert_eq!(db.export_bloom_to_path("s2l", &path).await.unwrap(), 20);
+        assert_eq!(
+            db.import_bloom_from_path("restored", &path).await.unwrap(),
+            20
+        );
+        assert_eq!(
+            db.load_bloom_snapshot("restored").await.unwrap().as_deref(),
+            Some(&payload[..])
+        );
+
+        let mut file = std::fs::read(&path).unwrap();
+        file[11] = 9;
+        std::fs::write(&path, &file).unwrap();
+        let err = db.import_bloom_from_path("s2l", &path).await.unwrap_err();
+        assert!(
+            matches!(&err, DatabaseError::InvalidSnapshot(msg) if msg.contains("version 9")),
+            "{err}"
+        );
+        assert!(matches!(
+            db.export_bloom_to_path("missing", &path).await,
+            Err(DatabaseError::NotFound)
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn prune_bloom_snapshots_keeps_the_latest_per_name() {
+        let db = test_db().await;
+        for version in 1..=3u8 {
+            db.save_bloom_snapshot("s2l", &[version]).await.unwrap();
+        }
+        db.save_bloom_snapshot("other", &[9]).await.unwrap();
+
+        assert_eq!(db.prune_bloom_snapshots(1).await.unwrap(), 0);
+        assert_eq!(db.load_bloom_snapshot("s2l").await.unwrap(), Some(vec![3]));
+        assert_eq!(
+            db.load_bloom_snapshot("other").await.unwrap(),
+            Some(vec![9])
+        );
+
+        assert_eq!(db.prune_bloom_snapshots(0).await.unwrap(), 2);
+        assert_eq!(db.load_bloom_snapshot("s2l").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn self_check_passes_on_a_migrated_database() {
+        let db = test_db().await;
+        let report = db.self_check().await.unwrap();
+        assert!(report.is_ok(), "{report}");
+    }
+
+    #[tokio::test]
+    async fn self_check_reports_schema_drift() {
+        let db = test_db().await;
+        sqlx::query("DROP INDEX aliases_target_id_idx")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        sqlx::query("DROP VIEW all_short_codes")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        sqlx::query("CREATE VIEW all_short_codes AS SELECT code, id AS target_id FROM urls")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        sqlx::query("PRAGMA foreign_keys = OFF")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let report = db.self_check().await.unwrap();
+
+        assert_eq!(report.discrepancies.len(), 3, "{report}");
+        assert!(report.discrepancies[0].contains("foreign key enforcement"));
+        assert!(report.discrepancies[1].contains("aliases_target_id_idx"));
+        assert!(report.discrepancies[2].contains("all_short_codes"));
+    }
 }
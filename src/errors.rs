@@ -57,7 +57,11 @@ use axum::response::{IntoResponse, Response};
 /// - `Unauthorized` - Authentication required or failed (401)
 /// - `Forbidden` - Access denied (403)
 /// - `Conflict` - Resource conflict (409)
+/// - `Gone` - Resource is no longer available (410)
+/// - `UnavailableForLegalReasons` - Resource was removed for legal reasons (451)
 /// - `Internal` - Server internal error (500)
+/// - `ServiceUnavailable` - Temporarily unable to handle the request, e.g. during maintenance (503)
+/// - `GatewayTimeout` - The request did not finish within its timeout (504)
 /// - `Unprocessable` - Request data is valid but cannot be processed (422)
 /// - `Tera` - Template rendering error (500)
 ///
@@ -102,10 +106,26 @@ pub enum ApiError {
     #[error("Conflict: {0}")]
     Conflict(String),
 
+    /// Gone error - resource existed but is no longer available
+    #[error("Gone: {0}")]
+    Gone(String),
+
+    /// Unavailable for legal reasons - resource was taken down (451)
+    #[error("Unavailable for legal reasons: {0}")]
+    UnavailableForLegalReasons(String),
+
     /// Internal server error - unexpected server error
     #[error("Internal server error: {0}")]
     Internal(String),
 
+    /// Service unavailable - temporarily unable to handle the request (503)
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
+
+    /// Gateway timeout - the request or a query it waited on ran out of time (504)
+    #[error("Gateway timeout: {0}")]
+    GatewayTimeout(String),
+
     /// Unprocessable entity error - valid data that cannot be processed
     #[error("Unprocessable entity: {0}")]
     Unprocessable(String),
@@ -159,8 +179,14 @@ impl IntoResponse for ApiError {
             ApiError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
             ApiError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
             ApiError::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            ApiError::Gone(msg) => (StatusCode::GONE, msg),
+            ApiError::UnavailableForLegalReasons(msg) => {
+                (StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS, msg)
+            }
             ApiError::Unprocessable(msg) => (StatusCode::UNPROCESSABLE_ENTITY, msg),
+            ApiError::GatewayTimeout(msg) => (StatusCode::GATEWAY_TIMEOUT, msg),
             ApiError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+            ApiError::ServiceUnavailable(msg) => (StatusCode::SERVICE_UNAVAILABLE, msg),
             ApiError::Tera(msg) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("Tera template rendering error: {msg}"),
@@ -0,0 +1,64 @@
+//! Code-space usage estimate.
+//!
+//! Random engines only start to hurt once a noticeable share of the code
+//! space is taken: every generated candidate then has a matching chance of
+//! colliding and being retried. [`check`] compares the number of stored codes
+//! with [`ShortenerConfig::code_space`] so operators can raise
+//! `shortener.length` before that happens.
+
+use crate::configuration::CodeSpaceSettings;
+use crate::database::{DatabaseError, UrlDatabase};
+use crate::generator::config::ShortenerConfig;
+
+/// Fraction of `space` taken by `used` codes, clamped to `0.0..=1.0`.
+///
+/// An empty space (for example an unparseable pattern) counts as full.
+pub fn used_fraction(used: u64, space: u128) -> f64 {
+    if space == 0 {
+        return 1.0;
+    }
+    (used as f64 / space as f64).min(1.0)
+}
+
+/// Counts stored links and aliases and reports how much of the configured
+/// code space they occupy.
+///
+/// The ratio is always emitted as the `code_space_used_ratio` gauge on the
+/// `metrics` tracing target; crossing `settings.warn_ratio` additionally logs
+/// a warning.
+pub async fn check(
+    db: &dyn UrlDatabase,
+    shortener: &ShortenerConfig,
+    settings: &CodeSpaceSettings,
+) -> Result<f64, DatabaseError> {
+    let stats = db.aggregate_stats().await?;
+    let used = (stats.total_links + stats.total_aliases).max(0) as u64;
+    let space = shortener.code_space();
+    let ratio = used_fraction(used, space);
+
+    tracing::debug!(target: "metrics", code_space_used_ratio = ratio, used, space = %space);
+    if ratio >= settings.warn_ratio {
+        tracing::warn!(
+            code_space_used_ratio = ratio,
+            used,
+            space = %space,
+            code_length = shortener.length,
+            "code space is {:.1}% used; consider increasing shortener.length",
+            ratio * 100.0
+        );
+    }
+    Ok(ratio)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn used_fraction_is_clamped() {
+        assert_eq!(used_fraction(0, 100), 0.0);
+        assert_eq!(used_fraction(50, 100), 0.5);
+        assert_eq!(used_fraction(500, 100), 1.0);
+        assert_eq!(used_fraction(1, 0), 1.0);
+    }
+}
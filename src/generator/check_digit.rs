@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use super::{GeneratorError, ShortCodeGenerator};
+
+/// Luhn mod N check character for `body` over `alphabet`.
+///
+/// Detects every single-character substitution and most adjacent
+/// transpositions. Returns `None` if `body` contains characters outside
+/// `alphabet`.
+pub fn check_char(body: &str, alphabet: &[char]) -> Option<char> {
+    let n = alphabet.len();
+    let mut sum = 0;
+    for (i, c) in body.chars().rev().enumerate() {
+        let mut addend = alphabet.iter().position(|a| *a == c)?;
+        if i % 2 == 0 {
+            addend *= 2;
+            addend = addend / n + addend % n;
+        }
+        sum += addend;
+    }
+    Some(alphabet[(n - sum % n) % n])
+}
+
+/// Returns `true` when the last character of `code` is the check character
+/// of the rest.
+pub fn verify(code: &str, alphabet: &[char]) -> bool {
+    let mut chars = code.chars();
+    let Some(last) = chars.next_back() else {
+        return false;
+    };
+    check_char(chars.as_str(), alphabet) == Some(last)
+}
+
+/// Wraps another generator and appends a [`check_char`] to every code, making
+/// codes one character longer than the inner engine's.
+pub struct CheckDigitEngine {
+    inner: Arc<dyn ShortCodeGenerator>,
+    alphabet: Vec<char>,
+}
+
+impl CheckDigitEngine {
+    pub fn new(inner: Arc<dyn ShortCodeGenerator>, alphabet: Vec<char>) -> Self {
+        CheckDigitEngine { inner, alphabet }
+    }
+}
+
+impl ShortCodeGenerator for CheckDigitEngine {
+    fn generate(&self) -> Result<String, GeneratorError> {
+        self.generate_retry(0)
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn generate_retry(&self, attempt: u32) -> Result<String, GeneratorError> {
+        let mut code = self.inner.generate_retry(attempt)?;
+        let check = check_char(&code, &self.alphabet).ok_or(GeneratorError::Internal(
+            "generated code contains characters outside the alphabet",
+        ))?;
+        code.push(check);
+        Ok(code)
+    }
+
+    fn validate(&self, code: &str) -> bool {
+        let mut body = code.chars();
+        body.next_back();
+        verify(code, &self.alphabet) && self.inner.validate(body.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{DEFAULT_ALPHABET, NanoIdEngine};
+
+    #[test]
+    fn generated_codes_round_trip() {
+        let engine = CheckDigitEngine::new(
+            Arc::new(NanoIdEngine::new(7, None)),
+            DEFAULT_ALPHABET.to_vec(),
+        );
+        for _ in 0..1_000 {
+            let code = engine.generate().unwrap();
+            assert_eq!(code.len(), 8);
+            assert!(verify(&code, DEFAULT_ALPHABET), "{code}");
+            assert!(engine.validate(&code), "{code}");
+        }
+        assert!(!engine.validate("aZq042x"));
+        assert!(!engine.validate(""));
+    }
+
+    #[test]
+    fn single_character_typos_are_detected() {
+        let body = "aZq042x";
+        let code = format!("{body}{}", check_char(body, DEFAULT_ALPHABET).unwrap());
+
+        for i in 0..code.len() {
+            for c in DEFAULT_ALPHABET {
+                let mut tampered: Vec<char> = code.chars().collect();
+                if tampered[i] == *c {
+                    continue;
+                }
+                tampered[i] = *c;
+                let tampered: String = tampered.into_iter().collect();
+                assert!(!verify(&tampered, DEFAULT_ALPHABET), "{tampered}");
+            }
+        }
+    }
+
+    #[test]
+    fn verify_rejects_empty_and_foreign_codes() {
+        assert!(!verify("", DEFAULT_ALPHABET));
+        assert!(!verify("ab-c", DEFAULT_ALPHABET));
+    }
+}
@@ -1,6 +1,10 @@
 use serde::Deserialize;
 use std::path::PathBuf;
 
+use crate::database::MAX_ALIAS_LENGTH;
+use crate::generator::DEFAULT_ALPHABET;
+use crate::generator::pattern::CodePattern;
+
 /// Top-level configuration for the short code generator.
 #[derive(Clone, Debug, Deserialize)]
 pub struct ShortenerConfig {
@@ -8,6 +12,67 @@ pub struct ShortenerConfig {
     pub alphabet: Option<String>,
     pub engine: EngineConfig,
     pub bit_layout: Option<BitLayoutConfig>,
+    /// Optional code shape such as `LLLDDD` (see [`CodePattern`]).
+    ///
+    /// When set, it replaces `length` and is only supported by the nanoid engine.
+    #[serde(default)]
+    pub code_pattern: Option<String>,
+    /// Append a check character (see [`check_digit`](crate::generator::check_digit))
+    /// so mistyped codes are rejected without a database lookup. Codes become
+    /// one character longer.
+    #[serde(default)]
+    pub check_digit: bool,
+    /// Letter case of generated codes (see [`CodeCase`]).
+    ///
+    /// Changing it only affects new codes. Existing ones keep their case,
+    /// and stay reachable unless `case_insensitive_lookup` is also turned on.
+    #[serde(default)]
+    pub code_case: CodeCase,
+    /// Fold incoming codes and new aliases to a single-case `code_case`, so
+    /// lookups ignore letter case.
+    ///
+    /// Folded lookups never match a stored code or alias in another
+    /// case. Switching an existing `mixed` deployment over with this on
+    /// makes every mixed-case code unreachable, so only enable it for data
+    /// created under the same `code_case`.
+    #[serde(default)]
+    pub case_insensitive_lookup: bool,
+}
+
+/// Letter case codes are generated in.
+///
+/// `Mixed` keeps the alphabet as configured and has the most entropy per
+/// character (62 symbols for the default alphabet against 36 otherwise), so
+/// it needs the shortest codes for a given code space. `Lower` and `Upper`
+/// fold the alphabet to a single case so codes survive clients and
+/// middleboxes that change case in transit. Lookups only become
+/// case-insensitive with [`ShortenerConfig::case_insensitive_lookup`].
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeCase {
+    #[default]
+    Mixed,
+    Lower,
+    Upper,
+}
+
+impl CodeCase {
+    /// `code` in this case; unchanged for `Mixed`.
+    pub fn fold(self, code: &str) -> String {
+        match self {
+            CodeCase::Mixed => code.to_string(),
+            CodeCase::Lower => code.to_lowercase(),
+            CodeCase::Upper => code.to_uppercase(),
+        }
+    }
+
+    fn fold_char(self, c: char) -> char {
+        match self {
+            CodeCase::Mixed => c,
+            CodeCase::Lower => c.to_ascii_lowercase(),
+            CodeCase::Upper => c.to_ascii_uppercase(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -15,13 +80,17 @@ pub struct EngineConfig {
     pub kind: EngineKind,
     pub nanoid: Option<NanoIdConfig>,
     pub sequence: Option<SequenceConfig>,
+    #[serde(default)]
+    pub words: Option<WordsConfig>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum EngineKind {
     Sequence,
     Nanoid,
+    /// Memorable adjective-noun codes; needs the `word-lists` feature
+    Words,
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
@@ -34,6 +103,24 @@ pub struct SequenceConfig {
     pub state_path: Option<PathBuf>,
 }
 
+/// Shape of word-list codes such as `brave-otter-42`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct WordsConfig {
+    /// Placed between words and before the suffix; a single character
+    /// outside letters, digits and `/`
+    pub separator: String,
+    /// Use only the first `adjectives` words of the embedded list; 0 for all
+    pub adjectives: usize,
+    /// Use only the first `nouns` words of the embedded list; 0 for all
+    pub nouns: usize,
+    /// Digits of the numeric suffix; 0 for none
+    pub suffix_digits: u32,
+}
+
+/// Longest `suffix_digits` accepted, which keeps codes far below
+/// [`MAX_ALIAS_LENGTH`] even after escalation.
+const MAX_SUFFIX_DIGITS: u32 = 8;
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct BitLayoutConfig {
     pub enabled: bool,
@@ -45,6 +132,75 @@ pub struct BitLayoutConfig {
 }
 
 impl ShortenerConfig {
+    /// `code` as lookups and new aliases see it: folded to `code_case` with
+    /// `case_insensitive_lookup`, unchanged otherwise.
+    pub fn lookup_code(&self, code: &str) -> String {
+        if self.case_insensitive_lookup {
+            self.code_case.fold(code)
+        } else {
+            code.to_string()
+        }
+    }
+
+    /// Characters codes are drawn from: the configured alphabet or
+    /// [`DEFAULT_ALPHABET`], folded to `code_case` with duplicates dropped.
+    pub fn alphabet_chars(&self) -> Vec<char> {
+        let raw = match &self.alphabet {
+            Some(alpha) => alpha.chars().collect(),
+            None => DEFAULT_ALPHABET.to_vec(),
+        };
+        let mut chars: Vec<char> = Vec::with_capacity(raw.len());
+        for c in raw.into_iter().map(|c| self.code_case.fold_char(c)) {
+            if !chars.contains(&c) {
+                chars.push(c);
+            }
+        }
+        chars
+    }
+
+    /// Alphabet handed to the engines: the configured one as is for `Mixed`,
+    /// otherwise the folded [`alphabet_chars`](Self::alphabet_chars).
+    pub fn engine_alphabet(&self) -> Option<String> {
+        match self.code_case {
+            CodeCase::Mixed => self.alphabet.clone(),
+            _ => Some(self.alphabet_chars().into_iter().collect()),
+        }
+    }
+
+    /// Number of distinct codes the configured generator can produce (saturating).
+    ///
+    /// This is `alphabet_len ^ length`, or the pattern's own space when
+    /// `code_pattern` is set. The check character, if enabled, adds no
+    /// codes. Used to estimate how close the dataset is to
+    /// exhausting the code space.
+    pub fn code_space(&self) -> u128 {
+        if self.engine.kind == EngineKind::Words {
+            return self.words_code_space();
+        }
+        let alphabet = self.alphabet_chars();
+        match &self.code_pattern {
+            Some(raw) => CodePattern::parse(raw, &alphabet)
+                .map(|pattern| pattern.code_space())
+                .unwrap_or(0),
+            None => {
+                (0..self.length).fold(1u128, |acc, _| acc.saturating_mul(alphabet.len() as u128))
+            }
+        }
+    }
+
+    #[cfg(feature = "word-lists")]
+    fn words_code_space(&self) -> u128 {
+        let Some(words) = &self.engine.words else {
+            return 0;
+        };
+        crate::generator::WordListEngine::new(words).code_space()
+    }
+
+    #[cfg(not(feature = "word-lists"))]
+    fn words_code_space(&self) -> u128 {
+        0
+    }
+
     pub fn validate(&self) -> Result<(), String> {
         if self.length < 5 {
             return Err("shortener.length must be >= 5".into());
@@ -59,10 +215,78 @@ impl ShortenerConfig {
             if chars.windows(2).any(|w| w[0] == w[1]) {
                 return Err("shortener.alphabet has duplicate characters".into());
             }
+            if self.alphabet_chars().len() < 2 {
+                return Err("shortener.alphabet must keep at least 2 chars under code_case".into());
+            }
+        }
+
+        if let Some(raw) = &self.code_pattern {
+            if self.engine.kind != EngineKind::Nanoid {
+                return Err("shortener.code_pattern requires engine.kind=nanoid".into());
+            }
+            let pattern = CodePattern::parse(raw, &self.alphabet_chars())?;
+            if pattern.len() > MAX_ALIAS_LENGTH {
+                return Err(format!(
+                    "shortener.code_pattern must not exceed {} characters",
+                    MAX_ALIAS_LENGTH
+                ));
+            }
+        }
+
+        // The sequence engine encodes in base 62 and cannot use a folded alphabet
+        if self.case_insensitive_lookup && self.code_case == CodeCase::Mixed {
+            return Err(
+                "shortener.case_insensitive_lookup requires a single-case code_case".into(),
+            );
+        }
+
+        if self.code_case != CodeCase::Mixed && self.engine.kind != EngineKind::Nanoid {
+            return Err("shortener.code_case requires engine.kind=nanoid".into());
+        }
+
+        if self.check_digit {
+            let len = match &self.code_pattern {
+                Some(raw) => raw.chars().count(),
+                None => self.length,
+            };
+            if len + 1 > MAX_ALIAS_LENGTH {
+                return Err(format!(
+                    "shortener.check_digit needs codes of at most {} characters",
+                    MAX_ALIAS_LENGTH - 1
+                ));
+            }
         }
 
         match self.engine.kind {
             EngineKind::Nanoid => {}
+            EngineKind::Words => {
+                if !cfg!(feature = "word-lists") {
+                    return Err("engine.kind=words needs the word-lists feature".into());
+                }
+                let words = self
+                    .engine
+                    .words
+                    .as_ref()
+                    .ok_or("engine.words must be provided when kind=Words")?;
+                let mut separator = words.separator.chars();
+                match (separator.next(), separator.next()) {
+                    (Some(c), None) if !c.is_alphanumeric() && c != '/' => {}
+                    _ => {
+                        return Err("engine.words.separator must be one non-alphanumeric character other than /".into());
+                    }
+                }
+                if words.suffix_digits > MAX_SUFFIX_DIGITS {
+                    return Err(format!(
+                        "engine.words.suffix_digits must be <= {}",
+                        MAX_SUFFIX_DIGITS
+                    ));
+                }
+                if self.check_digit {
+                    return Err(
+                        "shortener.check_digit is not supported with engine.kind=words".into(),
+                    );
+                }
+            }
             EngineKind::Sequence => {
                 let seq = self
                     .engine
@@ -87,3 +311,87 @@ impl ShortenerConfig {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(code_pattern: Option<&str>, kind: EngineKind) -> ShortenerConfig {
+        ShortenerConfig {
+            length: 7,
+            alphabet: None,
+            engine: EngineConfig {
+                kind,
+                nanoid: None,
+                sequence: Some(SequenceConfig {
+                    block_size: 16,
+                    persist_interval: 16,
+                    state_path: None,
+                }),
+                words: None,
+            },
+            bit_layout: None,
+            code_pattern: code_pattern.map(str::to_string),
+            check_digit: false,
+            code_case: CodeCase::Mixed,
+            case_insensitive_lookup: false,
+        }
+    }
+
+    #[test]
+    fn code_space_follows_length_or_pattern() {
+        assert_eq!(config(None, EngineKind::Nanoid).code_space(), 62u128.pow(7));
+        assert_eq!(
+            config(Some("LLLDDD"), EngineKind::Nanoid).code_space(),
+            52u128.pow(3) * 1_000
+        );
+    }
+
+    #[test]
+    fn single_case_folds_the_alphabet() {
+        let mut cfg = config(None, EngineKind::Nanoid);
+        cfg.code_case = CodeCase::Lower;
+        let alphabet = cfg.alphabet_chars();
+        assert_eq!(alphabet.len(), 36);
+        assert!(alphabet.iter().all(|c| !c.is_ascii_uppercase()));
+        assert_eq!(cfg.code_space(), 36u128.pow(7));
+
+        cfg.code_case = CodeCase::Upper;
+        assert!(cfg.alphabet_chars().iter().all(|c| !c.is_ascii_lowercase()));
+        assert_eq!(CodeCase::Upper.fold("aBc12"), "ABC12");
+        assert_eq!(CodeCase::Mixed.fold("aBc12"), "aBc12");
+
+        let mut sequence = config(None, EngineKind::Sequence);
+        sequence.code_case = CodeCase::Lower;
+        assert!(sequence.validate().is_err());
+    }
+
+    #[test]
+    fn lookups_are_only_folded_when_opted_in() {
+        let mut cfg = config(None, EngineKind::Nanoid);
+        cfg.code_case = CodeCase::Lower;
+        assert_eq!(cfg.lookup_code("aBc12"), "aBc12");
+
+        cfg.case_insensitive_lookup = true;
+        assert!(cfg.validate().is_ok());
+        assert_eq!(cfg.lookup_code("aBc12"), "abc12");
+
+        cfg.code_case = CodeCase::Mixed;
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn validate_checks_code_pattern() {
+        assert!(
+            config(Some("LLLDDD"), EngineKind::Nanoid)
+                .validate()
+                .is_ok()
+        );
+        assert!(config(Some("LLL?"), EngineKind::Nanoid).validate().is_err());
+        assert!(
+            config(Some("LLLDDD"), EngineKind::Sequence)
+                .validate()
+                .is_err()
+        );
+    }
+}
@@ -26,20 +26,53 @@ impl From<std::io::Error> for GeneratorError {
 
 /// A generator is only responsible for **producing candidate short codes**.
 /// It does **not** handle deduplication, database writes, or caching.
+///
+/// This is also the extension point for custom code schemes: implement it and
+/// hand the generator to
+/// [`Application::build_with_generator`](crate::startup::Application::build_with_generator).
 pub trait ShortCodeGenerator: Send + Sync {
     /// Generate a new short code.
     fn generate(&self) -> Result<String, GeneratorError>;
 
     /// Engine name, used for logging/identification.
     fn name(&self) -> &'static str;
+
+    /// Whether `code` has the shape this generator produces.
+    ///
+    /// Redirects reject codes with characters outside `shortener.alphabet`
+    /// before any lookup unless this returns `true`, so generators whose
+    /// codes leave the alphabet (separators, emoji) must override it. The
+    /// default accepts nothing beyond the alphabet.
+    fn validate(&self, _code: &str) -> bool {
+        false
+    }
+
+    /// Candidate for retry number `attempt` (0 for the first try) after the
+    /// previous candidates collided.
+    ///
+    /// Schemes with a small code space can widen later candidates here; the
+    /// default ignores `attempt`.
+    fn generate_retry(&self, attempt: u32) -> Result<String, GeneratorError> {
+        let _ = attempt;
+        self.generate()
+    }
 }
 
+pub mod capacity;
+pub mod check_digit;
 pub mod config;
 mod nanoid;
+pub mod pattern;
 mod sequence;
+#[cfg(feature = "word-lists")]
+pub mod words;
 
+pub use check_digit::CheckDigitEngine;
 pub use nanoid::NanoIdEngine;
+pub use pattern::{CodePattern, PatternEngine};
 pub use sequence::SequenceEngine;
+#[cfg(feature = "word-lists")]
+pub use words::WordListEngine;
 
 use crate::generator::config::{EngineKind, ShortenerConfig};
 
@@ -48,8 +81,31 @@ use crate::generator::config::{EngineKind, ShortenerConfig};
 pub fn build_generator(cfg: &ShortenerConfig) -> Arc<dyn ShortCodeGenerator> {
     cfg.validate().expect("invalid shortener config");
 
+    let engine = build_engine(cfg);
+    if cfg.check_digit {
+        return Arc::new(CheckDigitEngine::new(engine, cfg.alphabet_chars()));
+    }
+    engine
+}
+
+fn build_engine(cfg: &ShortenerConfig) -> Arc<dyn ShortCodeGenerator> {
+    if let Some(raw) = &cfg.code_pattern {
+        let pattern =
+            CodePattern::parse(raw, &cfg.alphabet_chars()).expect("invalid shortener.code_pattern");
+        return Arc::new(PatternEngine::new(pattern));
+    }
+
     match cfg.engine.kind {
-        EngineKind::Nanoid => Arc::new(NanoIdEngine::new(cfg.length, cfg.alphabet.clone())),
+        EngineKind::Nanoid => Arc::new(NanoIdEngine::new(cfg.length, cfg.engine_alphabet())),
+        #[cfg(feature = "word-lists")]
+        EngineKind::Words => Arc::new(WordListEngine::new(
+            cfg.engine
+                .words
+                .as_ref()
+                .expect("words config must exist when kind=Words"),
+        )),
+        #[cfg(not(feature = "word-lists"))]
+        EngineKind::Words => unreachable!("rejected by ShortenerConfig::validate"),
         EngineKind::Sequence => {
             let seq: &config::SequenceConfig = cfg
                 .engine
@@ -58,7 +114,7 @@ pub fn build_generator(cfg: &ShortenerConfig) -> Arc<dyn ShortCodeGenerator> {
                 .expect("sequence config must exist when kind=Sequence");
             Arc::new(SequenceEngine::new(
                 cfg.length,
-                cfg.alphabet.clone(),
+                cfg.engine_alphabet(),
                 seq.block_size.max(1),
                 seq.persist_interval.max(1),
                 seq.state_path.clone(),
@@ -26,4 +26,8 @@ impl ShortCodeGenerator for NanoIdEngine {
     fn name(&self) -> &'static str {
         "nanoid"
     }
+
+    fn validate(&self, code: &str) -> bool {
+        code.chars().count() == self.len && code.chars().all(|c| self.alphabet.contains(&c))
+    }
 }
@@ -0,0 +1,252 @@
+use rand::Rng;
+
+use super::{GeneratorError, ShortCodeGenerator};
+
+/// One position of a [`CodePattern`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatternSlot {
+    /// `L` - any letter of the configured alphabet
+    Letter,
+    /// `D` - any digit of the configured alphabet
+    Digit,
+    /// `A` - any character of the configured alphabet
+    Any,
+}
+
+/// A parsed `shortener.code_pattern`, e.g. `LLLDDD` for "3 letters + 3 digits".
+///
+/// Each pattern character selects the subset of the configured alphabet that
+/// may appear at that position:
+///
+/// - `L` - letters
+/// - `D` - digits
+/// - `A` - any alphabet character
+#[derive(Clone, Debug)]
+pub struct CodePattern {
+    slots: Vec<PatternSlot>,
+    letters: Vec<char>,
+    digits: Vec<char>,
+    any: Vec<char>,
+}
+
+impl CodePattern {
+    /// Parses `pattern` against `alphabet`.
+    ///
+    /// Fails when the pattern is empty, contains characters other than `L`, `D`
+    /// and `A`, or uses a class the alphabet has no characters for.
+    pub fn parse(pattern: &str, alphabet: &[char]) -> Result<Self, String> {
+        if pattern.is_empty() {
+            return Err("shortener.code_pattern must not be empty".into());
+        }
+
+        let slots = pattern
+            .chars()
+            .map(|c| match c {
+                'L' => Ok(PatternSlot::Letter),
+                'D' => Ok(PatternSlot::Digit),
+                'A' => Ok(PatternSlot::Any),
+                other => Err(format!(
+                    "shortener.code_pattern contains unsupported character '{}' (use L, D or A)",
+                    other
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let letters: Vec<char> = alphabet
+            .iter()
+            .copied()
+            .filter(char::is_ascii_alphabetic)
+            .collect();
+        let digits: Vec<char> = alphabet
+            .iter()
+            .copied()
+            .filter(char::is_ascii_digit)
+            .collect();
+
+        let pattern = Self {
+            slots,
+            letters,
+            digits,
+            any: alphabet.to_vec(),
+        };
+
+        for slot in &pattern.slots {
+            if pattern.choices(*slot).is_empty() {
+                return Err(format!(
+                    "shortener.code_pattern uses {:?} but the alphabet has no such characters",
+                    slot
+                ));
+            }
+        }
+
+        Ok(pattern)
+    }
+
+    /// Number of characters in every code produced by this pattern.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Always `false`: parsing rejects empty patterns.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// The positions of this pattern, in order.
+    pub fn slots(&self) -> &[PatternSlot] {
+        &self.slots
+    }
+
+    /// Characters allowed for `slot`.
+    pub fn choices(&self, slot: PatternSlot) -> &[char] {
+        match slot {
+            PatternSlot::Letter => &self.letters,
+            PatternSlot::Digit => &self.digits,
+            PatternSlot::Any => &self.any,
+        }
+    }
+
+    /// Total number of distinct codes this pattern can produce (saturating).
+    pub fn code_space(&self) -> u128 {
+        self.slots.iter().fold(1u128, |acc, slot| {
+            acc.saturating_mul(self.choices(*slot).len() as u128)
+        })
+    }
+
+    /// Every code matching a prefix of this pattern, shortest first.
+    ///
+    /// Prefixes of one slot, then two, up to the full pattern; within a
+    /// length codes follow the order of each slot's choices, last slot
+    /// fastest. Lazy, so callers can stop after the first few candidates.
+    pub fn candidates(&self) -> impl Iterator<Item = String> + '_ {
+        (1..=self.slots.len()).flat_map(move |len| {
+            let slots = &self.slots[..len];
+            let mut digits = Some(vec![0usize; len]);
+            std::iter::from_fn(move || {
+                let current = digits.as_mut()?;
+                let code = current
+                    .iter()
+                    .zip(slots)
+                    .map(|(i, slot)| self.choices(*slot)[*i])
+                    .collect();
+
+                // Advance the odometer; `None` once every position wrapped.
+                let mut pos = len;
+                loop {
+                    if pos == 0 {
+                        digits = None;
+                        break;
+                    }
+                    pos -= 1;
+                    current[pos] += 1;
+                    if current[pos] < self.choices(slots[pos]).len() {
+                        break;
+                    }
+                    current[pos] = 0;
+                }
+                Some(code)
+            })
+        })
+    }
+
+    /// Returns `true` when `code` could have been produced by this pattern.
+    pub fn matches(&self, code: &str) -> bool {
+        code.chars().count() == self.slots.len()
+            && code
+                .chars()
+                .zip(&self.slots)
+                .all(|(c, slot)| self.choices(*slot).contains(&c))
+    }
+}
+
+/// Random generator that honors a [`CodePattern`].
+pub struct PatternEngine {
+    pattern: CodePattern,
+}
+
+impl PatternEngine {
+    pub fn new(pattern: CodePattern) -> Self {
+        PatternEngine { pattern }
+    }
+}
+
+impl ShortCodeGenerator for PatternEngine {
+    fn generate(&self) -> Result<String, GeneratorError> {
+        let mut rng = rand::rng();
+        Ok(self
+            .pattern
+            .slots()
+            .iter()
+            .map(|slot| {
+                let choices = self.pattern.choices(*slot);
+                choices[rng.random_range(0..choices.len())]
+            })
+            .collect())
+    }
+
+    fn name(&self) -> &'static str {
+        "pattern"
+    }
+
+    fn validate(&self, code: &str) -> bool {
+        self.pattern.matches(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::DEFAULT_ALPHABET;
+
+    #[test]
+    fn generated_codes_match_the_pattern() {
+        let pattern = CodePattern::parse("LLLDDD", DEFAULT_ALPHABET).unwrap();
+        let engine = PatternEngine::new(pattern.clone());
+
+        for _ in 0..1_000 {
+            let code = engine.generate().unwrap();
+            assert_eq!(code.len(), 6);
+            assert!(code[..3].chars().all(|c| c.is_ascii_alphabetic()), "{code}");
+            assert!(code[3..].chars().all(|c| c.is_ascii_digit()), "{code}");
+            assert!(pattern.matches(&code));
+        }
+    }
+
+    #[test]
+    fn code_space_is_the_product_of_slot_choices() {
+        let pattern = CodePattern::parse("LLLDDD", DEFAULT_ALPHABET).unwrap();
+        assert_eq!(pattern.code_space(), 52u128.pow(3) * 10u128.pow(3));
+
+        let any = CodePattern::parse("AA", DEFAULT_ALPHABET).unwrap();
+        assert_eq!(any.code_space(), 62 * 62);
+    }
+
+    #[test]
+    fn parse_rejects_unknown_characters_and_empty_classes() {
+        assert!(CodePattern::parse("", DEFAULT_ALPHABET).is_err());
+        assert!(CodePattern::parse("LLX", DEFAULT_ALPHABET).is_err());
+
+        let digits_only: Vec<char> = "0123456789".chars().collect();
+        assert!(CodePattern::parse("DDD", &digits_only).is_ok());
+        assert!(CodePattern::parse("LDD", &digits_only).is_err());
+    }
+
+    #[test]
+    fn candidates_go_from_shortest_prefix_to_full_pattern() {
+        let pattern = CodePattern::parse("DL", DEFAULT_ALPHABET).unwrap();
+        let candidates: Vec<String> = pattern.candidates().collect();
+
+        assert_eq!(candidates.len(), 10 + 10 * 52);
+        assert_eq!(candidates[..3], ["0", "1", "2"]);
+        assert_eq!(candidates[10], "0A");
+        assert_eq!(candidates.last().unwrap(), "9z");
+    }
+
+    #[test]
+    fn matches_rejects_codes_outside_the_pattern() {
+        let pattern = CodePattern::parse("LLDD", DEFAULT_ALPHABET).unwrap();
+        assert!(pattern.matches("ab12"));
+        assert!(!pattern.matches("a112"));
+        assert!(!pattern.matches("ab123"));
+    }
+}
@@ -150,6 +150,10 @@ impl ShortCodeGenerator for SequenceEngine {
     fn name(&self) -> &'static str {
         "sequence"
     }
+
+    fn validate(&self, code: &str) -> bool {
+        code.chars().count() == self.len && code.chars().all(|c| self.alphabet.contains(&c))
+    }
 }
 
 /// State file stores `next_global` as little-endian u64.
@@ -0,0 +1,175 @@
+//! Memorable codes such as `brave-otter-42`.
+//!
+//! Codes combine an adjective and a noun from the embedded lists with an
+//! optional numeric suffix. The code space is small compared to random
+//! engines (`adjectives * nouns * 10^suffix_digits`), so collisions escalate:
+//! after two misses the suffix grows by [`EXTRA_DIGITS`], after five a second
+//! adjective is prepended. Only built with the `word-lists` feature.
+
+use rand::Rng;
+
+use super::config::WordsConfig;
+use super::{GeneratorError, ShortCodeGenerator};
+
+const ADJECTIVES: &str = include_str!("words/adjectives.txt");
+const NOUNS: &str = include_str!("words/nouns.txt");
+
+/// Digits appended to the suffix once the plain form keeps colliding.
+pub const EXTRA_DIGITS: u32 = 2;
+
+/// Attempts with the configured shape before the suffix grows.
+const PLAIN_ATTEMPTS: u32 = 2;
+
+/// Attempts before a second adjective is added.
+const WIDE_ATTEMPTS: u32 = 5;
+
+fn list(raw: &'static str, size: usize) -> Vec<&'static str> {
+    let words = raw.lines().map(str::trim).filter(|w| !w.is_empty());
+    match size {
+        0 => words.collect(),
+        n => words.take(n).collect(),
+    }
+}
+
+/// Generator for adjective-noun codes; see the [module docs](self).
+pub struct WordListEngine {
+    adjectives: Vec<&'static str>,
+    nouns: Vec<&'static str>,
+    separator: String,
+    suffix_digits: u32,
+}
+
+impl WordListEngine {
+    pub fn new(cfg: &WordsConfig) -> Self {
+        WordListEngine {
+            adjectives: list(ADJECTIVES, cfg.adjectives),
+            nouns: list(NOUNS, cfg.nouns),
+            separator: cfg.separator.clone(),
+            suffix_digits: cfg.suffix_digits,
+        }
+    }
+
+    /// Distinct codes of the configured shape, before any escalation.
+    pub fn code_space(&self) -> u128 {
+        (self.adjectives.len() as u128 * self.nouns.len() as u128)
+            .saturating_mul(10u128.saturating_pow(self.suffix_digits))
+    }
+
+    fn pick<'a>(words: &[&'a str], rng: &mut impl Rng) -> &'a str {
+        words[rng.random_range(0..words.len())]
+    }
+}
+
+impl ShortCodeGenerator for WordListEngine {
+    fn generate(&self) -> Result<String, GeneratorError> {
+        self.generate_retry(0)
+    }
+
+    fn name(&self) -> &'static str {
+        "words"
+    }
+
+    fn generate_retry(&self, attempt: u32) -> Result<String, GeneratorError> {
+        let mut rng = rand::rng();
+        let mut parts = Vec::with_capacity(4);
+        if attempt >= WIDE_ATTEMPTS {
+            parts.push(Self::pick(&self.adjectives, &mut rng).to_string());
+        }
+        parts.push(Self::pick(&self.adjectives, &mut rng).to_string());
+        parts.push(Self::pick(&self.nouns, &mut rng).to_string());
+
+        let digits = match attempt {
+            a if a < PLAIN_ATTEMPTS => self.suffix_digits,
+            _ => self.suffix_digits + EXTRA_DIGITS,
+        };
+        if digits > 0 {
+            let suffix: String = (0..digits)
+                .map(|_| char::from(b'0' + rng.random_range(0..10u8)))
+                .collect();
+            parts.push(suffix);
+        }
+        Ok(parts.join(&self.separator))
+    }
+
+    fn validate(&self, code: &str) -> bool {
+        let mut parts: Vec<&str> = code.split(self.separator.as_str()).collect();
+        if let Some(last) = parts.last()
+            && !last.is_empty()
+            && last.chars().all(|c| c.is_ascii_digit())
+        {
+            if last.len() > (self.suffix_digits + EXTRA_DIGITS) as usize {
+                return false;
+            }
+            parts.pop();
+        }
+        match parts.as_slice() {
+            [adjectives @ .., noun] if (1..=2).contains(&adjectives.len()) => {
+                self.nouns.contains(noun) && adjectives.iter().all(|a| self.adjectives.contains(a))
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shortcode::pattern_alias::RESERVED_SEGMENTS;
+
+    fn engine(suffix_digits: u32) -> WordListEngine {
+        WordListEngine::new(&WordsConfig {
+            separator: "-".to_string(),
+            adjectives: 0,
+            nouns: 0,
+            suffix_digits,
+        })
+    }
+
+    #[test]
+    fn embedded_lists_are_clean() {
+        for word in list(ADJECTIVES, 0).into_iter().chain(list(NOUNS, 0)) {
+            assert!(word.chars().all(|c| c.is_ascii_lowercase()), "{word}");
+            assert!(!RESERVED_SEGMENTS.contains(&word), "{word}");
+        }
+    }
+
+    #[test]
+    fn generated_codes_validate_at_every_escalation() {
+        let engine = engine(2);
+        for attempt in 0..8 {
+            let code = engine.generate_retry(attempt).unwrap();
+            assert!(engine.validate(&code), "{code}");
+            let parts = code.split('-').count();
+            assert_eq!(
+                parts,
+                if attempt >= WIDE_ATTEMPTS { 4 } else { 3 },
+                "{code}"
+            );
+            let suffix = code.rsplit('-').next().unwrap();
+            let digits = if attempt >= PLAIN_ATTEMPTS { 4 } else { 2 };
+            assert_eq!(suffix.len(), digits, "{code}");
+        }
+    }
+
+    #[test]
+    fn validate_rejects_foreign_codes() {
+        let engine = engine(0);
+        assert!(engine.validate("brave-otter"));
+        assert!(!engine.validate("otter-brave"));
+        assert!(!engine.validate("brave-brave-brave-otter"));
+        assert!(!engine.validate("brave-otter-123"));
+        assert!(!engine.validate("aZq042x"));
+        assert!(!engine.validate(""));
+    }
+
+    #[test]
+    fn list_sizes_bound_the_code_space() {
+        let cfg = WordsConfig {
+            separator: "-".to_string(),
+            adjectives: 10,
+            nouns: 20,
+            suffix_digits: 1,
+        };
+        assert_eq!(WordListEngine::new(&cfg).code_space(), 2_000);
+    }
+}
@@ -0,0 +1,217 @@
+//! TTL cache for destination host lookups.
+//!
+//! Outbound requests, today only
+//! [`follow_hop`](super::shortener_chain::follow_hop) for chained shortener
+//! links, need to know which addresses a host resolves to and whether any of
+//! them is in a blocked range. [`DnsCache`] resolves each host once and
+//! reuses the answer, including the blocked verdict, for
+//! `dns.cache_ttl_secs`.
+//!
+//! Hosts come from submitted URLs (`x1.bit.ly`, `x2.bit.ly`, ...), so the
+//! cache holds at most `dns.max_entries` of them: inserting into a full cache
+//! drops expired answers first, then the oldest.
+//!
+//! # TOCTOU
+//!
+//! A cached answer can be stale: a host that resolved to a public address at
+//! check time may point at a private one by the time a request is made
+//! (DNS rebinding). Every cached entry widens that window by up to the TTL,
+//! which is why the default is a few seconds. Callers that must be strict
+//! should connect to one of the returned [`Resolved::addrs`] directly rather
+//! than letting the HTTP client resolve the host again.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::io;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// The outcome of resolving one host.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resolved {
+    pub addrs: Vec<IpAddr>,
+    /// `true` when any address is loopback, private, link-local or otherwise
+    /// not publicly routable
+    pub blocked: bool,
+}
+
+/// Default for [`DnsCache::with_max_entries`].
+pub const DEFAULT_MAX_ENTRIES: usize = 1024;
+
+pub struct DnsCache {
+    ttl: Duration,
+    max_entries: usize,
+    entries: Mutex<HashMap<String, (Instant, Resolved)>>,
+}
+
+impl DnsCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Overrides how many hosts the cache holds (default
+    /// [`DEFAULT_MAX_ENTRIES`]); `0` disables caching.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Returns the cached answer for `host` if it is younger than the TTL.
+    pub fn cached(&self, host: &str) -> Option<Resolved> {
+        let key = host.to_ascii_lowercase();
+        let mut entries = self.entries.lock();
+        match entries.get(&key) {
+            Some((at, resolved)) if at.elapsed() < self.ttl => Some(resolved.clone()),
+            Some(_) => {
+                entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Resolves `host`, serving from the cache within the TTL.
+    ///
+    /// Failed lookups are not cached.
+    pub async fn resolve(&self, host: &str) -> io::Result<Resolved> {
+        if let Some(resolved) = self.cached(host) {
+            return Ok(resolved);
+        }
+
+        let addrs: Vec<IpAddr> = tokio::net::lookup_host((host, 0))
+            .await?
+            .map(|addr| addr.ip())
+            .collect();
+        let resolved = Resolved {
+            blocked: addrs.iter().any(is_blocked_ip),
+            addrs,
+        };
+
+        if !self.ttl.is_zero() && self.max_entries > 0 {
+            self.insert(host.to_ascii_lowercase(), resolved.clone());
+        }
+        Ok(resolved)
+    }
+
+    /// Stores an answer, making room in a full cache by dropping expired
+    /// entries and then the oldest ones.
+    fn insert(&self, key: String, resolved: Resolved) {
+        let mut entries = self.entries.lock();
+        if !entries.contains_key(&key) && entries.len() >= self.max_entries {
+            entries.retain(|_, (at, _)| at.elapsed() < self.ttl);
+            while entries.len() >= self.max_entries {
+                let Some(oldest) = entries
+                    .iter()
+                    .min_by_key(|(_, (at, _))| *at)
+                    .map(|(host, _)| host.clone())
+                else {
+                    break;
+                };
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(key, (Instant::now(), resolved));
+    }
+
+    /// Number of hosts currently cached, expired ones included.
+    pub fn len(&self) -> usize {
+        self.entries.lock().len()
+    }
+
+    /// Whether no host is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// `true` for addresses outbound requests must never reach.
+pub fn is_blocked_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                // 100.64.0.0/10 carrier-grade NAT
+                || (a == 100 && (64..128).contains(&b))
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_blocked_ip(&IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                // fc00::/7 unique local
+                || (first & 0xfe00) == 0xfc00
+                // fe80::/10 link local
+                || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocked_ranges() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(is_blocked_ip(&ip.parse().unwrap()), "{ip}");
+        }
+        for ip in ["93.184.216.34", "2606:4700::1111", "100.128.0.1"] {
+            assert!(!is_blocked_ip(&ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[tokio::test]
+    async fn answers_are_reused_within_the_ttl() {
+        let cache = DnsCache::new(Duration::from_secs(60));
+        assert!(cache.cached("127.0.0.1").is_none());
+
+        let resolved = cache.resolve("127.0.0.1").await.unwrap();
+        assert!(resolved.blocked);
+        assert_eq!(cache.cached("127.0.0.1"), Some(resolved));
+    }
+
+    #[tokio::test]
+    async fn full_cache_evicts_the_oldest_host() {
+        let cache = DnsCache::new(Duration::from_secs(60)).with_max_entries(2);
+        for host in ["127.0.0.1", "127.0.0.2", "127.0.0.3"] {
+            cache.resolve(host).await.unwrap();
+        }
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.cached("127.0.0.1").is_none());
+        assert!(cache.cached("127.0.0.2").is_some());
+        assert!(cache.cached("127.0.0.3").is_some());
+    }
+
+    #[tokio::test]
+    async fn zero_ttl_disables_caching() {
+        let cache = DnsCache::new(Duration::ZERO);
+        cache.resolve("127.0.0.1").await.unwrap();
+        assert!(cache.cached("127.0.0.1").is_none());
+    }
+}
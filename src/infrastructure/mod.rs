@@ -1,2 +1,5 @@
 pub mod db;
+pub mod dns;
 pub mod email;
+pub mod outbound;
+pub mod shortener_chain;
@@ -0,0 +1,75 @@
+//! HTTP client settings shared by every outbound request to a destination.
+//!
+//! [`client_builder`] applies `outbound` from the configuration: requests
+//! can be switched off entirely, and go through `outbound.proxy` when set or
+//! otherwise through the standard `HTTP_PROXY` / `HTTPS_PROXY` / `NO_PROXY`
+//! variables.
+//!
+//! Behind a proxy the connection goes to the proxy, which resolves the
+//! destination itself; pinning the address checked through
+//! [`DnsCache`](super::dns::DnsCache) has no effect there, so the proxy's own
+//! egress rules are the last line against rebinding.
+
+use crate::configuration::OutboundSettings;
+use reqwest::{ClientBuilder, Proxy};
+
+/// Why no outbound client could be built.
+#[derive(Debug, thiserror::Error)]
+pub enum OutboundError {
+    #[error("outbound requests are disabled")]
+    Disabled,
+    #[error("invalid outbound proxy: {0}")]
+    Proxy(#[source] reqwest::Error),
+}
+
+/// A client builder with the configured proxy, or [`OutboundError::Disabled`]
+/// when `outbound.enabled` is off. Callers add their own timeout and
+/// redirect policy.
+pub fn client_builder(settings: &OutboundSettings) -> Result<ClientBuilder, OutboundError> {
+    if !settings.enabled {
+        return Err(OutboundError::Disabled);
+    }
+    let builder = reqwest::Client::builder();
+    let Some(url) = settings.proxy.as_deref() else {
+        return Ok(builder);
+    };
+    let mut proxy = Proxy::all(url).map_err(OutboundError::Proxy)?;
+    if let Some(username) = settings.proxy_username.as_deref() {
+        proxy = proxy.basic_auth(username, settings.proxy_password.as_deref().unwrap_or(""));
+    }
+    Ok(builder.proxy(proxy))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_builder_honours_the_kill_switch_and_rejects_bad_proxies() {
+        let disabled = OutboundSettings {
+            enabled: false,
+            ..Default::default()
+        };
+        assert!(matches!(
+            client_builder(&disabled),
+            Err(OutboundError::Disabled)
+        ));
+
+        let bad_proxy = OutboundSettings {
+            proxy: Some("not a url".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            client_builder(&bad_proxy),
+            Err(OutboundError::Proxy(_))
+        ));
+
+        let proxied = OutboundSettings {
+            proxy: Some("http://proxy.corp:3128".to_string()),
+            proxy_username: Some("svc".to_string()),
+            proxy_password: Some("secret".to_string()),
+            ..Default::default()
+        };
+        assert!(client_builder(&proxied).unwrap().build().is_ok());
+    }
+}
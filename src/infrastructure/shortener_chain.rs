@@ -0,0 +1,120 @@
+//! Links whose destination is another URL shortener.
+//!
+//! Chaining shorteners (ours -> theirs -> ours -> phishing page) hides the
+//! real destination from users and from our own abuse checks.
+//! [`shortener_host`] spots such destinations; [`follow_hop`] resolves one
+//! hop behind the same SSRF rules and [`outbound`] settings as other outbound
+//! requests.
+
+use crate::configuration::{ChainedShortenerSettings, OutboundSettings};
+use crate::infrastructure::dns::DnsCache;
+use crate::infrastructure::outbound::{self, OutboundError};
+use reqwest::header::LOCATION;
+use reqwest::redirect::Policy;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// Why [`follow_hop`] could not resolve a destination.
+#[derive(Debug, thiserror::Error)]
+pub enum HopError {
+    #[error(transparent)]
+    Outbound(#[from] OutboundError),
+    #[error("destination has no host")]
+    NoHost,
+    #[error("destination resolves to a blocked address")]
+    Blocked,
+    #[error("destination lookup failed: {0}")]
+    Lookup(#[from] std::io::Error),
+    #[error("request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("destination answered {0} without a redirect")]
+    NoRedirect(reqwest::StatusCode),
+    #[error("redirect location is not a valid URL")]
+    InvalidLocation,
+}
+
+/// The host of `url` if it belongs to a known shortener: one of
+/// `settings.domains` or a subdomain of one, or the host of `base_url`.
+pub fn shortener_host(
+    url: &str,
+    settings: &ChainedShortenerSettings,
+    base_url: &str,
+) -> Option<String> {
+    let host = url::Url::parse(url).ok()?.host_str()?.to_ascii_lowercase();
+    let own = url::Url::parse(base_url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_ascii_lowercase));
+    let matches = |domain: &str| {
+        let domain = domain.trim().trim_end_matches('.').to_ascii_lowercase();
+        host == domain
+            || host
+                .strip_suffix(domain.as_str())
+                .is_some_and(|rest| rest.ends_with('.'))
+    };
+    (own.as_deref().is_some_and(matches) || settings.domains.iter().any(|d| matches(d)))
+        .then_some(host)
+}
+
+/// Requests `url` once without following redirects and returns the absolute
+/// `Location` it redirects to.
+///
+/// The host is resolved through `dns` first and refused if any address is
+/// blocked; the request then connects to that checked address rather than
+/// resolving the host again, unless it goes through a proxy.
+pub async fn follow_hop(
+    url: &str,
+    dns: &DnsCache,
+    outbound_settings: &OutboundSettings,
+    timeout: Duration,
+) -> Result<String, HopError> {
+    let builder = outbound::client_builder(outbound_settings)?;
+    let parsed = url::Url::parse(url).map_err(|_| HopError::InvalidLocation)?;
+    let host = parsed.host_str().ok_or(HopError::NoHost)?;
+    let port = parsed.port_or_known_default().ok_or(HopError::NoHost)?;
+
+    let resolved = dns.resolve(host).await?;
+    if resolved.blocked {
+        return Err(HopError::Blocked);
+    }
+    let addr = resolved.addrs.first().ok_or(HopError::NoHost)?;
+
+    let client = builder
+        .redirect(Policy::none())
+        .timeout(timeout)
+        .resolve(host, SocketAddr::new(*addr, port))
+        .build()?;
+    let response = client.get(parsed.clone()).send().await?;
+    if !response.status().is_redirection() {
+        return Err(HopError::NoRedirect(response.status()));
+    }
+    let location = response
+        .headers()
+        .get(LOCATION)
+        .and_then(|v| v.to_str().ok())
+        .ok_or(HopError::InvalidLocation)?;
+    parsed
+        .join(location)
+        .map(String::from)
+        .map_err(|_| HopError::InvalidLocation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortener_host_matches_domains_subdomains_and_our_own_host() {
+        let settings = ChainedShortenerSettings {
+            domains: vec!["bit.ly".to_string()],
+            ..Default::default()
+        };
+        let base = "https://sho.rt";
+        let host = |url: &str| shortener_host(url, &settings, base);
+
+        assert_eq!(host("https://bit.ly/abc").as_deref(), Some("bit.ly"));
+        assert_eq!(host("https://j.BIT.ly/abc").as_deref(), Some("j.bit.ly"));
+        assert_eq!(host("https://sho.rt/abc").as_deref(), Some("sho.rt"));
+        assert_eq!(host("https://notbit.ly/abc"), None);
+        assert_eq!(host("https://example.com/?u=https://bit.ly/x"), None);
+    }
+}
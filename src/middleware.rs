@@ -7,6 +7,7 @@
 //! ## Available Middleware
 //!
 //! - [`check_api_key`] - Validates API key authentication for protected endpoints
+//! - [`enforce_timeout`] - Answers requests that run past their timeout with 504
 //!
 //! ## Usage
 //!
@@ -21,21 +22,40 @@
 //!     .route("/api/shorten", post(shorten_handler))
 //!     .route_layer(from_fn_with_state(state, check_api_key));
 //! ```
+use crate::configuration::TimeoutSettings;
+use crate::errors::ApiError;
 use crate::response::ApiResponse;
 use crate::state::AppState;
 
 use axum::{
-    extract::{ConnectInfo, Request, State},
+    extract::{ConnectInfo, MatchedPath, Request, State},
     http::StatusCode,
     middleware::Next,
     response::{IntoResponse, Response},
 };
+use sha2::{Digest, Sha256};
 use std::{
     net::{IpAddr, SocketAddr},
     str::FromStr,
+    sync::Arc,
 };
 use uuid::Uuid;
 
+/// Stable, non-secret identifier of an API key: the first 8 bytes of its
+/// SHA-256, hex-encoded.
+///
+/// [`check_api_key`] attaches it to authenticated requests as an extension,
+/// and it is stored as a link's `owner_key_id`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApiKeyId(pub String);
+
+impl ApiKeyId {
+    pub fn of(key: &Uuid) -> Self {
+        let digest = Sha256::digest(key.as_bytes());
+        ApiKeyId(digest[..8].iter().map(|b| format!("{b:02x}")).collect())
+    }
+}
+
 /// Middleware function that validates API key authentication.
 ///
 /// This middleware checks for a valid API key in the `x-api-key` header of incoming requests.
@@ -49,6 +69,8 @@ use uuid::Uuid;
 /// 3. Compares the provided key with the configured API key
 /// 4. Allows the request to proceed if keys match, otherwise returns 401 Unauthorized
 ///
+/// Authenticated requests carry the key's [`ApiKeyId`] as an extension.
+///
 /// # Arguments
 ///
 /// * `State(state)` - Application state containing the configured API key
@@ -95,7 +117,7 @@ use uuid::Uuid;
 /// - The key comparison is done using constant-time comparison for security
 pub async fn check_api_key(
     State(state): State<AppState>,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> Response {
     let api_key: &Uuid = state.api_key.as_ref();
@@ -107,12 +129,45 @@ pub async fn check_api_key(
         .and_then(|s| Uuid::parse_str(s.trim()).ok());
 
     if provided_api_key.as_ref() == Some(api_key) {
+        request.extensions_mut().insert(ApiKeyId::of(api_key));
         next.run(request).await
     } else {
         ApiResponse::<()>::error("Unauthorized", StatusCode::UNAUTHORIZED).into_response()
     }
 }
 
+/// Middleware that bounds how long the rest of the stack may take.
+///
+/// The limit comes from [`TimeoutSettings::for_route`] for the matched route
+/// template. When it runs out the handler future is dropped, which also
+/// abandons any query it was waiting on, and the client gets a
+/// `504 Gateway Timeout` JSON error. A query that fails on its own with
+/// `DatabaseError::Timeout` is answered with the same status by the handler.
+pub async fn enforce_timeout(
+    State(timeouts): State<Arc<TimeoutSettings>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_owned());
+    let Some(limit) = timeouts.for_route(route.as_deref()) else {
+        return next.run(request).await;
+    };
+    match tokio::time::timeout(limit, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            tracing::warn!(
+                route = route.as_deref().unwrap_or("-"),
+                timeout_secs = limit.as_secs(),
+                "request timed out"
+            );
+            ApiError::GatewayTimeout("Request timed out".to_string()).into_response()
+        }
+    }
+}
+
 // src/middleware/client_meta.rs
 
 #[derive(Clone, Debug)]
@@ -155,3 +210,39 @@ pub async fn capture_client_meta(
         .insert(ClientMeta { ip, user_agent: ua });
     next.run(req).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, middleware::from_fn_with_state, routing::get};
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use tower::Service;
+
+    async fn call(router: &mut Router, path: &str) -> StatusCode {
+        let request = Request::builder().uri(path).body(Body::empty()).unwrap();
+        router.call(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn enforce_timeout_applies_route_overrides() {
+        let timeouts = TimeoutSettings {
+            request_secs: 0,
+            routes: HashMap::from([("/slow/{n}".to_string(), 1)]),
+        };
+        let slow = || async {
+            tokio::time::sleep(Duration::from_millis(1500)).await;
+            "done"
+        };
+        let mut router = Router::new()
+            .route("/slow/{n}", get(slow))
+            .route("/unbounded", get(slow))
+            .layer(from_fn_with_state(Arc::new(timeouts), enforce_timeout));
+
+        assert_eq!(
+            call(&mut router, "/slow/1").await,
+            StatusCode::GATEWAY_TIMEOUT
+        );
+        assert_eq!(call(&mut router, "/unbounded").await, StatusCode::OK);
+    }
+}
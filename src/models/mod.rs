@@ -1,19 +1,265 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::collections::BTreeMap;
 
-#[derive(Debug, FromRow)]
+/// A primary link with its bookkeeping columns.
+///
+/// Serialized field names are camelCase with the `camel-case-json` feature;
+/// the `FromRow` mapping follows the columns either way.
+#[derive(Clone, Debug, Serialize, FromRow)]
+#[cfg_attr(feature = "camel-case-json", serde(rename_all = "camelCase"))]
 pub struct UrlRecord {
+    pub id: i64,
     pub code: String,
     pub url: String,
+    pub created_at: Option<DateTime<Utc>>,
+    /// [`ApiKeyId`](crate::middleware::ApiKeyId) of the key that created the link
+    pub owner_key_id: Option<String>,
+    pub click_count: i64,
+    /// Expiry of the looked-up code (an alias's own expiry wins)
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Set when the link is blocked, e.g. after a takedown
+    pub blocked_reason: Option<String>,
+    /// Last change to anything stored for the link (destination settings,
+    /// expiry, blocking, owner, metadata, aliases, featuring); click counting
+    /// does not touch it. Only read by
+    /// [`UrlDatabase::changed_since`](crate::database::UrlDatabase::changed_since)
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// One line of an owner export; see
+/// [`UrlDatabase::export_owner`](crate::database::UrlDatabase::export_owner).
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(feature = "camel-case-json", serde(rename_all = "camelCase"))]
+pub struct ExportedLink {
+    #[serde(flatten)]
+    pub link: UrlRecord,
+    /// Aliases pointing at the link, sorted
+    pub aliases: Vec<String>,
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// A wildcard alias; see [`pattern_alias`](crate::shortcode::pattern_alias).
+#[derive(Clone, Debug, Serialize, FromRow)]
+pub struct PatternAlias {
+    pub id: i64,
+    pub pattern: String,
+    /// Destination template with `{1}`, `{2}`, ... for the captures
+    pub target: String,
 }
 
-#[derive(sqlx::FromRow)]
+#[derive(Serialize, sqlx::FromRow)]
+#[cfg_attr(feature = "camel-case-json", serde(rename_all = "camelCase"))]
 pub struct UpsertResult {
     pub id: i64,
     pub created: bool,
 }
 
-#[derive(sqlx::FromRow)]
+#[derive(Clone, Debug, Serialize, sqlx::FromRow)]
+#[cfg_attr(feature = "camel-case-json", serde(rename_all = "camelCase"))]
 pub struct Urls {
     pub id: i64,
     pub code: String,
 }
+
+/// How [`UrlDatabase::insert_url_with_mode`](crate::database::UrlDatabase::insert_url_with_mode)
+/// treats a destination that is already shortened.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InsertMode {
+    /// Return the existing link, as [`UrlDatabase::insert_url`](crate::database::UrlDatabase::insert_url) does
+    #[default]
+    Dedup,
+    /// Fail with `DatabaseError::Duplicate`
+    FailIfExists,
+    /// Add the new code as an alias of the existing link
+    AlwaysNew,
+}
+
+/// What became of the alias requested alongside a new link.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AliasOutcome {
+    /// The alias now points at the link
+    Created(String),
+    /// The alias already pointed at this link
+    Existing(String),
+    /// The alias belongs to another link; the link itself was still stored
+    Taken(String),
+}
+
+/// Result of a combined insert such as
+/// [`UrlDatabase::insert_url_with_alias`](crate::database::UrlDatabase::insert_url_with_alias).
+#[derive(Clone, Debug)]
+pub struct InsertOutcome {
+    /// The stored link; for an existing destination, its original code
+    pub record: Urls,
+    /// `false` when the destination was already shortened
+    pub created: bool,
+    /// `None` when no alias was requested
+    pub alias: Option<AliasOutcome>,
+}
+
+/// Per-link settings a newly created link is written with, in the same
+/// statement or transaction as the link itself.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LinkSettings {
+    /// API key that created the link
+    pub owner_key_id: Option<String>,
+    /// Redirects after which the link stops working
+    pub max_clicks: Option<u64>,
+    /// UTM opt-in; `None` leaves injection off
+    pub utm: Option<UtmParams>,
+    /// Opt the link out of the redirect interstitial
+    pub skip_interstitial: bool,
+}
+
+/// Headline numbers across the whole dataset, as served by `GET /api/stats`.
+#[derive(Clone, Debug, Serialize, FromRow)]
+#[cfg_attr(feature = "camel-case-json", serde(rename_all = "camelCase"))]
+pub struct AggregateStats {
+    /// Number of primary short links
+    pub total_links: i64,
+    /// Number of custom aliases pointing at those links
+    pub total_aliases: i64,
+    /// Sum of `click_count` over all links
+    pub total_clicks: i64,
+    /// Links that can no longer be served: past their `expires_at` or blocked
+    pub expired_links: i64,
+}
+
+/// How much the link data occupies, as returned by
+/// [`UrlDatabase::storage_stats`](crate::database::UrlDatabase::storage_stats).
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(feature = "camel-case-json", serde(rename_all = "camelCase"))]
+pub struct StorageStats {
+    /// Row count per link-data table
+    pub tables: BTreeMap<String, i64>,
+    /// Size of the whole database; for SQLite this includes free pages
+    /// until the file is vacuumed
+    pub database_bytes: i64,
+    /// Stored bloom snapshot data, inline and chunked
+    pub bloom_snapshot_bytes: i64,
+}
+
+/// Per-code reporting numbers, as returned by
+/// [`UrlDatabase::stats_for_codes`](crate::database::UrlDatabase::stats_for_codes).
+///
+/// Aliases report the numbers of the link they point at.
+#[derive(Clone, Debug, Serialize, FromRow)]
+#[cfg_attr(feature = "camel-case-json", serde(rename_all = "camelCase"))]
+pub struct CodeStats {
+    pub created_at: Option<DateTime<Utc>>,
+    pub click_count: i64,
+    /// Time of the most recent counted redirect; `None` if never clicked
+    pub last_accessed_at: Option<DateTime<Utc>>,
+}
+
+/// UTM attribution parameters merged into a destination URL on redirect.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct UtmParams {
+    pub source: Option<String>,
+    pub medium: Option<String>,
+    pub campaign: Option<String>,
+}
+
+impl UtmParams {
+    /// Fills every unset parameter from `defaults`.
+    pub fn or(self, defaults: &UtmParams) -> UtmParams {
+        UtmParams {
+            source: self.source.or_else(|| defaults.source.clone()),
+            medium: self.medium.or_else(|| defaults.medium.clone()),
+            campaign: self.campaign.or_else(|| defaults.campaign.clone()),
+        }
+    }
+
+    /// `(query key, value)` pairs for the parameters that are set.
+    pub fn pairs(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        [
+            ("utm_source", self.source.as_deref()),
+            ("utm_medium", self.medium.as_deref()),
+            ("utm_campaign", self.campaign.as_deref()),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|v| (key, v)))
+    }
+}
+
+/// Everything the redirect handler needs to know about a code.
+#[derive(Debug, FromRow)]
+pub struct RedirectTarget {
+    pub url: String,
+    /// Whether the link opted in to UTM injection
+    pub utm_enabled: bool,
+    pub utm_source: Option<String>,
+    pub utm_medium: Option<String>,
+    pub utm_campaign: Option<String>,
+    /// Expiry of the alias if it has one, else of the link
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Set when the link was taken down; such links never redirect
+    pub blocked_reason: Option<String>,
+    /// Redirect straight away even when the interstitial page is enabled
+    pub skip_interstitial: bool,
+    /// [`UrlRecord::updated_at`]: every change to how the link redirects is
+    /// among them, so a client cache it validates is never stale
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Clicks after which the link stops redirecting
+    pub max_clicks: Option<i64>,
+}
+
+impl RedirectTarget {
+    /// The link's own UTM parameters, or `None` when it has not opted in.
+    pub fn utm(&self) -> Option<UtmParams> {
+        self.utm_enabled.then(|| UtmParams {
+            source: self.utm_source.clone(),
+            medium: self.utm_medium.clone(),
+            campaign: self.utm_campaign.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_structs_follow_the_configured_field_naming() {
+        let record = UrlRecord {
+            id: 1,
+            code: "Abc1234".to_string(),
+            url: "https://example.com/".to_string(),
+            created_at: None,
+            owner_key_id: None,
+            click_count: 3,
+            expires_at: None,
+            blocked_reason: None,
+            updated_at: None,
+        };
+        let json = serde_json::to_value(&record).unwrap();
+        let (click_count, owner_key_id) = if cfg!(feature = "camel-case-json") {
+            ("clickCount", "ownerKeyId")
+        } else {
+            ("click_count", "owner_key_id")
+        };
+        assert_eq!(json[click_count], 3);
+        assert!(json.get(owner_key_id).is_some());
+
+        // The flattened record and the export's own fields share one style
+        let exported = serde_json::to_value(ExportedLink {
+            link: record,
+            aliases: vec![],
+            metadata: BTreeMap::new(),
+        })
+        .unwrap();
+        assert_eq!(exported[click_count], 3);
+        assert!(exported.get("aliases").is_some());
+
+        let upsert = serde_json::to_value(UpsertResult {
+            id: 1,
+            created: true,
+        })
+        .unwrap();
+        assert_eq!(upsert["created"], true);
+    }
+}
@@ -0,0 +1,44 @@
+//! # Link Administration Handler
+//!
+//! Lets support inspect a link exactly as stored, including the expiry and
+//! block status that keep it from resolving.
+
+use crate::database::DatabaseError;
+use crate::errors::ApiError;
+use crate::models::UrlRecord;
+use crate::response::ApiResponse;
+use crate::state::AppState;
+use axum::extract::{Path, State};
+use axum_macros::debug_handler;
+
+/// Returns the stored record behind a code, even if it is expired or blocked.
+///
+/// # Endpoint
+///
+/// `GET /api/admin/links/{code}` (protected - requires API key)
+///
+/// Aliases return the record of the link they point at, with the alias's own
+/// expiry if it has one.
+///
+/// # Status Codes
+///
+/// - `200 OK` - The record, with `expires_at` and `blocked_reason`
+/// - `401 Unauthorized` - Missing or invalid API key
+/// - `404 Not Found` - No link or alias with this code
+/// - `500 Internal Server Error` - Database error occurred
+#[debug_handler]
+#[tracing::instrument(name = "get_link_admin", skip(state))]
+pub async fn get_link_admin(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+) -> Result<ApiResponse<UrlRecord>, ApiError> {
+    let code = state.config.shortener.lookup_code(&code);
+    match state.database.get_url_admin(&code).await {
+        Ok(record) => Ok(ApiResponse::success(record)),
+        Err(DatabaseError::NotFound) => Err(ApiError::NotFound("URL not found".to_string())),
+        Err(e) => {
+            tracing::error!("Database error: {}", e);
+            Err(ApiError::Internal(e.to_string()))
+        }
+    }
+}
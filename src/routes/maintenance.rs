@@ -0,0 +1,80 @@
+//! # Maintenance Mode Handler
+//!
+//! Toggles the global read-only mode at runtime. While it is on, redirects are
+//! still served but every write is answered with `503 Service Unavailable`.
+//! Also hosts the post-deploy self-test.
+
+use crate::database::SelfTestReport;
+use crate::errors::ApiError;
+use crate::response::ApiResponse;
+use crate::state::AppState;
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum_macros::debug_handler;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ReadOnlyMode {
+    pub enabled: bool,
+}
+
+/// Switches read-only mode on or off.
+///
+/// # Endpoint
+///
+/// `PUT /api/maintenance/read-only` (protected - requires API key)
+///
+/// # Request Body
+///
+/// ```json
+/// { "enabled": true }
+/// ```
+///
+/// # Status Codes
+///
+/// - `200 OK` - Mode changed; the response echoes the new state
+/// - `401 Unauthorized` - Missing or invalid API key
+#[debug_handler]
+#[tracing::instrument(name = "set_read_only", skip(state))]
+pub async fn put_read_only(
+    State(state): State<AppState>,
+    Json(mode): Json<ReadOnlyMode>,
+) -> Result<ApiResponse<ReadOnlyMode>, ApiError> {
+    state.read_only.set(mode.enabled);
+    tracing::warn!(enabled = mode.enabled, "read-only maintenance mode changed");
+    Ok(ApiResponse::success(ReadOnlyMode {
+        enabled: state.read_only.is_enabled(),
+    }))
+}
+
+/// Runs [`UrlDatabase::self_test`](crate::database::UrlDatabase::self_test):
+/// inserts, reads back, resolves and deletes a throwaway link.
+///
+/// # Endpoint
+///
+/// `POST /api/admin/self-test` (protected - requires API key)
+///
+/// # Status Codes
+///
+/// - `200 OK` - Every step passed; the report lists them with timings
+/// - `401 Unauthorized` - Missing or invalid API key
+/// - `503 Service Unavailable` - A step failed; the same report says which
+#[debug_handler]
+#[tracing::instrument(name = "self_test", skip(state))]
+pub async fn post_self_test(
+    State(state): State<AppState>,
+) -> Result<ApiResponse<SelfTestReport>, ApiError> {
+    let report = state.database.self_test().await.map_err(|e| {
+        tracing::error!("Database error: {}", e);
+        ApiError::Internal(e.to_string())
+    })?;
+    if report.is_ok() {
+        return Ok(ApiResponse::success(report));
+    }
+    tracing::error!(?report, "self-test failed");
+    let mut response = ApiResponse::success_with_status(StatusCode::SERVICE_UNAVAILABLE, report);
+    response.success = false;
+    response.message = Some("self-test failed".into());
+    Ok(response)
+}
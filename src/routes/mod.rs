@@ -10,9 +10,16 @@
 //! - `GET /api/health_check` - Health check endpoint
 //! - `GET /api/redirect/{id}` - Redirect to original URL
 //! - `POST /api/public/shorten` - Shorten URL (public endpoint)
+//! - `GET /api/stats` - Aggregate statistics (optionally requires API key)
+//! - `GET /api/preview/{token}` - Show the destination behind a signed preview token
+//! - `GET /{path}` - Any other path is matched against the wildcard aliases
 //!
 //! ### Protected API (Requires API Key)
 //! - `POST /api/shorten` - Shorten URL (protected endpoint)
+//! - `POST /api/preview-tokens/{code}` - Issue a signed preview token
+//! - `PUT /api/maintenance/read-only` - Toggle read-only maintenance mode
+//! - `POST /api/pattern-aliases` - Register a wildcard alias such as `go/*`
+//! - `GET /api/admin/storage` - Row counts and on-disk size for capacity planning
 //!
 //! ### Admin Panel
 //! - `GET /admin` - Web interface for management
@@ -49,8 +56,13 @@ pub mod admin;
 pub mod docs;
 pub mod health_check;
 pub mod index;
+pub mod links;
+pub mod maintenance;
+pub mod pattern_alias;
+pub mod preview;
 pub mod redirect;
 pub mod shorten;
+pub mod stats;
 
 // re-exports
 pub use admin::*;
@@ -58,5 +70,10 @@ pub use docs::*;
 // Re-exports for convenience
 pub use health_check::*;
 pub use index::*;
+pub use links::*;
+pub use maintenance::*;
+pub use pattern_alias::*;
+pub use preview::*;
 pub use redirect::*;
 pub use shorten::*;
+pub use stats::*;
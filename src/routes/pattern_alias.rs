@@ -0,0 +1,87 @@
+//! # Pattern Alias Handler
+//!
+//! Registers wildcard aliases such as `go/*`; see
+//! [`pattern_alias`](crate::shortcode::pattern_alias) for the syntax.
+
+use crate::core::destination::{blocked_scheme, normalize_url};
+use crate::database::DatabaseError;
+use crate::errors::ApiError;
+use crate::models::PatternAlias;
+use crate::response::ApiResponse;
+use crate::shortcode::pattern_alias;
+use crate::state::AppState;
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum_macros::debug_handler;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct NewPatternAlias {
+    pub pattern: String,
+    pub target: String,
+}
+
+/// Registers a wildcard alias.
+///
+/// # Endpoint
+///
+/// `POST /api/pattern-aliases` (protected - requires API key)
+///
+/// # Request Body
+///
+/// ```json
+/// { "pattern": "go/*", "target": "https://docs.example.com/{1}" }
+/// ```
+///
+/// # Status Codes
+///
+/// - `201 Created` - Pattern registered
+/// - `409 Conflict` - The pattern is already registered
+/// - `422 Unprocessable Entity` - Invalid pattern or target, or
+///   `pattern_aliases.max_count` reached
+/// - `500 Internal Server Error` - Database error occurred
+#[debug_handler]
+#[tracing::instrument(name = "create_pattern_alias", skip(state))]
+pub async fn post_pattern_alias(
+    State(state): State<AppState>,
+    Json(new): Json<NewPatternAlias>,
+) -> Result<ApiResponse<PatternAlias>, ApiError> {
+    pattern_alias::validate(&new.pattern, &new.target).map_err(ApiError::Unprocessable)?;
+    // Every capture is at least one character, so probe the template with one
+    let probe = pattern_alias::probe(&new.target);
+    if blocked_scheme(&probe, &state.config.url_schemes).is_some() {
+        return Err(ApiError::Unprocessable(
+            "Target uses a blocked scheme".to_string(),
+        ));
+    }
+    normalize_url(&probe)
+        .map_err(|_| ApiError::Unprocessable("Target is not a valid URL template".to_string()))?;
+
+    let existing = state
+        .database
+        .list_pattern_aliases()
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+    if existing.len() >= state.config.pattern_aliases.max_count {
+        return Err(ApiError::Unprocessable(format!(
+            "At most {} pattern aliases can be registered",
+            state.config.pattern_aliases.max_count
+        )));
+    }
+
+    match state
+        .database
+        .insert_pattern_alias(&new.pattern, &new.target)
+        .await
+    {
+        Ok(alias) => Ok(ApiResponse::success_with_status(StatusCode::CREATED, alias)),
+        Err(DatabaseError::Duplicate) => Err(ApiError::Conflict(
+            "Pattern is already registered".to_string(),
+        )),
+        Err(DatabaseError::ReadOnly) => Err(ApiError::ServiceUnavailable(
+            "Pattern registration is paused for maintenance, please retry later".to_string(),
+        )),
+        Err(e) => Err(ApiError::Internal(e.to_string())),
+    }
+}
@@ -0,0 +1,121 @@
+//! # Link Preview Handlers
+//!
+//! Previews show a link's destination without redirecting. They are
+//! addressed by a signed, expiring token (see
+//! [`crate::core::security::preview`]) instead of the raw code, so a shared
+//! preview URL cannot be used to enumerate other links.
+
+use crate::core::security::preview::{make_preview_token, verify_preview_token};
+use crate::database::{DatabaseError, check_servable};
+use crate::errors::ApiError;
+use crate::response::ApiResponse;
+use crate::state::AppState;
+use axum::extract::{Path, State};
+use axum_macros::debug_handler;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct PreviewTokenResponse {
+    /// The signed token
+    pub token: String,
+    /// Shareable `GET /api/preview/{token}` URL
+    pub preview_url: String,
+    /// When the token stops working
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreviewResponse {
+    pub code: String,
+    pub url: String,
+}
+
+/// Issues a preview token for an existing code.
+///
+/// # Endpoint
+///
+/// `POST /api/preview-tokens/{code}` (protected - requires API key)
+///
+/// Tokens are valid for `preview.token_ttl_secs` seconds.
+///
+/// # Status Codes
+///
+/// - `200 OK` - Token issued
+/// - `404 Not Found` - Unknown code
+/// - `410 Gone` - The link expired
+/// - `451 Unavailable For Legal Reasons` - The link is blocked
+/// - `500 Internal Server Error` - Database error occurred
+#[debug_handler]
+#[tracing::instrument(name = "create_preview_token", skip(state))]
+pub async fn post_preview_token(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+) -> Result<ApiResponse<PreviewTokenResponse>, ApiError> {
+    let code = state.config.shortener.lookup_code(&code);
+    lookup(&state, &code).await?;
+
+    let expires_at = Utc::now() + Duration::seconds(state.config.preview.token_ttl_secs as i64);
+    let token = make_preview_token(&code, expires_at, state.api_key.as_bytes());
+    let preview_url = format!(
+        "{}/api/preview/{}",
+        state.config.application.base_url.trim_end_matches('/'),
+        token
+    );
+
+    Ok(ApiResponse::success(PreviewTokenResponse {
+        token,
+        preview_url,
+        expires_at,
+    }))
+}
+
+/// Shows the destination behind a preview token.
+///
+/// # Endpoint
+///
+/// `GET /api/preview/{token}`
+///
+/// # Status Codes
+///
+/// - `200 OK` - Token valid, destination returned
+/// - `404 Not Found` - Token malformed, forged or expired, or the link is gone
+/// - `410 Gone` - The link expired since the token was issued
+/// - `451 Unavailable For Legal Reasons` - The link was blocked
+/// - `500 Internal Server Error` - Database error occurred
+#[debug_handler]
+#[tracing::instrument(name = "preview", skip(state, token))]
+pub async fn get_preview(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<ApiResponse<PreviewResponse>, ApiError> {
+    // Every token failure looks the same to the caller
+    let code = verify_preview_token(&token, state.api_key.as_bytes()).map_err(|e| {
+        tracing::info!("rejecting preview: {}", e);
+        ApiError::NotFound("Preview not found".to_string())
+    })?;
+
+    let url = lookup(&state, &code).await?;
+    Ok(ApiResponse::success(PreviewResponse { code, url }))
+}
+
+/// The destination of `code`, refused the same way a redirect would be.
+async fn lookup(state: &AppState, code: &str) -> Result<String, ApiError> {
+    let servable = state
+        .database
+        .get_redirect_target(code)
+        .await
+        .and_then(|target| {
+            check_servable(target.expires_at, target.blocked_reason).map(|()| target.url)
+        });
+    match servable {
+        Ok(url) => Ok(url),
+        Err(DatabaseError::NotFound) => Err(ApiError::NotFound("URL not found".to_string())),
+        Err(DatabaseError::Blocked(reason)) => Err(ApiError::UnavailableForLegalReasons(reason)),
+        Err(DatabaseError::Expired) => Err(ApiError::Gone("URL has expired".to_string())),
+        Err(e) => {
+            tracing::error!("Database error: {}", e);
+            Err(ApiError::Internal(e.to_string()))
+        }
+    }
+}
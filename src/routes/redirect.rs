@@ -3,14 +3,29 @@
 //! This module provides the URL redirect handler for the URL shortener service.
 //! It handles requests to shortened URLs and redirects users to the original URLs.
 
+use crate::configuration::RedirectSettings;
+use crate::core::destination::{blocked_scheme, strip_tracking_params};
 use crate::database::{DatabaseError, MAX_ALIAS_LENGTH};
 use crate::errors::ApiError;
+use crate::generator::check_digit;
+use crate::models::UtmParams;
+use crate::shortcode::pattern_alias;
 use crate::state::AppState;
+use crate::templates::get_templates;
 use axum::{
     extract::{Path, State},
-    response::{IntoResponse, Redirect},
+    http::{
+        HeaderMap, HeaderValue, Method, StatusCode, Uri,
+        header::{ACCEPT, CACHE_CONTROL, LINK, REFERER, USER_AGENT},
+    },
+    response::{Html, IntoResponse, Redirect, Response},
 };
+use axum_extra::TypedHeader;
+use axum_extra::headers::{IfModifiedSince, LastModified};
 use axum_macros::debug_handler;
+use chrono::SubsecRound;
+use std::time::SystemTime;
+use tera::Context;
 
 /// URL redirect handler that redirects users to the original URL.
 ///
@@ -18,6 +33,17 @@ use axum_macros::debug_handler;
 /// the original URLs stored in the database. It uses HTTP 308 Permanent Redirect
 /// to ensure proper SEO handling and browser caching.
 ///
+/// Browsers get the `interstitial` page instead when it is enabled and the
+/// link has not opted out.
+///
+/// With `redirect.conditional_requests` the response carries `Last-Modified`
+/// and an `If-Modified-Since` at or after the link's last change gets
+/// `304 Not Modified`, which is not counted as a click.
+///
+/// `Cache-Control` follows `redirect_cache` in the configuration; see
+/// [`RedirectCacheSettings::cache_control`](crate::configuration::RedirectCacheSettings::cache_control).
+/// Links with a click quota always get `no-store`.
+///
 /// # Endpoint
 ///
 /// `GET /api/redirect/{id}`
@@ -36,15 +62,22 @@ use axum_macros::debug_handler;
 ///
 /// - **HTTP 308 Permanent Redirect** - Indicates that the resource has permanently
 ///   moved to the new location
+/// - **HTTP 307 Temporary Redirect** - For links with an expiry or a click
+///   quota, which clients must not remember past their end
 /// - **SEO Friendly** - Search engines understand that the short URL is an alias
 ///   for the original URL
 /// - **Browser Caching** - Browsers may cache the redirect for performance
 ///
 /// # Status Codes
 ///
+/// - `307 Temporary Redirect` - URL found, but it expires or has a click quota
 /// - `308 Permanent Redirect` - URL found and redirect successful
+/// - `403 Forbidden` - The stored destination has a blocked scheme
 /// - `404 Not Found` - Short URL not found in database
+/// - `410 Gone` - The link expired or used up its `max_clicks`
 /// - `500 Internal Server Error` - Database error occurred
+/// - `503 Service Unavailable` - A limited-use link during read-only mode
+/// - `504 Gateway Timeout` - The database or the request ran out of time
 ///
 /// # Tracing
 ///
@@ -66,6 +99,15 @@ use axum_macros::debug_handler;
 ///
 /// This handler handles the following error cases:
 /// - **URL Not Found** - Returns 404 with appropriate error message
+/// - **Malformed Code** - Returns 400 for codes failing the
+///   `shortener.check_digit` check, without a database lookup unless the
+///   Bloom filter may hold an alias of that name
+/// - **URL Blocked** - Returns 451 with the takedown reason and, if
+///   `legal.notice_url` is set, a `Link: rel="blocked-by"` header
+/// - **Blocked Scheme** - Returns 403 with `Cache-Control: no-store` when the
+///   stored destination uses a scheme from `url_schemes`, e.g. a row written
+///   around the API
+/// - **URL Expired** - Returns 410 with `Cache-Control: no-store`
 /// - **Database Errors** - Returns 500 with internal error message
 /// - **Invalid ID Format** - Handled by Axum's path extraction
 ///
@@ -75,11 +117,16 @@ use axum_macros::debug_handler;
 /// - Redirects are processed asynchronously
 /// - Error responses are minimal to reduce bandwidth
 #[debug_handler]
-#[tracing::instrument(name = "redirect" skip(state))]
+#[tracing::instrument(name = "redirect" skip(state, headers, if_modified_since))]
 pub async fn get_redirect(
     State(state): State<AppState>,
     Path(id): Path<String>,
-) -> Result<impl IntoResponse, ApiError> {
+    if_modified_since: Option<TypedHeader<IfModifiedSince>>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    // Opted-in single-case deployments look codes up regardless of case
+    let id = state.config.shortener.lookup_code(&id);
+
     // Validate against configured length and alphabet before DB lookup
     // check length (use char count to be safe)
     if id.chars().count() > MAX_ALIAS_LENGTH {
@@ -87,30 +134,358 @@ pub async fn get_redirect(
         return Err(ApiError::NotFound("URL not found".to_string()));
     }
 
-    // Use precomputed allowed_chars from AppState
-    if id.chars().any(|c| !state.allowed_chars.contains(&c)) {
+    // Use precomputed allowed_chars from AppState; custom generators may vouch
+    // for codes outside the alphabet
+    if id.chars().any(|c| !state.allowed_chars.contains(&c)) && !state.code_generator.validate(&id)
+    {
         tracing::info!("rejecting redirect: id contains invalid characters");
         return Err(ApiError::NotFound("URL not found".to_string()));
     }
 
-    if !state.blooms.s2l.may_contain(&id) {
+    // Decided before any lookup: aliases carry no check character, so a
+    // mismatch is a typo unless the filter may hold an alias of that name
+    let ruled_out = !state.blooms.may_contain(&id);
+    let mistyped = fails_check_digit(&state, &id);
+    if ruled_out && mistyped {
+        return Err(mistyped_code());
+    }
+    if ruled_out && state.config.bloom.trust_negatives {
         tracing::info!("rejecting redirect: id is not in the short to long filter");
         return Err(ApiError::NotFound("URL not found".to_string()));
     }
 
     // Proceed with DB lookup
-    match state.database.get_url(&id).await {
-        Ok(url) => {
+    match state.database.get_redirect_target(&id).await {
+        Ok(target) => {
+            if ruled_out {
+                state
+                    .blooms
+                    .record_false_negative(&state.database, &state.config.bloom, &id);
+            }
+            // A cached redirect would bypass the quota and go uncounted
+            let cache_control = if target.max_clicks.is_some() {
+                Some("no-store".to_string())
+            } else {
+                state
+                    .config
+                    .redirect_cache
+                    .cache_control(target.expires_at, chrono::Utc::now())
+            };
+            if let Some(reason) = target.blocked_reason {
+                tracing::info!("rejecting redirect: link is blocked");
+                return Ok(blocked_response(&state, reason));
+            }
+            if let Some(scheme) = blocked_scheme(&target.url, &state.config.url_schemes) {
+                tracing::warn!(scheme = %scheme, "rejecting redirect: destination scheme is blocked");
+                return Ok((
+                    [(CACHE_CONTROL, "no-store")],
+                    ApiError::Forbidden(format!("Blocked scheme: {scheme}")),
+                )
+                    .into_response());
+            }
+            if target.expires_at.is_some_and(|at| at <= chrono::Utc::now()) {
+                tracing::info!("rejecting redirect: link has expired");
+                return Ok((
+                    [(CACHE_CONTROL, "no-store")],
+                    ApiError::Gone("URL has expired".to_string()),
+                )
+                    .into_response());
+            }
+            let last_modified = target
+                .updated_at
+                .filter(|_| state.config.redirect.conditional_requests)
+                // HTTP dates have whole seconds
+                .map(|at| SystemTime::from(at.trunc_subsecs(0)));
+            if let (Some(at), Some(TypedHeader(since))) = (last_modified, &if_modified_since)
+                && !since.is_modified(at)
+            {
+                tracing::info!("shortened URL unchanged since the client's copy");
+                let response = (
+                    StatusCode::NOT_MODIFIED,
+                    TypedHeader(LastModified::from(at)),
+                );
+                return Ok(match cache_control {
+                    Some(value) => ([(CACHE_CONTROL, value)], response).into_response(),
+                    None => response.into_response(),
+                });
+            }
+            // Limited-use links count the click before redirecting, in the
+            // same statement that checks the quota; with click events every
+            // click is recorded that way, together with its event row
+            let track = state.config.redirect.click_events;
+            if track || target.max_clicks.is_some() {
+                let counted = if track {
+                    let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+                    state
+                        .database
+                        .resolve_and_track(&id, header(REFERER), header(USER_AGENT))
+                        .await
+                } else {
+                    state.database.get_url_and_count(&id).await
+                };
+                match counted {
+                    Ok(_) => {}
+                    // Changed since the lookup above
+                    Err(DatabaseError::Blocked(reason)) => {
+                        return Ok(blocked_response(&state, reason));
+                    }
+                    Err(DatabaseError::Expired) => {
+                        return Ok((
+                            [(CACHE_CONTROL, "no-store")],
+                            ApiError::Gone("URL has expired".to_string()),
+                        )
+                            .into_response());
+                    }
+                    Err(DatabaseError::QuotaExceeded) => {
+                        tracing::info!("rejecting redirect: click quota used up");
+                        return Ok((
+                            [(CACHE_CONTROL, "no-store")],
+                            ApiError::Gone("URL has reached its click limit".to_string()),
+                        )
+                            .into_response());
+                    }
+                    // Clicks are deliberately not counted in read-only mode
+                    Err(DatabaseError::ReadOnly) if target.max_clicks.is_none() => {}
+                    Err(DatabaseError::ReadOnly) => {
+                        return Err(ApiError::ServiceUnavailable(
+                            "Limited-use links are unavailable during maintenance".to_string(),
+                        ));
+                    }
+                    Err(DatabaseError::NotFound) => {
+                        return Err(ApiError::NotFound("URL not found".to_string()));
+                    }
+                    Err(DatabaseError::Timeout) => {
+                        return Err(ApiError::GatewayTimeout(
+                            "Database did not respond in time".to_string(),
+                        ));
+                    }
+                    Err(e) => {
+                        tracing::error!("Database error: {}", e);
+                        return Err(ApiError::Internal(e.to_string()));
+                    }
+                }
+            }
             tracing::info!("shortened URL retrieved, redirecting...");
-            Ok(Redirect::permanent(&url))
+            let destination = strip_tracking_params(&target.url, &state.config.privacy);
+            let url = match target.utm() {
+                Some(utm) => apply_utm(
+                    &destination,
+                    &utm.or(&state.config.utm.params()),
+                    state.config.utm.overwrite,
+                ),
+                None => destination,
+            };
+            // A failed counter update must never break the redirect itself;
+            // in read-only mode clicks are deliberately not counted
+            if !track && target.max_clicks.is_none() {
+                match state.database.increment_click_count(&id).await {
+                    Ok(()) | Err(DatabaseError::ReadOnly) => {}
+                    Err(e) => tracing::warn!("failed to record click: {}", e),
+                }
+            }
+            let mut response = if state.config.interstitial.enabled
+                && !target.skip_interstitial
+                && accepts_html(&headers)
+            {
+                render_interstitial(&state, &url)?
+            } else if target.expires_at.is_some() || target.max_clicks.is_some() {
+                // Clients may keep a permanent redirect forever, which a link
+                // that stops working must not get
+                Redirect::temporary(&url).into_response()
+            } else {
+                Redirect::permanent(&url).into_response()
+            };
+            if let Some(link) = connection_hints(&state.config.redirect, &url) {
+                response.headers_mut().insert(LINK, link);
+            }
+            if let Some(at) = last_modified {
+                response = (TypedHeader(LastModified::from(at)), response).into_response();
+            }
+            Ok(match cache_control {
+                Some(value) => ([(CACHE_CONTROL, value)], response).into_response(),
+                None => response,
+            })
         }
         Err(DatabaseError::NotFound) => {
+            if mistyped {
+                return Err(mistyped_code());
+            }
             tracing::error!("shortened URL not found in the database...");
             Err(ApiError::NotFound("URL not found".to_string()))
         }
+        Err(DatabaseError::Timeout) => Err(ApiError::GatewayTimeout(
+            "Database did not respond in time".to_string(),
+        )),
         Err(e) => {
             tracing::error!("Database error: {}", e);
             Err(ApiError::Internal(e.to_string()))
         }
     }
 }
+
+/// Whether `shortener.check_digit` is on and `id` fails its check character.
+fn fails_check_digit(state: &AppState, id: &str) -> bool {
+    state.config.shortener.check_digit
+        && !check_digit::verify(id, &state.config.shortener.alphabet_chars())
+}
+
+/// `400` for a code that failed its check character and matched no alias.
+fn mistyped_code() -> ApiError {
+    tracing::info!("rejecting redirect: check character mismatch");
+    ApiError::BadRequest("Malformed code: check character mismatch, was it mistyped?".to_string())
+}
+
+/// `451` for a blocked link, with a `Link: rel="blocked-by"` header when
+/// `legal.notice_url` is set.
+fn blocked_response(state: &AppState, reason: String) -> Response {
+    let error = ApiError::UnavailableForLegalReasons(reason).into_response();
+    let mut response = ([(CACHE_CONTROL, "no-store")], error).into_response();
+    if let Some(link) = state
+        .config
+        .legal
+        .notice_url
+        .as_deref()
+        .and_then(|url| HeaderValue::from_str(&format!("<{url}>; rel=\"blocked-by\"")).ok())
+    {
+        response.headers_mut().insert(LINK, link);
+    }
+    response
+}
+
+/// Fallback for paths no route matched: redirects through the most specific
+/// wildcard alias, if any.
+///
+/// Exact codes never contain `/` and patterns always do, so this only runs
+/// once the exact routes have missed. A configured `redirect.route_prefix`
+/// is stripped before matching.
+///
+/// # Status Codes
+///
+/// - `308 Permanent Redirect` - A pattern matched
+/// - `403 Forbidden` - The expanded destination has a blocked scheme, or a
+///   capture changed its scheme or host
+/// - `404 Not Found` - No pattern matched, or the method is not `GET`
+/// - `500 Internal Server Error` - Database error occurred
+#[tracing::instrument(name = "pattern_redirect" skip(state))]
+pub async fn get_pattern_redirect(
+    State(state): State<AppState>,
+    method: Method,
+    uri: Uri,
+) -> Result<Response, ApiError> {
+    let not_found = || ApiError::NotFound("URL not found".to_string());
+    if method != Method::GET {
+        return Err(not_found());
+    }
+
+    let patterns = state
+        .database
+        .list_pattern_aliases()
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+    let mut path = uri.path();
+    if let Some(prefix) = state.config.redirect.route_prefix()
+        && let Some(rest) = path.strip_prefix(prefix.as_str())
+        && rest.starts_with('/')
+    {
+        path = rest;
+    }
+    let path = path.trim_start_matches('/');
+    match pattern_alias::resolve(&patterns, path) {
+        Some((_, destination))
+            if blocked_scheme(&destination, &state.config.url_schemes).is_some() =>
+        {
+            tracing::warn!("rejecting pattern redirect: destination scheme is blocked");
+            Err(ApiError::Forbidden("Blocked scheme".to_string()))
+        }
+        Some((alias, destination)) if !pattern_alias::keeps_origin(&alias.target, &destination) => {
+            tracing::warn!(
+                "rejecting pattern redirect: captured text changed the destination host"
+            );
+            Err(ApiError::Forbidden(
+                "Destination leaves the pattern's host".to_string(),
+            ))
+        }
+        Some((_, destination)) => {
+            tracing::info!("pattern alias matched, redirecting...");
+            let destination = strip_tracking_params(&destination, &state.config.privacy);
+            Ok(Redirect::permanent(&destination).into_response())
+        }
+        None => Err(not_found()),
+    }
+}
+
+/// `Link` header with the configured `preconnect` / `dns-prefetch` hints for
+/// the origin of `destination`; `None` when both are off or the destination
+/// has no network origin (e.g. `data:` URLs).
+fn connection_hints(settings: &RedirectSettings, destination: &str) -> Option<HeaderValue> {
+    let origin = url::Url::parse(destination).ok()?.origin();
+    if !origin.is_tuple() {
+        return None;
+    }
+    let origin = origin.ascii_serialization();
+
+    let hints: Vec<String> = [
+        (settings.preconnect, "preconnect"),
+        (settings.dns_prefetch, "dns-prefetch"),
+    ]
+    .into_iter()
+    .filter(|(enabled, _)| *enabled)
+    .map(|(_, rel)| format!("<{origin}>; rel={rel}"))
+    .collect();
+    if hints.is_empty() {
+        return None;
+    }
+    HeaderValue::from_str(&hints.join(", ")).ok()
+}
+
+/// Browsers send `Accept: text/html`; API clients and crawlers usually don't.
+fn accepts_html(headers: &HeaderMap) -> bool {
+    headers
+        .get(ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/html"))
+}
+
+/// Renders the configured interstitial page forwarding to `destination`.
+fn render_interstitial(state: &AppState, destination: &str) -> Result<Response, ApiError> {
+    let mut context = Context::new();
+    context.insert("title", "URL Shortener");
+    context.insert("page", "Redirecting");
+    context.insert("destination", destination);
+    context.insert("delay_secs", &state.config.interstitial.delay_secs);
+
+    let body = get_templates().render(&state.config.interstitial.template, &context)?;
+    Ok(Html(body).into_response())
+}
+
+/// Merges `utm` into the query string of `destination`.
+///
+/// Existing query pairs and the fragment are kept. A UTM key already present
+/// in the destination is only replaced when `overwrite` is set. Destinations
+/// that fail to parse are returned unchanged.
+fn apply_utm(destination: &str, utm: &UtmParams, overwrite: bool) -> String {
+    let Ok(mut url) = url::Url::parse(destination) else {
+        return destination.to_string();
+    };
+
+    let mut pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    let mut changed = false;
+    for (key, value) in utm.pairs() {
+        match pairs.iter_mut().find(|(k, _)| k == key) {
+            Some(existing) if overwrite => {
+                existing.1 = value.to_string();
+                changed = true;
+            }
+            Some(_) => {}
+            None => {
+                pairs.push((key.to_string(), value.to_string()));
+                changed = true;
+            }
+        }
+    }
+
+    if changed {
+        url.query_pairs_mut().clear().extend_pairs(&pairs);
+    }
+    url.into()
+}
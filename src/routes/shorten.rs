@@ -4,15 +4,25 @@
 //! It processes requests to shorten URLs and stores them in the database with
 //! unique identifiers.
 
+use crate::configuration::ChainAction;
+use crate::core::destination::{blocked_scheme, canonical_destination};
 use crate::database::MAX_ALIAS_LENGTH;
 use crate::errors::ApiError;
+use crate::infrastructure::shortener_chain::{follow_hop, shortener_host};
+use crate::middleware::ApiKeyId;
 use crate::response::ApiResponse;
 use crate::state::AppState;
-use crate::{database::DatabaseError, models::UpsertResult};
-use axum::extract::{Query, State};
+use crate::{
+    database::DatabaseError,
+    models::{AliasOutcome, InsertOutcome, LinkSettings, UtmParams},
+};
+use axum::extract::{Extension, Query, State};
+use axum::http::{HeaderMap, header};
+use axum::response::{IntoResponse, Response};
 use axum_extra::{TypedHeader, headers::Host};
 use axum_macros::debug_handler;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use tracing::instrument;
 
 /// Maximum allowed URL length in characters.
@@ -26,6 +36,21 @@ const MAX_ID_RETRIES: usize = 8;
 pub struct ShortenParams {
     /// Optional custom alias to use instead of generating a random ID
     pub alias: Option<String>,
+    /// Opt a new link in to UTM injection on redirect
+    #[serde(default)]
+    pub utm: bool,
+    /// Per-link `utm_source`, overriding the global `utm.source`
+    pub utm_source: Option<String>,
+    /// Per-link `utm_medium`, overriding the global `utm.medium`
+    pub utm_medium: Option<String>,
+    /// Per-link `utm_campaign`, overriding the global `utm.campaign`
+    pub utm_campaign: Option<String>,
+    /// Redirect a new link directly even when the interstitial page is enabled
+    #[serde(default)]
+    pub skip_interstitial: bool,
+    /// Stop redirecting after this many clicks; only for new links, since an
+    /// already shortened URL is shared
+    pub max_clicks: Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -38,6 +63,98 @@ pub struct ShortenResponse {
     pub id: String,
 }
 
+const FORM_URLENCODED: &str = "application/x-www-form-urlencoded";
+
+/// Representations the create endpoints can answer with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShortenFormat {
+    /// The [`ApiResponse`] envelope around [`ShortenResponse`] (default)
+    Json,
+    /// Just the short URL, as `text/plain`
+    Text,
+    /// The [`ShortenResponse`] fields, form-encoded
+    Form,
+}
+
+impl ShortenFormat {
+    /// Picks the representation for an `Accept` header value.
+    ///
+    /// Media ranges are tried in order of their `q` weight. `fallback` is used
+    /// when the client expresses no preference (no header, or only `*/*`);
+    /// unsupported types end up as JSON.
+    pub fn negotiate(accept: Option<&str>, fallback: Self) -> Self {
+        let Some(accept) = accept else {
+            return fallback;
+        };
+
+        let mut ranges: Vec<(&str, f32)> = accept
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let media = parts.next()?.trim();
+                let q = parts
+                    .filter_map(|p| p.trim().strip_prefix("q="))
+                    .find_map(|q| q.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                (!media.is_empty() && q > 0.0).then_some((media, q))
+            })
+            .collect();
+        // Stable sort keeps header order for equal weights
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        for (media, _) in ranges {
+            match media.to_ascii_lowercase().as_str() {
+                "application/json" | "application/*" => return Self::Json,
+                "text/plain" | "text/*" => return Self::Text,
+                FORM_URLENCODED => return Self::Form,
+                "*/*" => return fallback,
+                _ => {}
+            }
+        }
+        Self::Json
+    }
+
+    fn render(self, data: ShortenResponse) -> Response {
+        match self {
+            Self::Json => ApiResponse::success(data).into_response(),
+            Self::Text => (
+                [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+                data.shortened_url,
+            )
+                .into_response(),
+            Self::Form => {
+                let body = url::form_urlencoded::Serializer::new(String::new())
+                    .append_pair("shortened_url", &data.shortened_url)
+                    .append_pair("original_url", &data.original_url)
+                    .append_pair("id", &data.id)
+                    .finish();
+                ([(header::CONTENT_TYPE, FORM_URLENCODED)], body).into_response()
+            }
+        }
+    }
+}
+
+/// Extracts the URL to shorten from the request body.
+///
+/// Form-encoded bodies carrying a `url` field are unpacked (`true` is returned
+/// so the response can mirror the form encoding). Anything else, including
+/// `curl -d 'https://...'` which is labelled form-encoded but has no `url`
+/// field, is taken verbatim.
+fn extract_url(headers: &HeaderMap, body: String) -> (String, bool) {
+    let is_form = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.trim().to_ascii_lowercase().starts_with(FORM_URLENCODED));
+
+    if is_form
+        && let Some((_, url)) =
+            url::form_urlencoded::parse(body.as_bytes()).find(|(key, _)| key == "url")
+    {
+        return (url.into_owned(), true);
+    }
+    (body, false)
+}
+
 /// URL shortening handler that creates short URLs from long URLs.
 ///
 /// This handler processes requests to shorten URLs by generating a unique
@@ -53,7 +170,8 @@ pub struct ShortenResponse {
 ///
 /// * `State(state)` - Application state containing database connection
 /// * `TypedHeader(header)` - Host header for constructing the response URL
-/// * `url` - The URL to shorten (provided in request body as plain text)
+/// * `headers` - `Accept` and `Content-Type` drive content negotiation
+/// * `body` - The URL to shorten (plain text, or a form-encoded `url` field)
 ///
 /// # Request Format
 ///
@@ -63,9 +181,18 @@ pub struct ShortenResponse {
 /// https://www.example.com/very/long/url/with/many/parameters
 /// ```
 ///
+/// or, with `Content-Type: application/x-www-form-urlencoded`, a `url` field:
+///
+/// ```text
+/// url=https%3A%2F%2Fwww.example.com%2Fvery%2Flong%2Furl
+/// ```
+///
 /// # Response Format
 ///
-/// Returns a JSON response with the shortened URL information:
+/// The representation is negotiated from `Accept` (see [`ShortenFormat`]):
+/// `text/plain` returns only the short URL, `application/x-www-form-urlencoded`
+/// the form-encoded fields. Without a preference, form-encoded requests get a
+/// form-encoded answer and everything else gets JSON:
 ///
 /// ```json
 /// {
@@ -93,6 +220,7 @@ pub struct ShortenResponse {
 /// - `200 OK` - URL shortened successfully
 /// - `422 Unprocessable Entity` - Invalid URL format or URL exceeds maximum length
 /// - `500 Internal Server Error` - Database error or ID collision
+/// - `503 Service Unavailable` - Read-only maintenance mode is on
 ///
 /// # URL Validation
 ///
@@ -142,6 +270,8 @@ pub struct ShortenResponse {
 /// - **URL Too Long** - Returns 422 if URL exceeds MAX_URL_LENGTH
 /// - **Invalid URL Format** - Returns 422 with validation error
 /// - **Database Errors** - Returns 500 with internal error message
+/// - **Click Quota on an Existing Link** - Returns 409 when `max_clicks` is
+///   set for a URL that is already shortened
 /// - **ID Collision** - Returns 500 with collision error (rare occurrence)
 ///
 /// # Security Considerations
@@ -165,8 +295,20 @@ pub async fn post_shorten(
     State(state): State<AppState>,
     TypedHeader(header): TypedHeader<Host>,
     Query(params): Query<ShortenParams>,
-    url: String,
-) -> Result<ApiResponse<ShortenResponse>, ApiError> {
+    key_id: Option<Extension<ApiKeyId>>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Response, ApiError> {
+    let (url, form_request) = extract_url(&headers, body);
+    let format = ShortenFormat::negotiate(
+        headers.get(header::ACCEPT).and_then(|v| v.to_str().ok()),
+        if form_request {
+            ShortenFormat::Form
+        } else {
+            ShortenFormat::Json
+        },
+    );
+
     // 1) Early length validation to prevent resource exhaustion
     if url.len() > MAX_URL_LENGTH {
         tracing::warn!("URL length {} exceeds max {}", url.len(), MAX_URL_LENGTH);
@@ -176,116 +318,205 @@ pub async fn post_shorten(
         )));
     }
 
-    // 2) Parse and normalize the URL (lowercase host, remove fragments, etc.)
-    let norm = normalize_url(&url).map_err(|e| {
+    // 2) Refuse script and local schemes before anything else looks at the URL
+    if let Some(scheme) = blocked_scheme(&url, &state.config.url_schemes) {
+        tracing::warn!(scheme = %scheme, "refusing destination with a blocked scheme");
+        return Err(ApiError::Unprocessable(format!("Blocked scheme: {scheme}")));
+    }
+
+    // Normalize the URL (lowercase host, remove fragments, strip tracking
+    // parameters) the same way lookups by URL do
+    let norm = canonical_destination(&url, &state.config.privacy).map_err(|e| {
         tracing::error!("Unable to parse URL: {}", e);
         ApiError::Unprocessable(e.to_string())
     })?;
 
     // let hostname = header.hostname();
 
-    let (upset, code) = insert_with_retry(&state, &norm).await?;
-    if upset.created {
-        state.blooms.s2l.insert(&code);
+    // 3) Links to other shorteners are flagged, refused or resolved
+    let (norm, chained_host) = check_chained_shortener(&state, norm).await?;
+
+    // 4) Insert path: generate a code with retries, attaching the alias if provided
+    if params.max_clicks == Some(0) {
+        return Err(ApiError::BadRequest(
+            "max_clicks must be at least 1".to_string(),
+        ));
     }
 
-    // 3) Insert path: use custom alias if provided, otherwise generate with retries
-    let final_code = if let Some(alias) = params.alias {
-        validate_alias(alias.as_str(), &state)?;
-        match state.database.insert_alias(alias.as_str(), upset.id).await {
-            Ok(()) => {
-                state.blooms.s2l.insert(&alias);
-                alias
-            }
-            Err(DatabaseError::Duplicate) => {
-                return Err(ApiError::Conflict("Alias is already taken".to_string()));
-            }
+    // A quota on a shared link would cut off everyone else using it
+    let quota_conflict = || {
+        ApiError::Conflict(
+            "URL is already shortened; click quotas only apply to new links".to_string(),
+        )
+    };
+    if params.max_clicks.is_some() {
+        match state.database.get_id_by_url(&norm).await {
+            Ok(_) => return Err(quota_conflict()),
+            Err(DatabaseError::NotFound) => {}
             Err(e) => {
-                tracing::error!("Database error on insert with alias: {}", e);
+                tracing::error!("Database error looking up existing link: {}", e);
                 return Err(ApiError::Internal(e.to_string()));
             }
         }
-    } else {
-        code
+    }
+
+    // Aliases follow the code case so they stay reachable by folded lookups
+    let alias = params
+        .alias
+        .as_deref()
+        .map(|alias| state.config.shortener.lookup_code(alias));
+    if let Some(alias) = &alias {
+        validate_alias(alias.as_str(), &state)?;
+    }
+    // Written with the link itself, so it never exists without them; existing
+    // links are shared and keep their own
+    let settings = LinkSettings {
+        owner_key_id: key_id.map(|Extension(key_id)| key_id.0),
+        max_clicks: params.max_clicks,
+        utm: params.utm.then_some(UtmParams {
+            source: params.utm_source,
+            medium: params.utm_medium,
+            campaign: params.utm_campaign,
+        }),
+        skip_interstitial: params.skip_interstitial,
+    };
+    let outcome = insert_with_retry(&state, &norm, alias.as_deref(), &settings).await?;
+    let link_id = outcome.record.id;
+    if params.max_clicks.is_some() && !outcome.created {
+        return Err(quota_conflict());
+    }
+    if outcome.created {
+        state.blooms.s2l.insert(&outcome.record.code);
+    }
+
+    if let Some(host) = &chained_host
+        && outcome.created
+    {
+        state
+            .database
+            .set_link_metadata(link_id, "chained_shortener", host)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error flagging chained shortener: {}", e);
+                ApiError::Internal(e.to_string())
+            })?;
+    }
+
+    let final_code = match outcome.alias {
+        Some(AliasOutcome::Created(alias)) => {
+            state.blooms.s2l.insert(&alias);
+            alias
+        }
+        Some(AliasOutcome::Existing(alias)) => alias,
+        Some(AliasOutcome::Taken(_)) => {
+            return Err(ApiError::Conflict("Alias is already taken".to_string()));
+        }
+        None => outcome.record.code,
     };
 
     tracing::info!("URL shortened and saved successfully");
-    Ok(make_response(
+    Ok(format.render(make_response(
         &state.config.application.base_url,
         &final_code,
         &norm,
-    ))
+    )))
 }
 
-/// Parses and normalizes a URL:
-/// - Enforces http/https schemes
-/// - Removes fragments
-/// - Lowercases host
-/// - Validates proper slashes after scheme using manual parsing
-pub fn normalize_url(raw: &str) -> Result<String, ApiError> {
-    let is_http = raw.starts_with("http://");
-    let is_https = raw.starts_with("https://");
-
-    if is_http || is_https {
-        let scheme_len = if is_http { 7 } else { 8 };
-        if raw[scheme_len..].starts_with('/') {
-            return Err(ApiError::Unprocessable(
-                "Wrong number of slashes (separators) in scheme".to_string(),
-            ));
-        }
-
-        let mut u = url::Url::parse(raw).map_err(|e| ApiError::Unprocessable(e.to_string()))?;
-        u.set_fragment(None);
+/// Applies `chained_shorteners` to a normalized destination.
+///
+/// Returns the URL to store and, with `action: flag`, the shortener host the
+/// link gets tagged with.
+async fn check_chained_shortener(
+    state: &AppState,
+    norm: String,
+) -> Result<(String, Option<String>), ApiError> {
+    let settings = &state.config.chained_shorteners;
+    let base_url = &state.config.application.base_url;
+    if settings.action == ChainAction::Allow {
+        return Ok((norm, None));
+    }
+    let Some(host) = shortener_host(&norm, settings, base_url) else {
+        return Ok((norm, None));
+    };
 
-        if let Some(h) = u.host_str() {
-            let lower = h.to_ascii_lowercase();
-            if lower != h {
-                let _ = u.set_host(Some(&lower));
+    match settings.action {
+        ChainAction::Allow => Ok((norm, None)),
+        ChainAction::Flag => {
+            tracing::warn!(host = %host, "destination is another URL shortener");
+            Ok((norm, Some(host)))
+        }
+        ChainAction::Reject => Err(ApiError::Unprocessable(format!(
+            "Links to other URL shorteners ({host}) are not accepted"
+        ))),
+        ChainAction::Follow => {
+            let timeout = Duration::from_secs(settings.follow_timeout_secs);
+            let target = follow_hop(&norm, &state.dns_cache, &state.config.outbound, timeout)
+                .await
+                .map_err(|e| {
+                    tracing::warn!(host = %host, "could not resolve chained shortener: {}", e);
+                    ApiError::Unprocessable(format!(
+                        "Could not resolve the {host} link to its destination"
+                    ))
+                })?;
+            if target.len() > MAX_URL_LENGTH {
+                return Err(ApiError::Unprocessable(format!(
+                    "The {host} link resolves to a URL longer than {MAX_URL_LENGTH} characters"
+                )));
+            }
+            if let Some(scheme) = blocked_scheme(&target, &state.config.url_schemes) {
+                return Err(ApiError::Unprocessable(format!(
+                    "The {host} link resolves to a blocked scheme ({scheme})"
+                )));
             }
+            let target = canonical_destination(&target, &state.config.privacy)
+                .map_err(|e| ApiError::Unprocessable(e.to_string()))?;
+            if let Some(next) = shortener_host(&target, settings, base_url) {
+                return Err(ApiError::Unprocessable(format!(
+                    "The {host} link points at another URL shortener ({next})"
+                )));
+            }
+            Ok((target, None))
         }
-        return Ok(u.to_string());
-    }
-
-    // If it looks like a URL with a scheme but not http(s), call it what it is: unsupported scheme.
-    if let Some(pos) = raw.find("://") {
-        let scheme = &raw[..pos];
-        return Err(ApiError::Unprocessable(format!(
-            "Unsupported scheme: {}",
-            scheme
-        )));
-    }
-
-    // Explicitly catch http/https missing slashes like "http:example.com".
-    if raw.starts_with("http:") || raw.starts_with("https:") {
-        return Err(ApiError::Unprocessable(
-            "Wrong number of slashes (separators) in scheme".to_string(),
-        ));
     }
-
-    // Everything else is just not a URL we handle.
-    Err(ApiError::Unprocessable(
-        "Unsupported or invalid URL".to_string(),
-    ))
 }
 
-/// Inserts a new URL, retrying ID generation if duplicates occur.
+/// Inserts a new URL (and optional alias), retrying ID generation if duplicates occur.
 /// Relies on the database's Duplicate error to ensure atomicity and avoid TOCTOU issues.
 async fn insert_with_retry(
     state: &AppState,
     norm_url: &str,
-) -> Result<(UpsertResult, String), ApiError> {
+    alias: Option<&str>,
+    settings: &LinkSettings,
+) -> Result<InsertOutcome, ApiError> {
     for attempt in 0..MAX_ID_RETRIES {
-        let code = state.code_generator.generate().map_err(|e| {
-            tracing::error!("Code generation error: {:?}", e);
-            ApiError::Internal("Code generation failed".to_string())
-        })?;
+        let code = state
+            .code_generator
+            .generate_retry(attempt as u32)
+            .map_err(|e| {
+                tracing::error!("Code generation error: {:?}", e);
+                ApiError::Internal("Code generation failed".to_string())
+            })?;
 
-        match state.database.insert_url(code.as_str(), norm_url).await {
-            Ok((upsert, urls)) => return Ok((upsert, urls.code)),
+        match state
+            .database
+            .insert_url_with_alias(code.as_str(), norm_url, alias, settings)
+            .await
+        {
+            Ok(outcome) => return Ok(outcome),
             Err(DatabaseError::Duplicate) => {
                 tracing::warn!("ID collision on attempt {} — retrying", attempt + 1);
                 continue;
             }
+            Err(DatabaseError::ReadOnly) => {
+                return Err(ApiError::ServiceUnavailable(
+                    "Link creation is paused for maintenance, please retry later".to_string(),
+                ));
+            }
+            Err(DatabaseError::Timeout) => {
+                return Err(ApiError::GatewayTimeout(
+                    "Database did not respond in time".to_string(),
+                ));
+            }
             Err(e) => {
                 tracing::error!("Database error on insert: {}", e);
                 return Err(ApiError::Internal(e.to_string()));
@@ -298,17 +529,16 @@ async fn insert_with_retry(
 }
 
 /// Builds a unified response structure for shortened URLs.
-fn make_response(base_url: &str, id: &str, original_url: &str) -> ApiResponse<ShortenResponse> {
+fn make_response(base_url: &str, id: &str, original_url: &str) -> ShortenResponse {
     // Trim any trailing slash from the base_url to prevent double slashes (e.g., "http://localhost:8000//ID")
     let base = base_url.trim_end_matches('/');
     let shortened_url = format!("{}/{}", base, id);
 
-    let response_data = ShortenResponse {
+    ShortenResponse {
         shortened_url,
         original_url: original_url.to_string(),
         id: id.to_string(),
-    };
-    ApiResponse::success(response_data)
+    }
 }
 
 /// Validates a user-provided alias.
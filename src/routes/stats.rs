@@ -0,0 +1,140 @@
+//! # Aggregate Statistics Handler
+//!
+//! This module provides the statistics endpoint used by the status page.
+//! The aggregate is a full scan over `urls` and `aliases`, so results are
+//! cached in memory for `stats.cache_ttl_secs` seconds. With
+//! `stats.snapshot_interval_secs` set, the endpoint reads the latest stored
+//! snapshot instead of aggregating on a cache miss. It also serves the
+//! storage footprint for capacity planning.
+
+use crate::database::DatabaseError;
+use crate::errors::ApiError;
+use crate::models::{AggregateStats, StorageStats};
+use crate::response::ApiResponse;
+use crate::state::AppState;
+use axum::extract::{Query, State};
+use axum_macros::debug_handler;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+/// Short-lived in-memory cache for [`AggregateStats`].
+pub struct StatsCache {
+    ttl: Duration,
+    entry: Mutex<Option<(Instant, AggregateStats)>>,
+}
+
+impl StatsCache {
+    /// Creates an empty cache whose entries live for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entry: Mutex::new(None),
+        }
+    }
+
+    /// Returns the cached value if it is still fresh.
+    pub fn get(&self) -> Option<AggregateStats> {
+        let entry = self.entry.lock();
+        entry
+            .as_ref()
+            .filter(|(stored_at, _)| stored_at.elapsed() < self.ttl)
+            .map(|(_, stats)| stats.clone())
+    }
+
+    /// Stores a freshly computed value.
+    pub fn put(&self, stats: AggregateStats) {
+        *self.entry.lock() = Some((Instant::now(), stats));
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct StatsQuery {
+    /// Skip the cache and the snapshot and aggregate live
+    pub fresh: bool,
+}
+
+/// Aggregate statistics handler.
+///
+/// # Endpoint
+///
+/// `GET /api/stats[?fresh=true]` (requires API key when `stats.require_api_key` is set)
+///
+/// # Response
+///
+/// ```json
+/// {
+///   "success": true,
+///   "message": "ok",
+///   "status": 200,
+///   "time": "2025-01-18T12:00:00Z",
+///   "data": {
+///     "total_links": 42,
+///     "total_aliases": 7,
+///     "total_clicks": 1337,
+///     "expired_links": 3
+///   }
+/// }
+/// ```
+///
+/// # Status Codes
+///
+/// - `200 OK` - Statistics returned (possibly from cache or the latest snapshot)
+/// - `401 Unauthorized` - API key required but missing or invalid
+/// - `500 Internal Server Error` - Database error occurred
+#[debug_handler]
+#[tracing::instrument(name = "stats", skip(state))]
+pub async fn get_stats(
+    State(state): State<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> Result<ApiResponse<AggregateStats>, ApiError> {
+    if !query.fresh
+        && let Some(stats) = state.stats_cache.get()
+    {
+        return Ok(ApiResponse::success(stats));
+    }
+
+    let snapshot = if !query.fresh && state.config.stats.snapshot_interval_secs > 0 {
+        state.database.latest_stats().await
+    } else {
+        Err(DatabaseError::NotFound)
+    };
+    let stats = match snapshot {
+        Ok(stats) => Ok(stats),
+        // No snapshot yet (or not wanted): aggregate live
+        Err(DatabaseError::NotFound) => state.database.aggregate_stats().await,
+        Err(e) => Err(e),
+    };
+    let stats = stats.map_err(|e| {
+        tracing::error!("Database error while computing stats: {}", e);
+        ApiError::Internal(e.to_string())
+    })?;
+    state.stats_cache.put(stats.clone());
+
+    Ok(ApiResponse::success(stats))
+}
+
+/// Storage footprint handler; see
+/// [`UrlDatabase::storage_stats`](crate::database::UrlDatabase::storage_stats).
+///
+/// # Endpoint
+///
+/// `GET /api/admin/storage` (protected - requires API key)
+///
+/// # Status Codes
+///
+/// - `200 OK` - Row counts, database size and bloom snapshot bytes
+/// - `401 Unauthorized` - Missing or invalid API key
+/// - `500 Internal Server Error` - Database error occurred
+#[debug_handler]
+#[tracing::instrument(name = "storage_stats", skip(state))]
+pub async fn get_storage_stats(
+    State(state): State<AppState>,
+) -> Result<ApiResponse<StorageStats>, ApiError> {
+    let stats = state.database.storage_stats().await.map_err(|e| {
+        tracing::error!("Database error while measuring storage: {}", e);
+        ApiError::Internal(e.to_string())
+    })?;
+    Ok(ApiResponse::success(stats))
+}
@@ -1,14 +1,32 @@
 // shortcode/mod.rs
-use crate::database::UrlDatabase;
-use anyhow::{Context, Result, anyhow};
+use crate::configuration::{BloomSettings, RebuildOverMemory};
+use crate::database::{DatabaseError, UrlDatabase};
+use crate::generator::CodePattern;
+use anyhow::{Context, Result, anyhow, bail};
 use fastbloom_rs::{BloomFilter, FilterBuilder, Hashes, Membership};
 use parking_lot::RwLock;
-use std::{env, sync::Arc};
+use std::{
+    collections::HashMap,
+    env,
+    f64::consts::LN_2,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+};
 
 pub const S2L_SNAPSHOT_KEY: &str = "short_to_long";
 const EXPECTED: u64 = 10_000_000;
 const FPP: f64 = 0.01;
 const PAGE: u64 = 50_000;
+/// Smallest page a memory cap can shrink rebuild pages to.
+const MIN_PAGE: u64 = 1_000;
+/// Bytes budgeted per paged code (`String` header plus a typical code).
+const CODE_BYTES: u64 = 64;
+/// A shrunk filter this inaccurate no longer saves database lookups.
+const MAX_SHRUNK_FPP: f64 = 0.5;
+/// Candidates checked per round trip by [`BloomState::shortest_available`].
+const AVAILABILITY_BATCH: usize = 256;
 
 pub trait ProbSet: Send + Sync {
     fn may_contain(&self, key: &str) -> bool;
@@ -29,6 +47,142 @@ pub trait ProbSet: Send + Sync {
 #[derive(Clone)]
 pub struct BloomState {
     pub s2l: Arc<dyn ProbSet>,
+    ready: Arc<AtomicBool>,
+    false_negatives: Arc<AtomicU64>,
+    repairing: Arc<AtomicBool>,
+}
+
+impl BloomState {
+    /// Wraps a fully populated filter.
+    pub fn new(s2l: Arc<dyn ProbSet>) -> Self {
+        Self::with_ready(s2l, true)
+    }
+
+    /// Wraps a filter that is still being rebuilt; see [`BloomState::is_ready`].
+    fn rebuilding(s2l: Arc<dyn ProbSet>) -> Self {
+        Self::with_ready(s2l, false)
+    }
+
+    fn with_ready(s2l: Arc<dyn ProbSet>, ready: bool) -> Self {
+        Self {
+            s2l,
+            ready: Arc::new(AtomicBool::new(ready)),
+            false_negatives: Arc::new(AtomicU64::new(0)),
+            repairing: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// `false` while the filter is being rebuilt in the background.
+    ///
+    /// An incomplete filter produces false negatives, so callers must fall back
+    /// to the database and must not persist it as a snapshot until it is ready.
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Acquire)
+    }
+
+    /// Like [`ProbSet::may_contain`] on s2l, but answers `true` while the
+    /// filter is not ready so lookups go straight to the database.
+    pub fn may_contain(&self, code: &str) -> bool {
+        !self.is_ready() || self.s2l.may_contain(code)
+    }
+
+    /// How many stored codes the filter has ruled out since startup; see
+    /// [`BloomState::record_false_negative`].
+    pub fn false_negatives(&self) -> u64 {
+        self.false_negatives.load(Ordering::Relaxed)
+    }
+
+    /// Records that the filter ruled out `code` although the database holds it.
+    ///
+    /// A Bloom filter never misses a code it was given, so a miss means the
+    /// filter and the database have drifted apart. `code` is added right away
+    /// and a background rebuild re-adds every stored code; a rebuild already
+    /// under way is not started again.
+    pub fn record_false_negative(
+        &self,
+        db: &Arc<dyn UrlDatabase>,
+        settings: &BloomSettings,
+        code: &str,
+    ) {
+        self.s2l.insert(code);
+        let total = self.false_negatives.fetch_add(1, Ordering::Relaxed) + 1;
+        tracing::debug!(target: "metrics", bloom_false_negatives = total);
+        tracing::warn!(code, "Bloom filter ruled out a stored code; rebuilding it.");
+
+        if self.repairing.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let page = plan_rebuild(settings).map_or(PAGE, |plan| plan.page);
+        let (db, state) = (Arc::clone(db), self.clone());
+        tokio::spawn(async move {
+            rebuild(db, state.clone(), page).await;
+            state.repairing.store(false, Ordering::Release);
+        });
+    }
+
+    /// Bulk existence check with a Bloom pre-filter.
+    ///
+    /// Codes the s2l filter reports as definitely absent are answered with `false`
+    /// without touching the database; only the possibly-present ones are confirmed
+    /// through [`UrlDatabase::exists_many`].
+    pub async fn exists_many(
+        &self,
+        db: &dyn UrlDatabase,
+        codes: &[String],
+    ) -> Result<HashMap<String, bool>, DatabaseError> {
+        let (maybe_present, absent): (Vec<String>, Vec<String>) = codes
+            .iter()
+            .cloned()
+            .partition(|code| self.may_contain(code));
+
+        let mut result = if maybe_present.is_empty() {
+            HashMap::with_capacity(codes.len())
+        } else {
+            db.exists_many(&maybe_present).await?
+        };
+        result.extend(absent.into_iter().map(|code| (code, false)));
+
+        Ok(result)
+    }
+
+    /// Shortest unused code matching (a prefix of) `pattern`.
+    ///
+    /// Walks [`CodePattern::candidates`] in batches through
+    /// [`BloomState::exists_many`], so candidates the filter rules out never
+    /// reach the database. Gives up with `None` after `max_tries` candidates.
+    pub async fn shortest_available(
+        &self,
+        db: &dyn UrlDatabase,
+        pattern: &CodePattern,
+        max_tries: usize,
+    ) -> Result<Option<String>, DatabaseError> {
+        let mut candidates = pattern.candidates().take(max_tries);
+        loop {
+            let batch: Vec<String> = candidates.by_ref().take(AVAILABILITY_BATCH).collect();
+            if batch.is_empty() {
+                return Ok(None);
+            }
+            let taken = self.exists_many(db, &batch).await?;
+            if let Some(free) = batch.into_iter().find(|code| !taken[code]) {
+                return Ok(Some(free));
+            }
+        }
+    }
+}
+
+/// Checks that `bytes` has the shape of a [`ProbSet::snapshot`] payload: a
+/// hash count followed by whole 64-bit words.
+pub(crate) fn check_snapshot_payload(bytes: &[u8]) -> Result<()> {
+    if bytes.len() < 4 {
+        return Err(anyhow!("Bloom snapshot payload too small"));
+    }
+    let body = bytes.len() - 4;
+    if !body.is_multiple_of(8) {
+        return Err(anyhow!(
+            "Bloom snapshot body length {body} is not a multiple of 8"
+        ));
+    }
+    Ok(())
 }
 
 pub struct LocalBloom {
@@ -36,7 +190,7 @@ pub struct LocalBloom {
 }
 
 impl LocalBloom {
-    pub fn _new(expected: u64, fpp: f64) -> Self {
+    pub fn new(expected: u64, fpp: f64) -> Self {
         let bf = FilterBuilder::new(expected, fpp).build_bloom_filter();
         Self {
             inner: RwLock::new(bf),
@@ -57,12 +211,9 @@ impl LocalBloom {
     }
 
     pub fn from_snapshot(bytes: &[u8]) -> Result<Self> {
-        if bytes.len() < 4 {
-            return Err(anyhow!("Bloom snapshot payload too small"));
-        }
+        check_snapshot_payload(bytes)?;
         let hashes = u32::from_be_bytes(bytes[..4].try_into()?);
         let body = &bytes[4..];
-        assert_eq!(body.len() % 8, 0);
 
         let mut words = Vec::<u64>::with_capacity(body.len() / 8);
         for chunk in body.chunks_exact(8) {
@@ -93,41 +244,136 @@ impl ProbSet for LocalBloom {
     }
 }
 
-pub async fn build_bloom_state(db: &Arc<dyn UrlDatabase>) -> Result<BloomState> {
-    if let Some(bytes) = db
+/// Filter accuracy and page size a rebuild uses.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct RebuildPlan {
+    fpp: f64,
+    page: u64,
+}
+
+/// Approximate size of an optimal filter for `expected` items at `fpp`.
+fn filter_bytes(expected: u64, fpp: f64) -> u64 {
+    let bits = -(expected as f64) * fpp.ln() / (LN_2 * LN_2);
+    (bits / 8.0).ceil() as u64
+}
+
+/// Fits a rebuild into `bloom.rebuild_max_memory`.
+///
+/// Pages shrink first (to an eighth of the cap, at least [`MIN_PAGE`]
+/// codes). If the filter and its snapshot copy still do not fit, the filter
+/// is sized to the remaining budget at a higher false-positive rate, or the
+/// rebuild is refused with `rebuild_over_memory: fail`.
+fn plan_rebuild(settings: &BloomSettings) -> Result<RebuildPlan> {
+    let Some(cap) = settings.rebuild_max_memory else {
+        return Ok(RebuildPlan {
+            fpp: FPP,
+            page: PAGE,
+        });
+    };
+    let page = (cap / 8 / CODE_BYTES).clamp(MIN_PAGE, PAGE);
+    let needed = 2 * filter_bytes(EXPECTED, FPP) + page * CODE_BYTES;
+    if needed <= cap {
+        return Ok(RebuildPlan { fpp: FPP, page });
+    }
+
+    if settings.rebuild_over_memory == RebuildOverMemory::Fail {
+        bail!(
+            "Bloom rebuild needs about {needed} bytes, more than bloom.rebuild_max_memory ({cap})"
+        );
+    }
+    let budget_bits = (cap.saturating_sub(page * CODE_BYTES) / 2 * 8) as f64;
+    let fpp = (-(budget_bits / EXPECTED as f64) * LN_2 * LN_2).exp();
+    if fpp >= MAX_SHRUNK_FPP {
+        bail!("bloom.rebuild_max_memory ({cap} bytes) is too small for a useful Bloom filter");
+    }
+    tracing::warn!(
+        needed,
+        cap,
+        fpp,
+        "Bloom rebuild exceeds bloom.rebuild_max_memory; building a smaller filter"
+    );
+    Ok(RebuildPlan { fpp, page })
+}
+
+/// Builds the s2l filter at startup.
+///
+/// A persisted snapshot is used when it loads and decodes. Otherwise the
+/// service starts with an empty, not-ready filter and repopulates it from the
+/// database in the background, unless `settings.require_bloom_snapshot` is set,
+/// in which case the missing or corrupt snapshot is returned as an error.
+/// The rebuild stays within `settings.rebuild_max_memory`; see
+/// [`plan_rebuild`].
+pub async fn build_bloom_state(
+    db: &Arc<dyn UrlDatabase>,
+    settings: &BloomSettings,
+) -> Result<BloomState> {
+    let reason = match load_snapshot(db).await {
+        Ok(Some(s2l)) => {
+            tracing::info!("Loaded Bloom snapshot from database.");
+            return Ok(BloomState::new(Arc::new(s2l)));
+        }
+        Ok(None) => anyhow!("no s2l bloom snapshot found in database"),
+        Err(err) => err,
+    };
+
+    if settings.require_bloom_snapshot {
+        return Err(reason.context("bloom.require_bloom_snapshot is enabled"));
+    }
+
+    tracing::warn!(
+        reason = %format!("{reason:#}"),
+        "starting with an empty Bloom filter and rebuilding it in the background"
+    );
+
+    let plan = plan_rebuild(settings)?;
+    let state = BloomState::rebuilding(Arc::new(LocalBloom::new(EXPECTED, plan.fpp)));
+    tokio::spawn(rebuild(Arc::clone(db), state.clone(), plan.page));
+    Ok(state)
+}
+
+async fn load_snapshot(db: &Arc<dyn UrlDatabase>) -> Result<Option<LocalBloom>> {
+    let Some(bytes) = db
         .load_bloom_snapshot(S2L_SNAPSHOT_KEY)
         .await
         .context("failed to load s2l bloom snapshot from database")?
-    {
-        let s2l = LocalBloom::from_snapshot(&bytes)
-            .context("failed to decode s2l bloom snapshot payload")?;
-        tracing::info!("Loaded Bloom snapshot from database.");
-        return Ok(BloomState { s2l: Arc::new(s2l) });
-    }
+    else {
+        return Ok(None);
+    };
 
-    // First-time build: pull data from DB in pages
-    let mut shorts: Vec<Vec<u8>> = Vec::new();
+    LocalBloom::from_snapshot(&bytes)
+        .map(Some)
+        .context("failed to decode s2l bloom snapshot payload")
+}
 
-    let mut offset: u64 = 0;
+/// Pages every short code into `state.s2l`, marks it ready and persists it.
+async fn rebuild(db: Arc<dyn UrlDatabase>, state: BloomState, page: u64) {
+    let mut after: Option<String> = None;
+    let mut codes: u64 = 0;
 
     loop {
-        let batch = db.list_short_codes(offset, PAGE).await?;
-        if batch.is_empty() {
-            break;
-        }
-        for rec in &batch {
-            shorts.push(rec.as_bytes().to_vec());
+        let batch = match db.list_short_codes_after(after.as_deref(), page).await {
+            Ok(batch) => batch,
+            Err(err) => {
+                // Stay not-ready: lookups keep going to the database.
+                tracing::error!(error = %err, "Bloom filter rebuild failed");
+                return;
+            }
+        };
+        for code in &batch {
+            state.s2l.insert(code);
         }
-        offset += batch.len() as u64;
-        if batch.len() < PAGE as usize {
+        codes += batch.len() as u64;
+        if batch.len() < page as usize {
             break;
         }
+        after = batch.last().cloned();
     }
 
-    let s2l = LocalBloom::from_items(shorts.iter().map(|v| &v[..]), EXPECTED, FPP);
+    state.ready.store(true, Ordering::Release);
+    tracing::info!(codes, "Bloom filter rebuild complete.");
 
     if not_disable_bf_snapshots() {
-        match s2l.snapshot() {
+        match state.s2l.snapshot() {
             Ok(bytes) => {
                 if let Err(err) = db
                     .save_bloom_snapshot(S2L_SNAPSHOT_KEY, &bytes)
@@ -142,8 +388,6 @@ pub async fn build_bloom_state(db: &Arc<dyn UrlDatabase>) -> Result<BloomState>
             }
         }
     }
-
-    Ok(BloomState { s2l: Arc::new(s2l) })
 }
 
 pub(crate) fn not_disable_bf_snapshots() -> bool {
@@ -152,3 +396,119 @@ pub(crate) fn not_disable_bf_snapshots() -> bool {
         Ok("1") | Ok("true") | Ok("TRUE")
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::sqlite::test_db;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn shortest_available_skips_taken_codes() {
+        let db: Arc<dyn UrlDatabase> = Arc::new(test_db().await);
+        db.insert_url("0", "https://example.com/zero")
+            .await
+            .unwrap();
+        db.insert_url("1", "https://example.com/one").await.unwrap();
+        let state = BloomState::new(Arc::new(LocalBloom::from_items(["0", "1"], 1_000, FPP)));
+        let pattern = CodePattern::parse("DD", crate::generator::DEFAULT_ALPHABET).unwrap();
+
+        let free = state.shortest_available(&*db, &pattern, 100).await.unwrap();
+        assert_eq!(free.as_deref(), Some("2"));
+        assert_eq!(
+            state.shortest_available(&*db, &pattern, 2).await.unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn corrupt_snapshot_falls_back_to_background_rebuild() {
+        let db: Arc<dyn UrlDatabase> = Arc::new(test_db().await);
+        let code = "Abc1234";
+        db.insert_url(code, "https://example.com/").await.unwrap();
+        db.save_bloom_snapshot(S2L_SNAPSHOT_KEY, &[0, 0, 0, 7, 1])
+            .await
+            .unwrap();
+
+        let state = build_bloom_state(&db, &BloomSettings::default())
+            .await
+            .unwrap();
+        assert!(state.may_contain(code));
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !state.is_ready() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("rebuild did not finish");
+        assert!(state.s2l.may_contain(code));
+    }
+
+    #[tokio::test]
+    async fn false_negative_adds_the_code_and_rebuilds_the_filter() {
+        let db: Arc<dyn UrlDatabase> = Arc::new(test_db().await);
+        db.insert_url("Abc1234", "https://example.com/a")
+            .await
+            .unwrap();
+        db.insert_url("Def5678", "https://example.com/d")
+            .await
+            .unwrap();
+        let state = BloomState::new(Arc::new(LocalBloom::new(1_000, FPP)));
+        assert!(!state.may_contain("Abc1234"));
+
+        state.record_false_negative(&db, &BloomSettings::default(), "Abc1234");
+        assert!(state.may_contain("Abc1234"));
+        assert_eq!(state.false_negatives(), 1);
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !state.may_contain("Def5678") {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("rebuild did not re-add the other stored code");
+    }
+
+    #[tokio::test]
+    async fn strict_mode_refuses_corrupt_or_missing_snapshot() {
+        let strict = BloomSettings {
+            require_bloom_snapshot: true,
+            ..BloomSettings::default()
+        };
+
+        let db: Arc<dyn UrlDatabase> = Arc::new(test_db().await);
+        assert!(build_bloom_state(&db, &strict).await.is_err());
+
+        db.save_bloom_snapshot(S2L_SNAPSHOT_KEY, &[0, 0, 0, 7, 1])
+            .await
+            .unwrap();
+        assert!(build_bloom_state(&db, &strict).await.is_err());
+    }
+
+    #[test]
+    fn rebuild_plan_fits_the_memory_cap() {
+        let capped = |cap, rebuild_over_memory| BloomSettings {
+            rebuild_max_memory: Some(cap),
+            rebuild_over_memory,
+            ..BloomSettings::default()
+        };
+
+        let roomy = plan_rebuild(&capped(1 << 30, RebuildOverMemory::Fail)).unwrap();
+        assert_eq!(
+            roomy,
+            RebuildPlan {
+                fpp: FPP,
+                page: PAGE
+            }
+        );
+
+        let tight = plan_rebuild(&capped(8 << 20, RebuildOverMemory::Shrink)).unwrap();
+        assert!(tight.fpp > FPP && tight.fpp < MAX_SHRUNK_FPP);
+        assert!(tight.page < PAGE);
+        assert!(2 * filter_bytes(EXPECTED, tight.fpp) + tight.page * CODE_BYTES <= 8 << 20);
+
+        assert!(plan_rebuild(&capped(8 << 20, RebuildOverMemory::Fail)).is_err());
+        assert!(plan_rebuild(&capped(1 << 10, RebuildOverMemory::Shrink)).is_err());
+    }
+}
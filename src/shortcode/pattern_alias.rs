@@ -0,0 +1,328 @@
+//! Wildcard aliases.
+//!
+//! A pattern such as `go/*` is literal text with `*` wildcards, each matching
+//! one or more characters, `/` included. Its target template refers to the
+//! captured text as `{1}`, `{2}`, ... in order, so `go/*` with target
+//! `https://docs.example.com/{1}` sends `/go/guides/setup` to
+//! `https://docs.example.com/guides/setup`.
+//!
+//! Patterns must start with a literal path segment (`go/`), which keeps them
+//! disjoint from exact codes: codes never contain `/`, so exact lookups are
+//! unaffected and patterns are only consulted for paths no route matched.
+//!
+//! Captures are percent-encoded on the way in, keeping `/` only where the
+//! placeholder sits in the path, and a destination whose scheme or host
+//! differs from the template's is refused, so placeholders belong in the
+//! path, query or fragment.
+
+use crate::models::PatternAlias;
+
+/// Upper bound on `*` wildcards in one pattern.
+pub const MAX_WILDCARDS: usize = 4;
+/// Upper bound on the length of a pattern, in characters.
+pub const MAX_PATTERN_LENGTH: usize = 128;
+/// First path segments owned by the service's own routes.
+pub(crate) const RESERVED_SEGMENTS: &[&str] = &["api", "admin", "static"];
+
+/// Checks `pattern` and that `target` only refers to captures it produces.
+pub fn validate(pattern: &str, target: &str) -> Result<(), String> {
+    if pattern.chars().count() > MAX_PATTERN_LENGTH {
+        return Err(format!(
+            "Pattern cannot exceed {} characters",
+            MAX_PATTERN_LENGTH
+        ));
+    }
+    if let Some(c) = pattern
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/' | '*')))
+    {
+        return Err(format!("Pattern contains unsupported character '{}'", c));
+    }
+    let prefix = pattern.split('*').next().unwrap_or_default();
+    let Some((segment, _)) = prefix.split_once('/') else {
+        return Err("Pattern must start with a literal path segment, e.g. `go/*`".to_string());
+    };
+    if segment.is_empty() || RESERVED_SEGMENTS.contains(&segment) {
+        return Err(format!("Pattern cannot start with `{}/`", segment));
+    }
+
+    let wildcards = pattern.matches('*').count();
+    if wildcards == 0 {
+        return Err("Pattern must contain a `*` wildcard".to_string());
+    }
+    if wildcards > MAX_WILDCARDS {
+        return Err(format!(
+            "Pattern cannot have more than {} wildcards",
+            MAX_WILDCARDS
+        ));
+    }
+    if pattern.contains("**") {
+        return Err("Pattern cannot contain adjacent wildcards".to_string());
+    }
+
+    for placeholder in placeholders(target) {
+        if placeholder == 0 || placeholder > wildcards {
+            return Err(format!(
+                "Target refers to {{{}}} but the pattern has {} wildcard(s)",
+                placeholder, wildcards
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Text captured by each wildcard when `path` matches `pattern`.
+///
+/// Literal runs between wildcards are matched leftmost, which finds a match
+/// whenever one exists.
+pub fn captures<'a>(pattern: &str, path: &'a str) -> Option<Vec<&'a str>> {
+    let mut parts = pattern.split('*');
+    let mut remaining = path.strip_prefix(parts.next()?)?;
+    let literals: Vec<&str> = parts.collect();
+    let mut captured = Vec::with_capacity(literals.len());
+
+    for (i, literal) in literals.iter().enumerate() {
+        let capture = if i + 1 == literals.len() {
+            let capture = remaining.strip_suffix(literal)?;
+            remaining = "";
+            capture
+        } else {
+            // Each wildcard takes at least one character
+            let skip = remaining.chars().next()?.len_utf8();
+            let at = remaining[skip..].find(literal)? + skip;
+            let capture = &remaining[..at];
+            remaining = &remaining[at + literal.len()..];
+            capture
+        };
+        if capture.is_empty() {
+            return None;
+        }
+        captured.push(capture);
+    }
+    Some(captured)
+}
+
+/// Value substituted for every placeholder when checking a template.
+const PROBE: &str = "x";
+
+/// Substitutes `captures` into the `{n}` placeholders of `target` in one
+/// pass, so text a capture brings along is never substituted again.
+///
+/// Each capture is percent-encoded; `/` and existing `%XX` escapes are kept
+/// only when the placeholder sits in the path.
+pub fn expand(target: &str, captures: &[&str]) -> String {
+    let mut expanded = String::with_capacity(target.len());
+    let mut rest = target;
+    while let Some(open) = rest.find('{') {
+        expanded.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let placeholder = after.split_once('}').and_then(|(number, tail)| {
+            let index = number.parse::<usize>().ok()?.checked_sub(1)?;
+            Some((*captures.get(index)?, tail))
+        });
+        match placeholder {
+            Some((capture, tail)) => {
+                let in_path = in_path(&target[..target.len() - rest.len() + open]);
+                encode_capture(&mut expanded, capture, in_path);
+                rest = tail;
+            }
+            None => {
+                expanded.push('{');
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// `target` with every placeholder filled by a one-character capture, as
+/// checked when the pattern is registered.
+pub fn probe(target: &str) -> String {
+    expand(target, &[PROBE; MAX_WILDCARDS])
+}
+
+/// Whether `destination` has the scheme, host and port of `target`'s
+/// [`probe`], i.e. the captures only changed its path, query or fragment.
+pub fn keeps_origin(target: &str, destination: &str) -> bool {
+    let origin = |url: &str| {
+        url::Url::parse(url).ok().map(|url| {
+            (
+                url.scheme().to_string(),
+                url.host_str().map(str::to_string),
+                url.port_or_known_default(),
+            )
+        })
+    };
+    match (origin(&probe(target)), origin(destination)) {
+        (Some(expected), Some(actual)) => expected == actual,
+        _ => false,
+    }
+}
+
+/// Destination for `path` under the most specific matching pattern.
+///
+/// More literal characters win; ties go to fewer wildcards, then to the
+/// pattern registered first.
+pub fn resolve<'a>(patterns: &'a [PatternAlias], path: &str) -> Option<(&'a PatternAlias, String)> {
+    let mut ordered: Vec<&PatternAlias> = patterns.iter().collect();
+    ordered.sort_by_key(|alias| {
+        let wildcards = alias.pattern.matches('*').count();
+        (
+            std::cmp::Reverse(alias.pattern.len() - wildcards),
+            wildcards,
+            alias.id,
+        )
+    });
+    ordered.into_iter().find_map(|alias| {
+        captures(&alias.pattern, path).map(|captured| (alias, expand(&alias.target, &captured)))
+    })
+}
+
+/// Whether a placeholder after `prefix` lands in the path, rather than in the
+/// authority, query or fragment.
+fn in_path(prefix: &str) -> bool {
+    if prefix.contains(['?', '#']) {
+        return false;
+    }
+    match prefix.split_once("://") {
+        Some((_, authority)) => authority.contains('/'),
+        None => true,
+    }
+}
+
+/// Appends `capture` to `out`, percent-encoding everything but unreserved
+/// characters and, in the path, `/` and well-formed `%XX` escapes.
+fn encode_capture(out: &mut String, capture: &str, in_path: bool) {
+    let bytes = capture.as_bytes();
+    for (i, &byte) in bytes.iter().enumerate() {
+        let escaped = byte == b'%'
+            && bytes.len() > i + 2
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit();
+        if byte.is_ascii_alphanumeric()
+            || matches!(byte, b'-' | b'.' | b'_' | b'~')
+            || (in_path && (byte == b'/' || escaped))
+        {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+}
+
+/// Numbers of the `{n}` placeholders in `target`.
+fn placeholders(target: &str) -> impl Iterator<Item = usize> + '_ {
+    target.split('{').skip(1).filter_map(|rest| {
+        let (number, _) = rest.split_once('}')?;
+        number.parse().ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alias(id: i64, pattern: &str, target: &str) -> PatternAlias {
+        PatternAlias {
+            id,
+            pattern: pattern.to_string(),
+            target: target.to_string(),
+        }
+    }
+
+    #[test]
+    fn captures_each_wildcard() {
+        assert_eq!(
+            captures("go/*", "go/guides/setup"),
+            Some(vec!["guides/setup"])
+        );
+        assert_eq!(
+            captures("gh/*/issues/*", "gh/app/issues/42"),
+            Some(vec!["app", "42"])
+        );
+        assert_eq!(captures("go/*", "go/"), None);
+        assert_eq!(captures("go/*", "docs/x"), None);
+        assert_eq!(captures("gh/*/issues/*", "gh/app/pulls/42"), None);
+    }
+
+    #[test]
+    fn resolve_prefers_the_most_specific_pattern() {
+        let patterns = [
+            alias(1, "go/*", "https://example.com/{1}"),
+            alias(2, "go/docs/*", "https://docs.example.com/{1}"),
+        ];
+
+        let destination = |path| resolve(&patterns, path).map(|(_, destination)| destination);
+
+        assert_eq!(
+            destination("go/docs/intro").as_deref(),
+            Some("https://docs.example.com/intro")
+        );
+        assert_eq!(
+            destination("go/blog").as_deref(),
+            Some("https://example.com/blog")
+        );
+        assert_eq!(destination("other/blog"), None);
+    }
+
+    #[test]
+    fn expand_encodes_captures_outside_the_path() {
+        assert_eq!(
+            expand("https://example.com/{1}", &["a b/c%20d"]),
+            "https://example.com/a%20b/c%20d"
+        );
+        assert_eq!(
+            expand("https://example.com{1}", &["@evil.com"]),
+            "https://example.com%40evil.com"
+        );
+        assert_eq!(
+            expand("https://{1}.example.com/", &["evil.com/"]),
+            "https://evil.com%2F.example.com/"
+        );
+        assert_eq!(
+            expand("https://example.com/?q={1}", &["a/b&c=d"]),
+            "https://example.com/?q=a%2Fb%26c%3Dd"
+        );
+    }
+
+    #[test]
+    fn expand_substitutes_in_a_single_pass() {
+        assert_eq!(
+            expand("https://example.com/{1}/{2}", &["{2}", "b"]),
+            "https://example.com/%7B2%7D/b"
+        );
+        assert_eq!(
+            expand("https://example.com/{2}/{1}", &["a", "{1}"]),
+            "https://example.com/%7B1%7D/a"
+        );
+    }
+
+    #[test]
+    fn keeps_origin_refuses_captures_that_change_the_host() {
+        let template = "https://example.com/{1}";
+        assert!(keeps_origin(
+            template,
+            &expand(template, &["docs/@evil.com"])
+        ));
+
+        let template = "https://example.com{1}";
+        assert!(!keeps_origin(template, &expand(template, &["@evil.com"])));
+        assert!(!keeps_origin(template, &expand(template, &[".evil.com"])));
+
+        let template = "https://{1}.example.com/";
+        assert!(!keeps_origin(template, &expand(template, &["evil.com/"])));
+        assert!(!keeps_origin(template, &expand(template, &["other"])));
+    }
+
+    #[test]
+    fn validate_rejects_unanchored_or_reserved_patterns() {
+        assert!(validate("go/*", "https://example.com/{1}").is_ok());
+        assert!(validate("*", "https://example.com/{1}").is_err());
+        assert!(validate("go*", "https://example.com/{1}").is_err());
+        assert!(validate("api/*", "https://example.com/{1}").is_err());
+        assert!(validate("go/**", "https://example.com/{1}").is_err());
+        assert!(validate("go/docs", "https://example.com/").is_err());
+        assert!(validate("go/*", "https://example.com/{2}").is_err());
+    }
+}
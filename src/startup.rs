@@ -17,6 +17,7 @@
 //! The application router is organized into several route groups:
 //! - **Public API** - Health check and redirect endpoints (no authentication)
 //! - **Protected API** - URL shortening endpoint (requires API key)
+//! - **Stats** - Aggregate statistics (API key optional, see `stats.require_api_key`)
 //! - **Admin Panel** - Web interface for management
 //! - **Static Files** - CSS, JavaScript, and other assets
 //!
@@ -47,40 +48,40 @@
 //! # }
 //! ```
 
-use crate::configuration::Settings;
+use crate::configuration::{MigrationSettings, PendingMigrationPolicy, Settings};
 use crate::core::security::jwt::JwtKeys;
-use crate::database::postgres_sql::PostgresUrlDatabase;
-use crate::database::{SqliteUrlDatabase, UrlDatabase};
+use crate::database::PendingMigration;
 use crate::features::auth::repositories::NoopAuthRepo;
 use crate::features::auth::routes as auth;
 use crate::features::auth::services::AuthService;
 use crate::features::users;
 use crate::features::users::repositories::NoopUserRepo;
 use crate::features::users::services::UserService;
-use crate::generator::{DEFAULT_ALPHABET, build_generator};
+use crate::generator::{DEFAULT_ALPHABET, ShortCodeGenerator, build_generator};
 use crate::infrastructure::db::{self};
 use crate::infrastructure::email::EmailService;
-use crate::middleware::check_api_key;
+use crate::middleware::{check_api_key, enforce_timeout};
 use crate::routes::{
-    get_admin_dashboard, get_analytics, get_index, get_login, get_redirect, get_register, get_urls,
-    get_user_profile, get_users, health_check, post_shorten, serve_openapi_spec, serve_swagger_ui,
+    get_admin_dashboard, get_analytics, get_index, get_link_admin, get_login, get_pattern_redirect,
+    get_preview, get_redirect, get_register, get_stats, get_storage_stats, get_urls,
+    get_user_profile, get_users, health_check, post_pattern_alias, post_preview_token,
+    post_self_test, post_shorten, put_read_only, serve_openapi_spec, serve_swagger_ui,
 };
 use axum::middleware::from_fn;
 use tokio::time::Duration as TokioDuration;
 
-use crate::shortcode::bloom_filter::{
-    S2L_SNAPSHOT_KEY, build_bloom_state, not_disable_bf_snapshots,
-};
-use crate::state::AppState;
+use crate::shortcode::bloom_filter::{S2L_SNAPSHOT_KEY, not_disable_bf_snapshots};
+use crate::state::{AppState, StartupError};
 use crate::telemetry::MakeRequestUuid;
 use crate::{DatabaseType, capture_client_meta};
 use anyhow::{Context, Result};
 use axum::http::{Request, Response};
 use axum::{
     Router,
+    extract::DefaultBodyLimit,
     http::HeaderName,
     middleware::from_fn_with_state,
-    routing::{get, post},
+    routing::{get, post, put},
 };
 use std::collections::HashSet;
 
@@ -234,48 +235,45 @@ impl Application {
     /// # }
     /// ```
     pub async fn build(cfg: Settings) -> Result<Self, anyhow::Error> {
-        let url_db: Arc<dyn UrlDatabase> = match cfg.database.r#type {
-            DatabaseType::Sqlite => {
-                let db = SqliteUrlDatabase::from_config(&cfg.database).await?;
-                db.migrate().await?;
-                Arc::new(db) as Arc<dyn UrlDatabase>
-            }
-            DatabaseType::Postgres => {
-                let db = PostgresUrlDatabase::from_config(&cfg.database).await?;
-                db.migrate().await?;
-                Arc::new(db) as Arc<dyn UrlDatabase>
-            }
-        };
-
         let code_gen = build_generator(&cfg.shortener);
-        let allowed_chars = build_allowed_chars(cfg.shortener.alphabet.as_deref());
-
-        let blooms: crate::shortcode::bloom_filter::BloomState = build_bloom_state(&url_db).await?;
-        let jwt = JwtKeys::new(cfg.application.api_key.as_bytes());
+        Self::build_with_generator(cfg, code_gen).await
+    }
 
-        let (auth_svc, user_svc) = build_services(&cfg, &jwt).await?;
+    /// Like [`Application::build`], but creates codes with `code_gen` instead
+    /// of the engine configured under `shortener`.
+    ///
+    /// The generator is used as given: `shortener.check_digit` and
+    /// `shortener.code_case` are not applied to it. Codes with characters outside
+    /// `shortener.alphabet` only resolve if
+    /// [`ShortCodeGenerator::validate`] accepts them.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use std::sync::Arc;
+    /// use url_shortener_ztm_lib::configuration::get_configuration;
+    /// use url_shortener_ztm_lib::generator::NanoIdEngine;
+    /// use url_shortener_ztm_lib::startup::Application;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let config = get_configuration()?;
+    /// let app = Application::build_with_generator(config, Arc::new(NanoIdEngine::new(10, None))).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn build_with_generator(
+        cfg: Settings,
+        code_gen: Arc<dyn ShortCodeGenerator>,
+    ) -> Result<Self, anyhow::Error> {
+        let state = AppState::from_settings_with_generator(&cfg, code_gen).await?;
 
-        // Set up the TCP listener and application state
+        // Set up the TCP listener
         let address = format!("{}:{}", cfg.application.host, cfg.application.port);
         let listener = TcpListener::bind(address)
             .await
             .context("Unable to obtain a TCP listener...")?;
         let port = listener.local_addr()?.port();
 
-        let state = AppState {
-            // db_pool: Arc::new(db_pool),
-            code_generator: code_gen,
-            blooms,
-            allowed_chars,
-            api_key: cfg.application.api_key,
-            template_dir: cfg.application.templates.clone(),
-            config: cfg.clone(),
-            auth_service: auth_svc,
-            user_service: user_svc,
-            jwt,
-            database: url_db,
-        };
-
         // Template initialization
         crate::templates::build_templates(state.clone()).expect("Failed to build templates");
 
@@ -292,6 +290,9 @@ impl Application {
                 let mut ticker = tokio::time::interval(Duration::minutes(5).to_std().unwrap());
                 loop {
                     ticker.tick().await;
+                    if !blooms.is_ready() {
+                        continue;
+                    }
                     let snapshot = match blooms.s2l.snapshot() {
                         Ok(bytes) => bytes,
                         Err(err) => {
@@ -388,7 +389,7 @@ impl Application {
         .with_graceful_shutdown(async move {
             shutdown_signal().await;
 
-            if not_disable_bf_snapshots() {
+            if not_disable_bf_snapshots() && blooms.is_ready() {
                 match blooms.s2l.snapshot() {
                     Ok(bytes) => {
                         if let Err(err) =
@@ -414,6 +415,12 @@ impl Application {
         .await
         .context("Unable to start the app server...")?;
 
+        // In-flight requests are done; persist buffered writes before the pool goes away
+        if let Err(err) = self.state.database.flush().await {
+            tracing::warn!(%err, "failed to flush buffered writes on shutdown");
+        }
+        self.state.database.close().await;
+
         Ok(())
     }
 }
@@ -438,6 +445,7 @@ impl Application {
 /// 1. **Request ID** - Generates unique identifiers for each request
 /// 2. **Tracing** - Logs request/response information
 /// 3. **API Key Authentication** - For protected endpoints only
+/// 4. **Timeout** - Answers requests past `timeouts` with 504
 ///
 /// # Arguments
 ///
@@ -538,17 +546,40 @@ pub async fn build_router(state: AppState) -> Result<Router<AppState>, anyhow::E
     };
 
     // Build public routes (no authentication required)
-    let public_routes = Router::new()
+    let mut public_routes = Router::new()
         .route("/", get(get_index))
         .nest_service("/static", ServeDir::new("static"))
         .route("/api/docs/openapi.yaml", get(serve_openapi_spec))
         .route("/api/docs", get(serve_swagger_ui))
         .route("/{id}", get(get_redirect))
         .route("/api/health_check", get(health_check))
-        .route("/api/redirect/{id}", get(get_redirect));
+        .route("/api/redirect/{id}", get(get_redirect))
+        .route("/api/preview/{token}", get(get_preview));
+
+    // `/{id}/` captures the code without the slash, so both forms share a handler
+    if state.config.redirect.strip_trailing_slash {
+        public_routes = public_routes
+            .route("/{id}/", get(get_redirect))
+            .route("/api/redirect/{id}/", get(get_redirect));
+    }
+
+    // The same lookups again behind the gateway prefix; the handlers only
+    // ever see the captured code or token
+    if let Some(prefix) = state.config.redirect.route_prefix() {
+        public_routes = public_routes
+            .route(&format!("{prefix}/{{id}}"), get(get_redirect))
+            .route(&format!("{prefix}/api/redirect/{{id}}"), get(get_redirect))
+            .route(&format!("{prefix}/api/preview/{{token}}"), get(get_preview));
+        if state.config.redirect.strip_trailing_slash {
+            public_routes = public_routes.route(&format!("{prefix}/{{id}}/"), get(get_redirect));
+        }
+    }
 
     // Build public rate-limited shorten endpoint
-    let mut public_shorten = Router::new().route("/api/public/shorten", post(post_shorten));
+    let create_body_limit = DefaultBodyLimit::max(state.config.body_limits.create_bytes);
+    let mut public_shorten = Router::new()
+        .route("/api/public/shorten", post(post_shorten))
+        .layer(create_body_limit);
 
     if let Some(rate_layer) = rate_limit_layer.clone() {
         public_shorten = public_shorten.layer(rate_layer);
@@ -557,12 +588,25 @@ pub async fn build_router(state: AppState) -> Result<Router<AppState>, anyhow::E
     // Build protected API routes (requires API key)
     let mut protected_api = Router::new()
         .route("/api/shorten", post(post_shorten))
-        .route_layer(from_fn_with_state(state.clone(), check_api_key));
+        .route("/api/preview-tokens/{code}", post(post_preview_token))
+        .route("/api/maintenance/read-only", put(put_read_only))
+        .route("/api/pattern-aliases", post(post_pattern_alias))
+        .route("/api/admin/links/{code}", get(get_link_admin))
+        .route("/api/admin/self-test", post(post_self_test))
+        .route("/api/admin/storage", get(get_storage_stats))
+        .route_layer(from_fn_with_state(state.clone(), check_api_key))
+        .layer(create_body_limit);
 
     if let Some(rate_layer) = rate_limit_layer {
         protected_api = protected_api.layer(rate_layer);
     }
 
+    // Stats endpoint, optionally behind the API key
+    let mut stats_routes = Router::new().route("/api/stats", get(get_stats));
+    if state.config.stats.require_api_key {
+        stats_routes = stats_routes.route_layer(from_fn_with_state(state.clone(), check_api_key));
+    }
+
     // Build protected admin routes (requires API key)
     let protected_admin = Router::new()
         .route("/admin", get(get_admin_dashboard))
@@ -579,7 +623,9 @@ pub async fn build_router(state: AppState) -> Result<Router<AppState>, anyhow::E
         .merge(public_routes)
         .merge(public_shorten)
         .merge(protected_api)
+        .merge(stats_routes)
         .merge(protected_admin)
+        .fallback(get_pattern_redirect)
         .layer(
             ServiceBuilder::new()
                 .layer(SetRequestIdLayer::new(
@@ -597,9 +643,34 @@ pub async fn build_router(state: AppState) -> Result<Router<AppState>, anyhow::E
             .layer(from_fn(capture_client_meta));
     }
 
+    let timeouts = Arc::new(state.config.timeouts.clone());
+    router = router.layer(from_fn_with_state(timeouts, enforce_timeout));
+
     Ok(router)
 }
 
+/// Applies `migrations.pending` to the migrations a dry run reported.
+pub(crate) fn check_pending_migrations(
+    pending: Vec<PendingMigration>,
+    settings: &MigrationSettings,
+) -> Result<(), StartupError> {
+    if pending.is_empty() || settings.pending == PendingMigrationPolicy::Ignore {
+        return Ok(());
+    }
+    let versions = pending
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    match settings.pending {
+        PendingMigrationPolicy::Fail => Err(StartupError::PendingMigrations(versions)),
+        _ => {
+            tracing::warn!(pending = %versions, "database schema is behind; starting anyway");
+            Ok(())
+        }
+    }
+}
+
 pub fn build_allowed_chars(alphabet: Option<&str>) -> HashSet<char> {
     let mut set = HashSet::new();
     if let Some(alpha) = alphabet {
@@ -11,6 +11,8 @@
 //! - API key for authentication
 //! - Template directory path for web interface rendering
 //!
+//! [`AppState::from_settings`] wires all of it from the configuration.
+//!
 //! ## Thread Safety
 //!
 //! The state is designed to be safely shared across multiple threads and async tasks.
@@ -30,19 +32,54 @@
 //! }
 //! ```
 
-use crate::configuration::Settings;
+use crate::DatabaseType;
+use crate::configuration::{ChainAction, Settings};
 use crate::core::security::jwt::JwtKeys;
 
-use crate::database::UrlDatabase;
+use crate::database::postgres_sql::PostgresUrlDatabase;
+use crate::database::{
+    BufferedClickDatabase, DatabaseError, EventingUrlDatabase, LoggingEventSink, ReadOnlySwitch,
+    ReadOnlyUrlDatabase, SelfCheckReport, SqliteUrlDatabase, UrlDatabase,
+};
 use crate::features::{auth::AuthService, users::UserService};
 
-use crate::generator::ShortCodeGenerator;
-use crate::shortcode::bloom_filter::BloomState;
+use crate::generator::{ShortCodeGenerator, build_generator, capacity};
+use crate::infrastructure::dns::DnsCache;
+use crate::infrastructure::outbound::{self, OutboundError};
+use crate::routes::stats::StatsCache;
+use crate::shortcode::bloom_filter::{BloomState, build_bloom_state};
+use crate::startup::{build_allowed_chars, build_services, check_pending_migrations};
 use axum_macros::FromRef;
 use std::collections::HashSet;
 use std::sync::Arc;
 use uuid::Uuid;
 
+/// Why [`AppState::from_settings`] could not build the state.
+#[derive(Debug, thiserror::Error)]
+pub enum StartupError {
+    #[error("outbound.proxy: {0}")]
+    OutboundProxy(#[source] reqwest::Error),
+    #[error("invalid shortener config: {0}")]
+    Generator(String),
+    #[error("database: {0}")]
+    Database(#[from] DatabaseError),
+    #[error("migrations: {0}")]
+    Migration(#[source] DatabaseError),
+    /// The schema is behind and `migrations.pending` is `fail`; lists the versions.
+    #[error(
+        "database schema is behind: pending migrations {0}; \
+         apply them or set migrations.run = true"
+    )]
+    PendingMigrations(String),
+    /// A strict self-check found discrepancies.
+    #[error("{0}")]
+    SelfCheck(SelfCheckReport),
+    #[error("bloom filter: {0:#}")]
+    Bloom(#[source] anyhow::Error),
+    #[error("auth services: {0:#}")]
+    Services(#[source] anyhow::Error),
+}
+
 /// Application state shared across all request handlers.
 ///
 /// This struct contains all the dependencies and configuration needed by handlers
@@ -103,10 +140,221 @@ pub struct AppState {
     pub template_dir: String,
     pub jwt: JwtKeys,
     pub config: Settings,
+    /// Cached result of the last `GET /api/stats` aggregate
+    pub stats_cache: Arc<StatsCache>,
+    /// Runtime toggle for read-only maintenance mode
+    pub read_only: ReadOnlySwitch,
+    /// Shared host lookups for following chained shortener links
+    pub dns_cache: Arc<DnsCache>,
 
     // pub db_pool: Arc<db::DbPool>,
     pub auth_service: Arc<AuthService>,
     pub user_service: Arc<UserService>,
 }
 
-impl AppState {}
+impl AppState {
+    /// Builds the state [`Application::build`](crate::startup::Application::build)
+    /// serves, from `settings` alone.
+    ///
+    /// Connects to the configured database, applies or checks migrations per
+    /// `migrations`, wraps it in the configured layers (events, click
+    /// buffering, read-only switch), loads or rebuilds the Bloom filter and
+    /// builds the code generator from `shortener`. Background tasks the
+    /// settings ask for (click flushes, stats snapshots, code-space checks) are
+    /// spawned here, so this must run inside a Tokio runtime.
+    ///
+    /// # Errors
+    ///
+    /// Fails with a [`StartupError`] when the outbound proxy or shortener
+    /// config is invalid, the database cannot be reached or migrated, pending
+    /// migrations are refused, a strict self-check finds discrepancies or a
+    /// required Bloom snapshot is missing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use url_shortener_ztm_lib::configuration::get_configuration;
+    /// use url_shortener_ztm_lib::state::AppState;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let config = get_configuration()?;
+    /// let state = AppState::from_settings(&config).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn from_settings(cfg: &Settings) -> Result<Self, StartupError> {
+        // build_generator panics on a bad config, so report it here instead
+        cfg.shortener.validate().map_err(StartupError::Generator)?;
+        Self::from_settings_with_generator(cfg, build_generator(&cfg.shortener)).await
+    }
+
+    /// Like [`AppState::from_settings`], but creates codes with `code_gen`; see
+    /// [`Application::build_with_generator`](crate::startup::Application::build_with_generator).
+    pub async fn from_settings_with_generator(
+        cfg: &Settings,
+        code_gen: Arc<dyn ShortCodeGenerator>,
+    ) -> Result<Self, StartupError> {
+        // A mistyped proxy should stop startup, not every later fetch
+        match outbound::client_builder(&cfg.outbound) {
+            Err(OutboundError::Proxy(e)) => return Err(StartupError::OutboundProxy(e)),
+            Err(OutboundError::Disabled)
+                if cfg.chained_shorteners.action == ChainAction::Follow =>
+            {
+                tracing::warn!(
+                    "chained_shorteners.action is follow but outbound requests are disabled; \
+                     such links will be refused"
+                );
+            }
+            _ => {}
+        }
+
+        let url_db: Arc<dyn UrlDatabase> = match cfg.database.r#type {
+            DatabaseType::Sqlite => {
+                let db = SqliteUrlDatabase::from_config(&cfg.database)
+                    .await?
+                    .with_snapshot_chunk_bytes(cfg.bloom.snapshot_chunk_bytes)
+                    .with_code_precedence(cfg.short_codes.precedence)
+                    .with_max_offset(cfg.pagination.max_offset)
+                    .with_max_page_size(cfg.pagination.max_page_size)
+                    .with_privacy(cfg.privacy.clone());
+                if cfg.migrations.run {
+                    db.migrate().await.map_err(StartupError::Migration)?;
+                } else {
+                    let pending = db
+                        .migrate_dry_run()
+                        .await
+                        .map_err(StartupError::Migration)?;
+                    check_pending_migrations(pending, &cfg.migrations)?;
+                }
+                Arc::new(db) as Arc<dyn UrlDatabase>
+            }
+            DatabaseType::Postgres => {
+                let db = PostgresUrlDatabase::from_config(&cfg.database)
+                    .await?
+                    .with_snapshot_chunk_bytes(cfg.bloom.snapshot_chunk_bytes)
+                    .with_code_precedence(cfg.short_codes.precedence)
+                    .with_max_offset(cfg.pagination.max_offset)
+                    .with_max_page_size(cfg.pagination.max_page_size)
+                    .with_privacy(cfg.privacy.clone());
+                if cfg.migrations.run {
+                    db.migrate().await.map_err(StartupError::Migration)?;
+                } else {
+                    let pending = db
+                        .migrate_dry_run()
+                        .await
+                        .map_err(StartupError::Migration)?;
+                    check_pending_migrations(pending, &cfg.migrations)?;
+                }
+                Arc::new(db) as Arc<dyn UrlDatabase>
+            }
+        };
+
+        if cfg.self_check.enabled {
+            let report = url_db.self_check().await?;
+            if report.is_ok() {
+                tracing::info!("{}", report);
+            } else if cfg.self_check.strict {
+                return Err(StartupError::SelfCheck(report));
+            } else {
+                for discrepancy in &report.discrepancies {
+                    tracing::warn!(%discrepancy, "schema self-check discrepancy");
+                }
+            }
+        }
+
+        let url_db: Arc<dyn UrlDatabase> = if cfg.events.log {
+            Arc::new(EventingUrlDatabase::new(url_db, LoggingEventSink))
+        } else {
+            url_db
+        };
+
+        let url_db: Arc<dyn UrlDatabase> = if cfg.stats.buffer_clicks {
+            let buffered: Arc<dyn UrlDatabase> = Arc::new(BufferedClickDatabase::new(url_db));
+            let flush_db = buffered.clone();
+            let every = std::time::Duration::from_secs(cfg.stats.click_flush_interval_secs.max(1));
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(every);
+                loop {
+                    ticker.tick().await;
+                    if let Err(err) = flush_db.flush().await {
+                        tracing::warn!(error = %err, "failed to flush buffered click counts");
+                    }
+                }
+            });
+            buffered
+        } else {
+            url_db
+        };
+
+        // Outermost, so rejected writes never reach the click buffer
+        let read_only = ReadOnlySwitch::new(cfg.maintenance.read_only);
+        let url_db: Arc<dyn UrlDatabase> =
+            Arc::new(ReadOnlyUrlDatabase::new(url_db, read_only.clone()));
+
+        if cfg.stats.snapshot_interval_secs > 0 {
+            let snapshot_db = url_db.clone();
+            let every = std::time::Duration::from_secs(cfg.stats.snapshot_interval_secs);
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(every);
+                loop {
+                    ticker.tick().await;
+                    let saved = match snapshot_db.aggregate_stats().await {
+                        Ok(stats) => snapshot_db.save_stats_snapshot(&stats).await,
+                        Err(err) => Err(err),
+                    };
+                    if let Err(err) = saved {
+                        tracing::warn!(error = %err, "failed to save stats snapshot");
+                    }
+                }
+            });
+        }
+
+        if cfg.code_space.warn_ratio > 0.0 {
+            let capacity_db = url_db.clone();
+            let shortener = cfg.shortener.clone();
+            let settings = cfg.code_space.clone();
+            let every = std::time::Duration::from_secs(settings.check_interval_secs.max(1));
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(every);
+                loop {
+                    ticker.tick().await;
+                    if let Err(err) = capacity::check(&*capacity_db, &shortener, &settings).await {
+                        tracing::warn!(error = %err, "failed to check code-space usage");
+                    }
+                }
+            });
+        }
+
+        let allowed_chars = build_allowed_chars(cfg.shortener.alphabet.as_deref());
+
+        let blooms = build_bloom_state(&url_db, &cfg.bloom)
+            .await
+            .map_err(StartupError::Bloom)?;
+        let jwt = JwtKeys::new(cfg.application.api_key.as_bytes());
+
+        let (auth_svc, user_svc) = build_services(cfg, &jwt)
+            .await
+            .map_err(StartupError::Services)?;
+
+        Ok(Self {
+            code_generator: code_gen,
+            blooms,
+            allowed_chars,
+            api_key: cfg.application.api_key,
+            template_dir: cfg.application.templates.clone(),
+            config: cfg.clone(),
+            stats_cache: Arc::new(StatsCache::new(std::time::Duration::from_secs(
+                cfg.stats.cache_ttl_secs,
+            ))),
+            dns_cache: Arc::new(
+                DnsCache::new(std::time::Duration::from_secs(cfg.dns.cache_ttl_secs))
+                    .with_max_entries(cfg.dns.max_entries),
+            ),
+            read_only,
+            auth_service: auth_svc,
+            user_service: user_svc,
+            jwt,
+            database: url_db,
+        })
+    }
+}
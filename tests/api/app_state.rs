@@ -0,0 +1,56 @@
+// tests/api/app_state.rs
+
+// integration tests for building the application state from configuration
+
+// dependencies
+use url_shortener_ztm_lib::database::DatabaseError;
+use url_shortener_ztm_lib::get_configuration;
+use url_shortener_ztm_lib::state::{AppState, StartupError};
+
+fn in_memory_settings() -> url_shortener_ztm_lib::configuration::Settings {
+    let mut c = get_configuration().expect("Failed to read configuration");
+    c.database.url = "sqlite::memory:".to_string();
+    c
+}
+
+#[tokio::test]
+async fn from_settings_migrates_and_wires_a_working_database() {
+    let state = AppState::from_settings(&in_memory_settings())
+        .await
+        .expect("Failed to build application state");
+
+    assert!(matches!(
+        state.database.get_url("Nope123").await,
+        Err(DatabaseError::NotFound)
+    ));
+    let code = state.code_generator.generate().unwrap();
+    assert!(state.code_generator.validate(&code));
+}
+
+#[tokio::test]
+async fn from_settings_refuses_pending_migrations_when_not_running_them() {
+    let mut settings = in_memory_settings();
+    settings.migrations.run = false;
+
+    let err = AppState::from_settings(&settings)
+        .await
+        .err()
+        .expect("a fresh database has pending migrations");
+    assert!(
+        matches!(&err, StartupError::PendingMigrations(versions) if !versions.is_empty()),
+        "{err}"
+    );
+    assert!(err.to_string().contains("pending migrations"), "{err}");
+}
+
+#[tokio::test]
+async fn from_settings_reports_an_invalid_shortener_config() {
+    let mut settings = in_memory_settings();
+    settings.shortener.length = 2;
+
+    let err = AppState::from_settings(&settings)
+        .await
+        .err()
+        .expect("a two-character code length is invalid");
+    assert!(matches!(err, StartupError::Generator(_)), "{err}");
+}
@@ -4,16 +4,13 @@
 use axum::http::StatusCode;
 use reqwest::header::CONTENT_TYPE;
 use serde_json::Value;
-use std::collections::HashSet;
 use std::sync::{Arc, LazyLock};
-use url_shortener_ztm_lib::core::security::jwt::JwtKeys;
-use url_shortener_ztm_lib::database::{SqliteUrlDatabase, UrlDatabase};
-use url_shortener_ztm_lib::generator::{self, build_generator};
+use url_shortener_ztm_lib::configuration::Settings;
+use url_shortener_ztm_lib::core::destination::normalize_url;
+use url_shortener_ztm_lib::database::UrlDatabase;
+use url_shortener_ztm_lib::generator::{ShortCodeGenerator, build_generator};
 use url_shortener_ztm_lib::get_configuration;
-use url_shortener_ztm_lib::routes::shorten::normalize_url;
-use url_shortener_ztm_lib::shortcode::bloom_filter::build_bloom_state;
 use url_shortener_ztm_lib::startup::build_router;
-use url_shortener_ztm_lib::startup::build_services;
 use url_shortener_ztm_lib::state::AppState;
 use url_shortener_ztm_lib::telemetry::{get_subscriber, init_subscriber};
 use uuid::Uuid;
@@ -43,6 +40,19 @@ pub struct TestApp {
 
 // Spin up an instance of our application and returns its address (i.e. http://localhost:XXXX)
 pub async fn spawn_app() -> TestApp {
+    spawn_app_with(|_| {}).await
+}
+
+// Like `spawn_app`, but lets a test adjust the configuration first
+pub async fn spawn_app_with(configure: impl FnOnce(&mut Settings)) -> TestApp {
+    spawn_app_with_generator(configure, |c| build_generator(&c.shortener)).await
+}
+
+// Like `spawn_app_with`, but creates codes with a custom generator
+pub async fn spawn_app_with_generator(
+    configure: impl FnOnce(&mut Settings),
+    make_generator: impl FnOnce(&Settings) -> Arc<dyn ShortCodeGenerator>,
+) -> TestApp {
     // Ensure that the tracing is only initialized once
     LazyLock::force(&TRACING);
     unsafe { std::env::set_var("BLOOM_SNAPSHOTS", "1") };
@@ -55,49 +65,17 @@ pub async fn spawn_app() -> TestApp {
         // Use more lenient rate limiting for tests (higher rate, smaller burst)
         c.rate_limiting.requests_per_second = 100; // 100 req/sec for fast tests
         c.rate_limiting.burst_size = 2; // Smaller burst for predictable testing
+        configure(&mut c);
         c
     };
 
-    // Create database and run migrations
-    let sqlite_db = SqliteUrlDatabase::from_config(&configuration.database)
+    // Wire the state exactly as the server does
+    let code_generator = make_generator(&configuration);
+    let test_app_state = AppState::from_settings_with_generator(&configuration, code_generator)
         .await
-        .expect("Failed to create database");
-
-    sqlite_db.migrate().await.expect("Failed to run migrations");
-    let database: Arc<dyn UrlDatabase> = Arc::new(sqlite_db);
-    let code_generator = build_generator(&configuration.shortener);
-
-    let allowed_chars: HashSet<char> = {
-        let mut set: HashSet<char> = HashSet::new();
-        if let Some(alpha) = &configuration.shortener.alphabet {
-            set.extend(alpha.chars());
-        } else {
-            set.extend(generator::DEFAULT_ALPHABET);
-        }
-
-        set
-    };
-
-    // Store the API key for use in tests
+        .expect("Failed to build application state");
+    let database = test_app_state.database.clone();
     let api_key = configuration.application.api_key;
-    let blooms = build_bloom_state(&database).await.unwrap();
-    let jwt = JwtKeys::new(configuration.application.api_key.as_bytes());
-
-    let (auth_svc, user_svc) = build_services(&configuration, &jwt).await.unwrap();
-
-    let test_app_state = AppState {
-        // db_pool: Arc::new(db_pool),
-        code_generator,
-        blooms,
-        allowed_chars,
-        api_key: configuration.application.api_key,
-        template_dir: configuration.application.templates.clone(),
-        config: configuration.clone(),
-        auth_service: auth_svc,
-        user_service: user_svc,
-        jwt,
-        database: database.clone(),
-    };
 
     // Launch the application as a background task
     let test_app = build_router(test_app_state.clone())
@@ -277,6 +255,22 @@ impl TestApp {
     }
 }
 
+// A response field name as the API writes it: camelCase with the
+// `camel-case-json` feature, snake_case otherwise
+pub fn field(snake_case: &str) -> String {
+    if !cfg!(feature = "camel-case-json") {
+        return snake_case.to_string();
+    }
+    let mut parts = snake_case.split('_');
+    let mut name = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        let mut chars = part.chars();
+        name.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+        name.push_str(chars.as_str());
+    }
+    name
+}
+
 // Assertion helpers
 pub async fn assert_json_ok(response: reqwest::Response) -> Value {
     assert!(response.status().is_success());
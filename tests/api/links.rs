@@ -0,0 +1,119 @@
+// tests/api/links.rs
+// Integration tests for the admin link inspection endpoint
+
+use crate::helpers::{assert_json_ok, field, spawn_app_with};
+use axum::http::StatusCode;
+
+#[tokio::test]
+async fn admin_lookup_returns_blocked_links_with_their_status() {
+    // Arrange
+    let app = spawn_app_with(|c| c.rate_limiting.enabled = false).await;
+    let response = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/blocked")
+        .await;
+    let body = assert_json_ok(response).await;
+    let id = body["data"]["id"].as_str().unwrap().to_string();
+    app._database
+        .block_link(&id, "takedown #42")
+        .await
+        .expect("Failed to block link");
+
+    // Act
+    let response = app
+        .client
+        .get(app.api(&format!("/api/admin/links/{}", id)))
+        .header("x-api-key", app.api_key.to_string())
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+
+    // Assert
+    let body = assert_json_ok(response).await;
+    let data = &body["data"];
+    assert_eq!(data["code"].as_str(), Some(id.as_str()));
+    assert_eq!(
+        data["url"].as_str(),
+        Some("https://www.example.com/blocked")
+    );
+    assert_eq!(data[field("blocked_reason")].as_str(), Some("takedown #42"));
+    assert!(data[field("expires_at")].is_null());
+}
+
+#[tokio::test]
+async fn admin_lookup_requires_api_key_and_known_code() {
+    // Arrange
+    let app = spawn_app_with(|c| c.rate_limiting.enabled = false).await;
+
+    // Act
+    let anonymous = app.get_api("/api/admin/links/Missing1").await;
+    let unknown = app
+        .client
+        .get(app.api("/api/admin/links/Missing1"))
+        .header("x-api-key", app.api_key.to_string())
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+
+    // Assert
+    assert_eq!(anonymous.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn self_test_runs_every_step_and_cleans_up() {
+    // Arrange
+    let app = spawn_app_with(|c| c.rate_limiting.enabled = false).await;
+
+    // Act
+    let response = app
+        .client
+        .post(app.api("/api/admin/self-test"))
+        .header("x-api-key", app.api_key.to_string())
+        .send()
+        .await
+        .expect("Failed to execute POST request");
+
+    // Assert
+    let body = assert_json_ok(response).await;
+    let steps: Vec<_> = body["data"]["steps"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|step| (step["name"].as_str().unwrap(), step["passed"].as_bool()))
+        .collect();
+    assert_eq!(
+        steps,
+        [
+            ("insert", Some(true)),
+            ("read", Some(true)),
+            ("resolve", Some(true)),
+            ("delete", Some(true)),
+        ]
+    );
+    let code = body["data"]["code"].as_str().unwrap();
+    assert!(app._database.get_url(code).await.is_err());
+}
+
+#[tokio::test]
+async fn storage_stats_reports_row_counts_and_size() {
+    // Arrange
+    let app = spawn_app_with(|c| c.rate_limiting.enabled = false).await;
+    app._database
+        .insert_url("Store01", "https://storage.example/")
+        .await
+        .unwrap();
+
+    // Act
+    let response = app
+        .client
+        .get(app.api("/api/admin/storage"))
+        .header("x-api-key", app.api_key.to_string())
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+
+    // Assert
+    let body = assert_json_ok(response).await;
+    assert_eq!(body["data"]["tables"]["urls"], 1);
+    assert!(body["data"][field("database_bytes")].as_i64().unwrap() > 0);
+}
@@ -1,10 +1,14 @@
 // tests/api/main.rs
 
 mod alias_validation_consistency;
+mod app_state;
 mod error_handling;
 mod health_check;
 mod helpers;
+mod links;
+mod preview;
 mod rate_limiting;
 mod redirect;
 mod shorten;
 mod static_assets;
+mod stats;
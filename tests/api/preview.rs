@@ -0,0 +1,120 @@
+// tests/api/preview.rs
+
+// integration tests for signed preview tokens
+
+// dependencies
+use crate::helpers::{assert_json_ok, spawn_app, spawn_app_with};
+use axum::http::StatusCode;
+
+#[tokio::test]
+async fn preview_token_reveals_the_destination() {
+    // Arrange
+    let app = spawn_app().await;
+    let created = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/preview")
+        .await;
+    let body = assert_json_ok(created).await;
+    let code = body["data"]["id"].as_str().unwrap().to_string();
+
+    let issued = app
+        .client
+        .post(app.api(&format!("/api/preview-tokens/{}", code)))
+        .header("x-api-key", app.api_key.to_string())
+        .send()
+        .await
+        .expect("Failed to execute POST request");
+    let body = assert_json_ok(issued).await;
+    let token = body["data"]["token"].as_str().unwrap().to_string();
+    assert!(
+        body["data"]["preview_url"]
+            .as_str()
+            .unwrap()
+            .ends_with(&token)
+    );
+
+    // Act
+    let response = app.get_api(&format!("/api/preview/{}", token)).await;
+
+    // Assert
+    let body = assert_json_ok(response).await;
+    assert_eq!(body["data"]["code"].as_str(), Some(code.as_str()));
+    assert_eq!(
+        body["data"]["url"].as_str(),
+        Some("https://www.example.com/preview")
+    );
+}
+
+#[tokio::test]
+async fn preview_rejects_raw_codes_and_forged_tokens() {
+    // Arrange
+    let app = spawn_app().await;
+    let created = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/secret")
+        .await;
+    let body = assert_json_ok(created).await;
+    let code = body["data"]["id"].as_str().unwrap().to_string();
+
+    // Act
+    let raw = app.get_api(&format!("/api/preview/{}", code)).await;
+    let forged = app
+        .get_api(&format!("/api/preview/{}.4102444800.AAAA", code))
+        .await;
+
+    // Assert
+    assert_eq!(raw.status(), StatusCode::NOT_FOUND);
+    assert_eq!(forged.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn preview_token_requires_api_key() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .client
+        .post(app.api("/api/preview-tokens/whatever"))
+        .send()
+        .await
+        .expect("Failed to execute POST request");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn preview_refuses_blocked_links() {
+    // Arrange
+    let app = spawn_app_with(|c| c.rate_limiting.enabled = false).await;
+    let created = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/removed")
+        .await;
+    let body = assert_json_ok(created).await;
+    let code = body["data"]["id"].as_str().unwrap().to_string();
+    let issue = || {
+        app.client
+            .post(app.api(&format!("/api/preview-tokens/{}", code)))
+            .header("x-api-key", app.api_key.to_string())
+            .send()
+    };
+    let body = assert_json_ok(issue().await.expect("Failed to execute POST request")).await;
+    let token = body["data"]["token"].as_str().unwrap().to_string();
+
+    // Act
+    app._database
+        .block_link(&code, "Removed following a court order")
+        .await
+        .unwrap();
+    let previewed = app.get_api(&format!("/api/preview/{}", token)).await;
+    let reissued = issue().await.expect("Failed to execute POST request");
+
+    // Assert
+    assert_eq!(
+        previewed.status(),
+        StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS
+    );
+    let body: serde_json::Value = previewed.json().await.unwrap();
+    assert_eq!(body["message"], "Removed following a court order");
+    assert!(!body.to_string().contains("example.com/removed"));
+    assert_eq!(reissued.status(), StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS);
+}
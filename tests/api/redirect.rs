@@ -4,7 +4,7 @@
 // this endpoint should redirect the user to the shortened URL
 
 // dependencies
-use crate::helpers::{assert_json_ok, assert_redirect_to, spawn_app};
+use crate::helpers::{assert_json_ok, assert_redirect_to, spawn_app, spawn_app_with};
 use axum::http::StatusCode;
 
 #[tokio::test]
@@ -82,3 +82,810 @@ async fn redirect_rejects_invalid_length() {
         "Expected 404 for id with length +1"
     );
 }
+
+#[tokio::test]
+async fn redirect_merges_utm_parameters_for_opted_in_links() {
+    // Arrange - the destination already carries a utm_source, which must be kept
+    let app = spawn_app().await;
+    let response = app
+        .client
+        .post(app.api("/api/shorten?utm=true&utm_source=news&utm_campaign=spring"))
+        .header("x-api-key", app.api_key.to_string())
+        .body("https://www.example.com/page?utm_source=keep&x=1")
+        .send()
+        .await
+        .expect("Failed to execute POST request");
+    let body = assert_json_ok(response).await;
+    let id = body["data"]["id"].as_str().unwrap();
+
+    // Act
+    let response = app.get_api(&format!("/api/redirect/{}", id)).await;
+
+    // Assert
+    assert_redirect_to(
+        response,
+        "https://www.example.com/page?utm_source=keep&x=1&utm_campaign=spring",
+        StatusCode::PERMANENT_REDIRECT,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn shortening_an_existing_url_does_not_change_its_utm_parameters() {
+    // Arrange
+    let app = spawn_app_with(|c| c.rate_limiting.enabled = false).await;
+    let url = "https://www.example.com/shared-utm";
+    let body = assert_json_ok(app.post_api_with_key("/api/shorten", url).await).await;
+    let id = body["data"]["id"].as_str().unwrap().to_string();
+
+    // Act
+    let response = app
+        .post_api_with_key("/api/shorten?utm=true&utm_campaign=hijack", url)
+        .await;
+    assert_json_ok(response).await;
+    let response = app.get_api(&format!("/api/redirect/{}", id)).await;
+
+    // Assert
+    assert_redirect_to(response, url, StatusCode::PERMANENT_REDIRECT).await;
+}
+
+#[tokio::test]
+async fn redirect_leaves_destination_untouched_without_utm_opt_in() {
+    // Arrange
+    let app = spawn_app().await;
+    let response = app
+        .client
+        .post(app.api("/api/shorten?utm_campaign=ignored"))
+        .header("x-api-key", app.api_key.to_string())
+        .body("https://www.example.com/plain?x=1")
+        .send()
+        .await
+        .expect("Failed to execute POST request");
+    let body = assert_json_ok(response).await;
+    let id = body["data"]["id"].as_str().unwrap();
+
+    // Act
+    let response = app.get_api(&format!("/api/redirect/{}", id)).await;
+
+    // Assert
+    assert_redirect_to(
+        response,
+        "https://www.example.com/plain?x=1",
+        StatusCode::PERMANENT_REDIRECT,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn redirect_sends_no_cache_control_by_default() {
+    // Arrange
+    let app = spawn_app().await;
+    let response = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/cached")
+        .await;
+    let body = assert_json_ok(response).await;
+    let id = body["data"]["id"].as_str().unwrap();
+
+    // Act
+    let response = app.get_api(&format!("/api/redirect/{}", id)).await;
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+    assert!(response.headers().get("cache-control").is_none());
+}
+
+#[tokio::test]
+async fn redirect_returns_451_for_blocked_links_until_unblocked() {
+    // Arrange
+    let app = spawn_app().await;
+    let response = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/takedown")
+        .await;
+    let body = assert_json_ok(response).await;
+    let id = body["data"]["id"].as_str().unwrap().to_string();
+    app._database
+        .block_link(&id, "Removed following a court order")
+        .await
+        .unwrap();
+
+    // Act
+    let blocked = app.get_api(&format!("/api/redirect/{}", id)).await;
+
+    // Assert
+    assert_eq!(blocked.status(), StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS);
+    let body: serde_json::Value = blocked.json().await.unwrap();
+    assert_eq!(body["message"], "Removed following a court order");
+
+    app._database.unblock_link(&id).await.unwrap();
+    let response = app.get_api(&format!("/api/redirect/{}", id)).await;
+    assert_redirect_to(
+        response,
+        "https://www.example.com/takedown",
+        StatusCode::PERMANENT_REDIRECT,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn redirect_shows_interstitial_to_browsers_only() {
+    // Arrange
+    let app = spawn_app_with(|c| c.interstitial.enabled = true).await;
+    let response = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/ad-supported")
+        .await;
+    let body = assert_json_ok(response).await;
+    let id = body["data"]["id"].as_str().unwrap();
+
+    // Act
+    let browser = app
+        .client
+        .get(app.api(&format!("/api/redirect/{}", id)))
+        .header("accept", "text/html,application/xhtml+xml")
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+    let api = app.get_api(&format!("/api/redirect/{}", id)).await;
+
+    // Assert
+    assert_eq!(browser.status(), StatusCode::OK);
+    let html = browser.text().await.unwrap();
+    assert!(html.contains(r#"http-equiv="refresh""#));
+    // Tera escapes the slashes, which the browser decodes again
+    assert!(html.contains("https:&#x2F;&#x2F;www.example.com&#x2F;ad-supported"));
+    assert_redirect_to(
+        api,
+        "https://www.example.com/ad-supported",
+        StatusCode::PERMANENT_REDIRECT,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn redirect_skips_interstitial_for_opted_out_links() {
+    // Arrange
+    let app = spawn_app_with(|c| c.interstitial.enabled = true).await;
+    let response = app
+        .post_api_with_key(
+            "/api/shorten?skip_interstitial=true",
+            "https://www.example.com/direct",
+        )
+        .await;
+    let body = assert_json_ok(response).await;
+    let id = body["data"]["id"].as_str().unwrap();
+
+    // Act
+    let response = app
+        .client
+        .get(app.api(&format!("/api/redirect/{}", id)))
+        .header("accept", "text/html")
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+
+    // Assert
+    assert_redirect_to(
+        response,
+        "https://www.example.com/direct",
+        StatusCode::PERMANENT_REDIRECT,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn shortening_an_existing_url_keeps_its_interstitial() {
+    // Arrange
+    let app = spawn_app_with(|c| {
+        c.interstitial.enabled = true;
+        c.rate_limiting.enabled = false;
+    })
+    .await;
+    let url = "https://www.example.com/guarded";
+    let body = assert_json_ok(app.post_api_with_key("/api/shorten", url).await).await;
+    let id = body["data"]["id"].as_str().unwrap().to_string();
+
+    // Act
+    let response = app
+        .post_api_with_key("/api/shorten?skip_interstitial=true", url)
+        .await;
+    assert_json_ok(response).await;
+    let response = app
+        .client
+        .get(app.api(&format!("/api/redirect/{}", id)))
+        .header("accept", "text/html")
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        response
+            .text()
+            .await
+            .unwrap()
+            .contains(r#"http-equiv="refresh""#)
+    );
+}
+
+#[tokio::test]
+async fn redirect_resolves_codes_with_and_without_trailing_slash() {
+    // Arrange
+    let app = spawn_app_with(|c| c.redirect.strip_trailing_slash = true).await;
+    let response = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/slash")
+        .await;
+    let body = assert_json_ok(response).await;
+    let id = body["data"]["id"].as_str().unwrap();
+
+    for path in [
+        format!("/{}", id),
+        format!("/{}/", id),
+        format!("/api/redirect/{}", id),
+        format!("/api/redirect/{}/", id),
+    ] {
+        // Act
+        let response = app
+            .client
+            .get(app.url(&path))
+            .send()
+            .await
+            .expect("Failed to execute GET request");
+
+        // Assert
+        assert_redirect_to(
+            response,
+            "https://www.example.com/slash",
+            StatusCode::PERMANENT_REDIRECT,
+        )
+        .await;
+    }
+    let deeper = app
+        .client
+        .get(app.url(&format!("/{}/more", id)))
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+    assert_eq!(deeper.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn redirect_with_trailing_slash_is_not_found_by_default() {
+    // Arrange
+    let app = spawn_app().await;
+    let response = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/strict")
+        .await;
+    let body = assert_json_ok(response).await;
+    let id = body["data"]["id"].as_str().unwrap();
+
+    // Act
+    let response = app
+        .client
+        .get(app.url(&format!("/{}/", id)))
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn redirect_strips_configured_route_prefix() {
+    // Arrange
+    let app = spawn_app_with(|c| c.redirect.route_prefix = Some("/s".to_string())).await;
+    let response = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/prefixed")
+        .await;
+    let body = assert_json_ok(response).await;
+    let id = body["data"]["id"].as_str().unwrap();
+
+    for path in [
+        format!("/s/{}", id),
+        format!("/s/api/redirect/{}", id),
+        format!("/{}", id),
+    ] {
+        // Act
+        let response = app
+            .client
+            .get(app.url(&path))
+            .send()
+            .await
+            .expect("Failed to execute GET request");
+
+        // Assert
+        assert_redirect_to(
+            response,
+            "https://www.example.com/prefixed",
+            StatusCode::PERMANENT_REDIRECT,
+        )
+        .await;
+    }
+    let health = app
+        .client
+        .get(app.url("/api/health_check"))
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+    assert_eq!(health.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn prefixed_redirect_is_not_found_without_route_prefix() {
+    // Arrange
+    let app = spawn_app().await;
+    let response = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/unprefixed")
+        .await;
+    let body = assert_json_ok(response).await;
+    let id = body["data"]["id"].as_str().unwrap();
+
+    // Act
+    let response = app
+        .client
+        .get(app.url(&format!("/s/{}", id)))
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn switching_code_case_keeps_existing_mixed_case_codes_reachable() {
+    // Arrange
+    let app = spawn_app_with(|c| {
+        c.shortener.code_case = url_shortener_ztm_lib::generator::config::CodeCase::Lower
+    })
+    .await;
+    app._database
+        .insert_url("MixEd12", "https://www.example.com/before-switch")
+        .await
+        .unwrap();
+
+    // Act
+    let response = app
+        .client
+        .get(app.url("/MixEd12"))
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+
+    // Assert
+    assert_redirect_to(
+        response,
+        "https://www.example.com/before-switch",
+        StatusCode::PERMANENT_REDIRECT,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn lower_code_case_generates_lowercase_codes_and_ignores_lookup_case() {
+    // Arrange
+    let app = spawn_app_with(|c| {
+        c.shortener.code_case = url_shortener_ztm_lib::generator::config::CodeCase::Lower;
+        c.shortener.case_insensitive_lookup = true;
+    })
+    .await;
+    let response = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/lower")
+        .await;
+    let body = assert_json_ok(response).await;
+    let id = body["data"]["id"].as_str().unwrap().to_string();
+    assert!(!id.chars().any(|c| c.is_ascii_uppercase()), "{id}");
+
+    let aliased = app
+        .client
+        .post(app.api("/api/shorten?alias=MixedAlias"))
+        .header("x-api-key", app.api_key.to_string())
+        .body("https://www.example.com/alias")
+        .send()
+        .await
+        .expect("Failed to execute POST request");
+    let body = assert_json_ok(aliased).await;
+    assert_eq!(body["data"]["id"].as_str(), Some("mixedalias"));
+
+    for (path, destination) in [
+        (
+            format!("/{}", id.to_uppercase()),
+            "https://www.example.com/lower",
+        ),
+        (format!("/{}", id), "https://www.example.com/lower"),
+        ("/MIXEDALIAS".to_string(), "https://www.example.com/alias"),
+    ] {
+        // Act
+        let response = app
+            .client
+            .get(app.url(&path))
+            .send()
+            .await
+            .expect("Failed to execute GET request");
+
+        // Assert
+        assert_redirect_to(response, destination, StatusCode::PERMANENT_REDIRECT).await;
+    }
+}
+
+#[tokio::test]
+async fn read_only_mode_keeps_redirects_and_rejects_writes() {
+    // Arrange - three writes in a row would trip the default burst limit
+    let app = spawn_app_with(|c| c.rate_limiting.enabled = false).await;
+    let response = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/maintenance")
+        .await;
+    let body = assert_json_ok(response).await;
+    let id = body["data"]["id"].as_str().unwrap();
+
+    // Act
+    let toggle = app
+        .client
+        .put(app.api("/api/maintenance/read-only"))
+        .header("x-api-key", app.api_key.to_string())
+        .json(&serde_json::json!({ "enabled": true }))
+        .send()
+        .await
+        .expect("Failed to execute PUT request");
+    let create = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/new")
+        .await;
+    let redirect = app.get_api(&format!("/api/redirect/{}", id)).await;
+
+    // Assert
+    assert_eq!(toggle.status(), StatusCode::OK);
+    assert_eq!(create.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_redirect_to(
+        redirect,
+        "https://www.example.com/maintenance",
+        StatusCode::PERMANENT_REDIRECT,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn unmatched_paths_redirect_through_pattern_aliases() {
+    // Arrange
+    let app = spawn_app().await;
+    let created = app
+        .client
+        .post(app.api("/api/pattern-aliases"))
+        .header("x-api-key", app.api_key.to_string())
+        .json(&serde_json::json!({
+            "pattern": "go/*",
+            "target": "https://docs.example.com/{1}",
+        }))
+        .send()
+        .await
+        .expect("Failed to execute POST request");
+    assert_eq!(created.status(), StatusCode::CREATED);
+
+    // Act
+    let matched = app
+        .client
+        .get(app.url("/go/guides/setup"))
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+    let unmatched = app
+        .client
+        .get(app.url("/elsewhere/guides"))
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+
+    // Assert
+    assert_redirect_to(
+        matched,
+        "https://docs.example.com/guides/setup",
+        StatusCode::PERMANENT_REDIRECT,
+    )
+    .await;
+    assert_eq!(unmatched.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn pattern_captures_cannot_change_the_destination_host() {
+    // Arrange
+    let app = spawn_app().await;
+    app._database
+        .insert_pattern_alias("go/*", "https://example.com{1}")
+        .await
+        .unwrap();
+    app._database
+        .insert_pattern_alias("docs/*", "https://example.com/{1}")
+        .await
+        .unwrap();
+
+    // Act
+    let hijacked = app
+        .client
+        .get(app.url("/go/@evil.com"))
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+    let kept = app
+        .client
+        .get(app.url("/docs/@evil.com"))
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+
+    // Assert
+    assert_eq!(hijacked.status(), StatusCode::FORBIDDEN);
+    assert!(hijacked.headers().get("location").is_none());
+    assert_redirect_to(
+        kept,
+        "https://example.com/%40evil.com",
+        StatusCode::PERMANENT_REDIRECT,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn redirect_sends_connection_hints_for_the_destination_origin() {
+    // Arrange
+    let app = spawn_app_with(|c| {
+        c.redirect.preconnect = true;
+        c.redirect.dns_prefetch = true;
+    })
+    .await;
+    let response = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/fast/path")
+        .await;
+    let body = assert_json_ok(response).await;
+    let id = body["data"]["id"].as_str().unwrap();
+
+    // Act
+    let response = app.get_api(&format!("/api/redirect/{}", id)).await;
+
+    // Assert
+    assert_eq!(
+        response.headers()["link"],
+        "<https://www.example.com>; rel=preconnect, <https://www.example.com>; rel=dns-prefetch"
+    );
+}
+
+#[tokio::test]
+async fn redirect_answers_if_modified_since_when_enabled() {
+    // Arrange
+    let app = spawn_app_with(|c| {
+        c.redirect.conditional_requests = true;
+        c.rate_limiting.enabled = false;
+    })
+    .await;
+    let response = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/crawled")
+        .await;
+    let body = assert_json_ok(response).await;
+    let path = format!("/api/redirect/{}", body["data"]["id"].as_str().unwrap());
+    let conditional = |since: String| {
+        app.client
+            .get(app.api(&path))
+            .header("if-modified-since", since)
+            .send()
+    };
+
+    // Act
+    let first = app.get_api(&path).await;
+    let last_modified = first.headers()["last-modified"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let unchanged = conditional(last_modified.clone()).await.unwrap();
+    let stale = conditional("Mon, 01 Jan 2001 00:00:00 GMT".to_string())
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(first.status(), StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(unchanged.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(unchanged.headers()["last-modified"], last_modified.as_str());
+    assert_eq!(stale.status(), StatusCode::PERMANENT_REDIRECT);
+}
+
+#[tokio::test]
+async fn expiring_and_limited_links_redirect_temporarily() {
+    // Arrange
+    let app = spawn_app_with(|c| c.rate_limiting.enabled = false).await;
+    let mut ids = Vec::new();
+    for path in ["/api/shorten", "/api/shorten", "/api/shorten?max_clicks=5"] {
+        let url = format!("https://www.example.com/lifetime/{}", ids.len());
+        let body = assert_json_ok(app.post_api_with_key(path, &url).await).await;
+        ids.push(body["data"]["id"].as_str().unwrap().to_string());
+    }
+    let tomorrow = chrono::Utc::now() + chrono::Duration::days(1);
+    app._database
+        .set_expiry_batch(std::slice::from_ref(&ids[1]), Some(tomorrow))
+        .await
+        .unwrap();
+
+    // Act
+    let mut statuses = Vec::new();
+    for id in &ids {
+        statuses.push(app.get_api(&format!("/api/redirect/{id}")).await.status());
+    }
+
+    // Assert
+    assert_eq!(
+        statuses,
+        [
+            StatusCode::PERMANENT_REDIRECT,
+            StatusCode::TEMPORARY_REDIRECT,
+            StatusCode::TEMPORARY_REDIRECT,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn click_limited_redirects_are_never_cached() {
+    // Arrange
+    let app = spawn_app_with(|c| c.redirect_cache.max_age_secs = Some(3600)).await;
+    let response = app
+        .post_api_with_key(
+            "/api/shorten?max_clicks=5",
+            "https://www.example.com/limited",
+        )
+        .await;
+    let body = assert_json_ok(response).await;
+    let id = body["data"]["id"].as_str().unwrap();
+
+    // Act
+    let response = app.get_api(&format!("/api/redirect/{}", id)).await;
+
+    // Assert
+    assert_eq!(response.headers()["cache-control"], "no-store");
+}
+
+#[tokio::test]
+async fn click_quota_lets_exactly_one_concurrent_request_through() {
+    // Arrange
+    let app = spawn_app_with(|c| c.rate_limiting.enabled = false).await;
+    let response = app
+        .post_api_with_key(
+            "/api/shorten?max_clicks=1",
+            "https://www.example.com/first-come",
+        )
+        .await;
+    let body = assert_json_ok(response).await;
+    let url = app.api(&format!(
+        "/api/redirect/{}",
+        body["data"]["id"].as_str().unwrap()
+    ));
+
+    // Act
+    let requests: Vec<_> = (0..16)
+        .map(|_| {
+            let request = app.client.get(&url).send();
+            tokio::spawn(async move { request.await.expect("Failed to execute GET request") })
+        })
+        .collect();
+    let mut statuses = Vec::new();
+    for request in requests {
+        statuses.push(request.await.unwrap().status());
+    }
+
+    // Assert
+    let redirected = statuses
+        .iter()
+        .filter(|s| **s == StatusCode::TEMPORARY_REDIRECT)
+        .count();
+    let gone = statuses.iter().filter(|s| **s == StatusCode::GONE).count();
+    assert_eq!((redirected, gone), (1, 15), "{statuses:?}");
+}
+
+#[tokio::test]
+async fn stored_links_with_blocked_schemes_are_not_redirected() {
+    // Arrange: a row written around the API, swapped onto a known code
+    let app = spawn_app().await;
+    let body = assert_json_ok(
+        app.post_api_with_key("/api/shorten", "https://www.example.com/safe")
+            .await,
+    )
+    .await;
+    let id = body["data"]["id"].as_str().unwrap();
+    app._database
+        .insert_url("JsLink1", "javascript:alert(1)")
+        .await
+        .unwrap();
+    app._database.swap_codes(id, "JsLink1").await.unwrap();
+    app._database
+        .insert_pattern_alias("js/*", "javascript:alert('{1}')")
+        .await
+        .unwrap();
+
+    // Act
+    let redirect = app.get_api(&format!("/api/redirect/{}", id)).await;
+    let pattern = app
+        .client
+        .get(app.url("/js/x"))
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+
+    // Assert
+    assert_eq!(redirect.status(), StatusCode::FORBIDDEN);
+    assert_eq!(redirect.headers()["cache-control"], "no-store");
+    assert_eq!(pattern.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn redirects_record_click_events_when_enabled() {
+    // Arrange
+    let app = spawn_app_with(|c| c.redirect.click_events = true).await;
+    let body = assert_json_ok(
+        app.post_api_with_key("/api/shorten", "https://www.example.com/tracked")
+            .await,
+    )
+    .await;
+    let id = body["data"]["id"].as_str().unwrap();
+
+    // Act
+    let response = app
+        .client
+        .get(app.api(&format!("/api/redirect/{}", id)))
+        .header("referer", "https://news.example/")
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+
+    // Assert
+    assert_redirect_to(
+        response,
+        "https://www.example.com/tracked",
+        StatusCode::PERMANENT_REDIRECT,
+    )
+    .await;
+    let storage = app._database.storage_stats().await.unwrap();
+    assert_eq!(storage.tables["click_events"], 1);
+    assert_eq!(
+        app._database.get_url_admin(id).await.unwrap().click_count,
+        1
+    );
+}
+
+#[tokio::test]
+async fn redirect_checks_the_database_when_bloom_negatives_are_not_trusted() {
+    let app = spawn_app_with(|c| c.bloom.trust_negatives = false).await;
+    // Written behind the service's back, so the filter has never seen it
+    app._database
+        .insert_url("Miss123", "https://example.com/missed")
+        .await
+        .unwrap();
+
+    for _ in 0..2 {
+        let response = app.get_api("/api/redirect/Miss123").await;
+        assert_redirect_to(
+            response,
+            "https://example.com/missed",
+            StatusCode::PERMANENT_REDIRECT,
+        )
+        .await;
+    }
+}
+
+#[tokio::test]
+async fn mistyped_codes_are_rejected_before_any_database_lookup() {
+    // Arrange - even with bloom negatives distrusted, a failed check
+    // character needs no lookup
+    let app = spawn_app_with(|c| {
+        c.shortener.check_digit = true;
+        c.bloom.trust_negatives = false;
+    })
+    .await;
+    let body = assert_json_ok(
+        app.post_api_with_key("/api/shorten", "https://www.example.com/typo")
+            .await,
+    )
+    .await;
+    let code = body["data"]["id"].as_str().unwrap();
+    let (body, check) = code.split_at(code.len() - 1);
+    let typo = format!("{body}{}", if check == "a" { "b" } else { "a" });
+    app._database.close().await;
+
+    // Act
+    let response = app.get_api(&format!("/api/redirect/{typo}")).await;
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
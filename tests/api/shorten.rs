@@ -7,10 +7,17 @@
 // - Edge cases (exact limit, exceeding limit)
 // - URL normalization and slash validation
 
-use crate::helpers::{assert_json_ok, spawn_app};
+use crate::helpers::{
+    assert_json_ok, assert_redirect_to, spawn_app, spawn_app_with, spawn_app_with_generator,
+};
 use axum::http::StatusCode;
 use regex::Regex;
-use url_shortener_ztm_lib::routes::shorten::normalize_url;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use url_shortener_ztm_lib::configuration::{ChainAction, PrivacySettings};
+use url_shortener_ztm_lib::core::destination::normalize_url;
+use url_shortener_ztm_lib::generator::config::EngineKind;
+use url_shortener_ztm_lib::generator::{GeneratorError, ShortCodeGenerator};
 
 /// Test that the shorten endpoint successfully shortens a valid URL
 #[tokio::test]
@@ -159,12 +166,30 @@ async fn shorten_rejects_very_long_url() {
     );
 }
 
+/// Test that bodies above `body_limits.create_bytes` are refused with 413
+#[tokio::test]
+async fn shorten_rejects_body_exceeding_size_limit() {
+    // Arrange
+    let app = spawn_app().await;
+    let url = make_url_with_total_len(64 * 1024);
+
+    // Act
+    let response = app.post_api_with_key("/api/shorten", &url).await;
+
+    // Assert
+    assert_eq!(
+        response.status(),
+        StatusCode::PAYLOAD_TOO_LARGE,
+        "Expected 413 for oversized body"
+    );
+}
+
 /// Unit tests for the normalize_url function
 /// Tests the slash validation functionality specifically
 #[cfg(test)]
 mod normalize_url_tests {
     use super::*;
-    use url_shortener_ztm_lib::errors::ApiError;
+    use url_shortener_ztm_lib::core::destination::UrlError;
 
     /// Test that valid HTTP URLs with proper double slashes are accepted
     #[test]
@@ -244,8 +269,8 @@ mod normalize_url_tests {
 
             let error = result.unwrap_err();
             assert!(
-                matches!(error, ApiError::Unprocessable(_)),
-                "Expected ApiError::Unprocessable for URL: '{}'",
+                matches!(error, UrlError::SchemeSlashes),
+                "Expected UrlError::SchemeSlashes for URL: '{}'",
                 url
             );
         }
@@ -265,8 +290,8 @@ mod normalize_url_tests {
 
             let error = result.unwrap_err();
             assert!(
-                matches!(error, ApiError::Unprocessable(_)),
-                "Expected ApiError::Unprocessable for URL: '{}'",
+                matches!(error, UrlError::SchemeSlashes),
+                "Expected UrlError::SchemeSlashes for URL: '{}'",
                 url
             );
         }
@@ -291,8 +316,11 @@ mod normalize_url_tests {
 
             let error = result.unwrap_err();
             assert!(
-                matches!(error, ApiError::Unprocessable(_)),
-                "Expected ApiError::Unprocessable for URL: '{}'",
+                matches!(
+                    error,
+                    UrlError::UnsupportedScheme(_) | UrlError::Unsupported
+                ),
+                "Expected an unsupported URL error for URL: '{}'",
                 url
             );
         }
@@ -342,3 +370,356 @@ mod normalize_url_tests {
         assert_eq!(normalized, "http://localhost:8080/");
     }
 }
+
+/// `Accept: text/plain` returns only the short URL
+#[tokio::test]
+async fn shorten_endpoint_returns_plaintext_when_accepted() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .client
+        .post(app.api("/api/shorten"))
+        .header("x-api-key", app.api_key.to_string())
+        .header("accept", "text/plain")
+        .body("https://www.example.com/plain")
+        .send()
+        .await
+        .expect("Failed to execute POST request");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain")
+    );
+    let body = response.text().await.unwrap();
+    let prefix = format!("{}/", app.base_url.trim_end_matches('/'));
+    assert!(body.starts_with(&prefix), "unexpected body: {body}");
+    assert!(!body[prefix.len()..].is_empty());
+}
+
+/// A form-encoded `url` field is accepted and, without an explicit `Accept`,
+/// answered form-encoded as well
+#[tokio::test]
+async fn shorten_endpoint_mirrors_form_encoded_requests() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .client
+        .post(app.api("/api/shorten"))
+        .header("x-api-key", app.api_key.to_string())
+        .header("content-type", "application/x-www-form-urlencoded")
+        .body("url=https%3A%2F%2Fwww.example.com%2Fform%3Fa%3D1")
+        .send()
+        .await
+        .expect("Failed to execute POST request");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/x-www-form-urlencoded"
+    );
+    let body = response.text().await.unwrap();
+    let fields: std::collections::HashMap<String, String> =
+        url::form_urlencoded::parse(body.as_bytes())
+            .into_owned()
+            .collect();
+    assert_eq!(fields["original_url"], "https://www.example.com/form?a=1");
+    assert!(fields["shortened_url"].ends_with(&fields["id"]));
+}
+
+/// `q` weights decide between supported types; unsupported ones fall back to JSON
+#[tokio::test]
+async fn shorten_endpoint_honours_accept_weights() {
+    // Arrange
+    let app = spawn_app().await;
+    let post = |accept: &'static str, url: &'static str| {
+        app.client
+            .post(app.api("/api/shorten"))
+            .header("x-api-key", app.api_key.to_string())
+            .header("accept", accept)
+            .body(url)
+            .send()
+    };
+
+    // Act
+    let weighted = post(
+        "text/plain;q=0.5, application/json;q=0.9",
+        "https://www.example.com/weighted",
+    )
+    .await
+    .expect("Failed to execute POST request");
+    let unsupported = post("image/png", "https://www.example.com/unsupported")
+        .await
+        .expect("Failed to execute POST request");
+
+    // Assert
+    assert_json_ok(weighted).await;
+    assert_json_ok(unsupported).await;
+}
+
+// Hands out `gen-0001`, `gen-0002`, ...; `-` is outside the configured alphabet
+struct CountingGenerator(AtomicU64);
+
+impl ShortCodeGenerator for CountingGenerator {
+    fn generate(&self) -> Result<String, GeneratorError> {
+        let n = self.0.fetch_add(1, Ordering::Relaxed) + 1;
+        Ok(format!("gen-{n:04}"))
+    }
+
+    fn name(&self) -> &'static str {
+        "counting"
+    }
+
+    fn validate(&self, code: &str) -> bool {
+        code.strip_prefix("gen-")
+            .is_some_and(|n| n.chars().all(|c| c.is_ascii_digit()))
+    }
+}
+
+#[tokio::test]
+async fn shorten_uses_injected_code_generator() {
+    // Arrange
+    let app =
+        spawn_app_with_generator(|_| {}, |_| Arc::new(CountingGenerator(Default::default()))).await;
+
+    // Act
+    let response = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/custom")
+        .await;
+
+    // Assert
+    let body = assert_json_ok(response).await;
+    assert_eq!(body["data"]["id"].as_str(), Some("gen-0001"));
+    let redirect = app
+        .client
+        .get(app.url("/gen-0001"))
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+    assert_redirect_to(
+        redirect,
+        "https://www.example.com/custom",
+        StatusCode::PERMANENT_REDIRECT,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn words_engine_creates_memorable_codes_that_redirect() {
+    // Arrange
+    let app = spawn_app_with(|c| c.shortener.engine.kind = EngineKind::Words).await;
+
+    // Act
+    let response = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/memorable")
+        .await;
+
+    // Assert
+    let body = assert_json_ok(response).await;
+    let id = body["data"]["id"].as_str().unwrap();
+    let shape = Regex::new(r"^[a-z]+-[a-z]+-[0-9]{2}$").unwrap();
+    assert!(shape.is_match(id), "{id}");
+    let redirect = app
+        .client
+        .get(app.url(&format!("/{}", id)))
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+    assert_redirect_to(
+        redirect,
+        "https://www.example.com/memorable",
+        StatusCode::PERMANENT_REDIRECT,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn chained_shortener_links_are_rejected_or_flagged() {
+    // Arrange
+    let reject = spawn_app_with(|c| c.chained_shorteners.action = ChainAction::Reject).await;
+    let flag = spawn_app_with(|c| c.chained_shorteners.action = ChainAction::Flag).await;
+
+    // Act
+    let rejected = reject
+        .post_api_with_key("/api/shorten", "https://bit.ly/abc123")
+        .await;
+    let plain = reject
+        .post_api_with_key("/api/shorten", "https://www.example.com/?u=bit.ly")
+        .await;
+    let flagged = flag
+        .post_api_with_key("/api/shorten", "https://j.bit.ly/abc123")
+        .await;
+
+    // Assert
+    assert_eq!(rejected.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_json_ok(plain).await;
+    assert_json_ok(flagged).await;
+    let hosts = flag
+        ._database
+        .distinct_metadata_values("chained_shortener")
+        .await
+        .unwrap();
+    assert_eq!(hosts, ["j.bit.ly"]);
+}
+
+#[tokio::test]
+async fn chained_shortener_flag_is_not_added_to_an_existing_link() {
+    // Arrange - a link stored before flagging was switched on
+    let app = spawn_app_with(|c| c.chained_shorteners.action = ChainAction::Flag).await;
+    app._database
+        .insert_url("Chain01", "https://bit.ly/existing")
+        .await
+        .unwrap();
+
+    // Act
+    let response = app
+        .post_api_with_key("/api/shorten", "https://bit.ly/existing")
+        .await;
+
+    // Assert
+    assert_json_ok(response).await;
+    let hosts = app
+        ._database
+        .distinct_metadata_values("chained_shortener")
+        .await
+        .unwrap();
+    assert!(hosts.is_empty(), "{hosts:?}");
+}
+
+#[tokio::test]
+async fn following_a_chained_shortener_refuses_blocked_addresses() {
+    // Arrange
+    let app = spawn_app_with(|c| {
+        c.chained_shorteners.action = ChainAction::Follow;
+        c.chained_shorteners.domains = vec!["localhost".to_string()];
+    })
+    .await;
+
+    // Act
+    let response = app
+        .post_api_with_key("/api/shorten", "http://localhost/abc123")
+        .await;
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn tracking_params_are_stripped_before_storing_when_enabled() {
+    // Arrange
+    let app = spawn_app_with(|c| {
+        c.rate_limiting.enabled = false;
+        c.privacy.strip_tracking_params = true;
+    })
+    .await;
+    let defaults = PrivacySettings::default().tracking_params;
+    let mut ids = Vec::new();
+
+    // Act
+    for param in &defaults {
+        let name = param.replace('*', "source");
+        let response = app
+            .post_api_with_key(
+                "/api/shorten",
+                &format!("https://www.example.com/page?id=7&{name}=abc"),
+            )
+            .await;
+        let body = assert_json_ok(response).await;
+        assert_eq!(
+            body["data"]["original_url"].as_str(),
+            Some("https://www.example.com/page?id=7"),
+            "{name}"
+        );
+        ids.push(body["data"]["id"].as_str().unwrap().to_string());
+    }
+
+    // Assert
+    ids.dedup();
+    assert_eq!(ids.len(), 1, "trackers should collapse to one code");
+    let response = app
+        .client
+        .get(app.api(&format!("/api/redirect/{}", ids[0])))
+        .send()
+        .await
+        .expect("Failed to execute GET request");
+    assert_redirect_to(
+        response,
+        "https://www.example.com/page?id=7",
+        StatusCode::PERMANENT_REDIRECT,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn tracking_params_are_kept_by_default() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/page?gclid=abc")
+        .await;
+
+    // Assert
+    let body = assert_json_ok(response).await;
+    assert_eq!(
+        body["data"]["original_url"].as_str(),
+        Some("https://www.example.com/page?gclid=abc")
+    );
+}
+
+#[tokio::test]
+async fn dangerous_schemes_are_blocked_before_the_allowlist() {
+    // Arrange
+    let app = spawn_app_with(|c| {
+        c.rate_limiting.enabled = false;
+        c.url_schemes.blocked = vec!["FTP".to_string()];
+    })
+    .await;
+
+    for url in [
+        "javascript:alert(1)",
+        "JaVaScRiPt:alert(document.cookie)",
+        " \tjava\tscript:alert(1)",
+        "vbscript:msgbox(1)",
+        "data:text/html,<script>alert(1)</script>",
+        "ftp://files.example.com/",
+    ] {
+        // Act
+        let response = app.post_api_with_key("/api/shorten", url).await;
+
+        // Assert
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY, "{url}");
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert!(body.to_string().contains("Blocked scheme"), "{url}: {body}");
+    }
+}
+
+#[tokio::test]
+async fn click_quota_is_refused_for_an_already_shortened_url() {
+    // Arrange
+    let app = spawn_app_with(|c| c.rate_limiting.enabled = false).await;
+    let url = "https://www.example.com/shared";
+    let body = assert_json_ok(app.post_api_with_key("/api/shorten", url).await).await;
+    let code = body["data"]["id"].as_str().unwrap().to_string();
+
+    // Act
+    let response = app
+        .post_api_with_key("/api/shorten?max_clicks=1", url)
+        .await;
+
+    // Assert - the shared link keeps redirecting without a quota
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    for _ in 0..2 {
+        let response = app.get_api(&format!("/api/redirect/{code}")).await;
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+    }
+}
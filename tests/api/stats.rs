@@ -0,0 +1,80 @@
+// tests/api/stats.rs
+
+// integration tests for the /api/stats endpoint
+
+// dependencies
+use crate::helpers::{assert_json_ok, field, spawn_app};
+use serde_json::Value;
+
+#[tokio::test]
+async fn stats_reports_links_aliases_and_clicks() {
+    // Arrange
+    let app = spawn_app().await;
+
+    let first = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/one")
+        .await;
+    let body = assert_json_ok(first).await;
+    let code = body["data"]["id"].as_str().unwrap().to_string();
+
+    let aliased = app
+        .client
+        .post(app.api("/api/shorten?alias=statsAlias"))
+        .header("x-api-key", app.api_key.to_string())
+        .body("https://www.example.com/two")
+        .send()
+        .await
+        .expect("Failed to execute POST request");
+    assert_json_ok(aliased).await;
+
+    app.get_api(&format!("/api/redirect/{}", code)).await;
+    app.get_api("/api/redirect/statsAlias").await;
+
+    // Act
+    let response = app.get_api("/api/stats").await;
+
+    // Assert
+    let body = assert_json_ok(response).await;
+    let data = &body["data"];
+    assert_eq!(data[field("total_links")].as_i64(), Some(2));
+    assert_eq!(data[field("total_aliases")].as_i64(), Some(1));
+    assert_eq!(data[field("total_clicks")].as_i64(), Some(2));
+    assert_eq!(data[field("expired_links")].as_i64(), Some(0));
+}
+
+#[tokio::test]
+async fn stats_are_served_from_cache_within_ttl() {
+    // Arrange
+    let app = spawn_app().await;
+    let before: Value = assert_json_ok(app.get_api("/api/stats").await).await;
+
+    // Act - a new link created within the TTL is not reflected yet
+    let created = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/cached")
+        .await;
+    assert_json_ok(created).await;
+    let after: Value = assert_json_ok(app.get_api("/api/stats").await).await;
+
+    // Assert
+    assert_eq!(before["data"], after["data"]);
+}
+
+#[tokio::test]
+async fn fresh_stats_bypass_the_cache() {
+    // Arrange
+    let app = spawn_app().await;
+    let before: Value = assert_json_ok(app.get_api("/api/stats").await).await;
+
+    // Act
+    let created = app
+        .post_api_with_key("/api/shorten", "https://www.example.com/fresh")
+        .await;
+    assert_json_ok(created).await;
+    let fresh: Value = assert_json_ok(app.get_api("/api/stats?fresh=true").await).await;
+
+    // Assert
+    assert_eq!(
+        fresh["data"][field("total_links")].as_i64(),
+        before["data"][field("total_links")].as_i64().map(|n| n + 1)
+    );
+}